@@ -0,0 +1,63 @@
+/* C interface to the spn_attacks crate (src/ffi.rs), ABI version 1.
+ *
+ * Build the library with
+ *   cargo rustc --lib --release --features ffi --crate-type staticlib
+ * and link target/release/libspn_attacks.a (plus -lpthread -ldl -lm on Linux).
+ */
+#ifndef SPN_ATTACKS_H
+#define SPN_ATTACKS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SPN_ABI_VERSION 1
+
+#define SPN_OK 0
+/* A required pointer was null */
+#define SPN_ERR_NULL (-1)
+/* An argument was out of range, e.g. a nibble index above 3 */
+#define SPN_ERR_ARGUMENT (-2)
+
+/* Opaque cipher handle */
+typedef struct SpnCipher SpnCipher;
+
+/* Key candidates for one nibble, best first, with their scores */
+typedef struct SpnRanking {
+    uint8_t keys[16];
+    float scores[16];
+} SpnRanking;
+
+uint32_t spn_abi_version(void);
+
+/* 80-bit master key, 10 bytes big-endian; NULL if key is NULL */
+SpnCipher *spn_cipher_new(const uint8_t *key);
+/* Five explicit round keys; NULL if round_keys is NULL */
+SpnCipher *spn_cipher_from_round_keys(const uint16_t *round_keys);
+/* NULL is ignored */
+void spn_cipher_free(SpnCipher *cipher);
+/* Copy the five round keys to out */
+int32_t spn_cipher_round_keys(const SpnCipher *cipher, uint16_t *out);
+
+uint16_t spn_cipher_encrypt(const SpnCipher *cipher, uint16_t block);
+uint16_t spn_cipher_decrypt(const SpnCipher *cipher, uint16_t block);
+/* Process len blocks in place */
+int32_t spn_cipher_encrypt_blocks(const SpnCipher *cipher, uint16_t *blocks, size_t len);
+int32_t spn_cipher_decrypt_blocks(const SpnCipher *cipher, uint16_t *blocks, size_t len);
+
+/* Rank the candidates for last-round-key nibble nibble_idx by bias magnitude */
+int32_t spn_linear_rank(const uint16_t *plaintexts, const uint16_t *ciphertexts, size_t len,
+                        uint16_t alpha, uint16_t beta, uint32_t nibble_idx, SpnRanking *out);
+/* Rank by fraction of right pairs; pairs without input difference delta_p are skipped */
+int32_t spn_differential_rank(const uint16_t *plaintexts1, const uint16_t *plaintexts2,
+                              const uint16_t *ciphertexts1, const uint16_t *ciphertexts2, size_t len,
+                              uint16_t delta_p, uint16_t delta_u, uint32_t nibble_idx, SpnRanking *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
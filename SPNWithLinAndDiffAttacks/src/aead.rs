@@ -0,0 +1,149 @@
+// Authenticated Encryption (Encrypt-then-MAC)
+// -------------------------------------------
+//
+// The plaintext is encrypted in CTR mode and the tag is a CMAC over the
+// nonce, the associated data and the ciphertext, under an independent key.
+// `open` checks the tag before decrypting anything. With a 16-bit block the
+// tag is 16 bits too, so forging by guessing succeeds with probability 2^-16.
+
+use std::fmt;
+
+use crate::cipher::BlockCipher;
+use crate::cmac::Cmac;
+use crate::modes::Ctr;
+
+/// The tag did not match: the ciphertext, nonce or associated data were
+/// modified, or the wrong key was used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthenticationFailed;
+
+impl fmt::Display for AuthenticationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "authentication failed")
+    }
+}
+
+impl std::error::Error for AuthenticationFailed {}
+
+/// CTR encryption composed with a CMAC tag
+#[derive(Clone, Debug)]
+pub struct EncryptThenMac<C> {
+    cipher: C,
+    mac: Cmac<C>,
+}
+
+impl<C: BlockCipher + Clone> EncryptThenMac<C> {
+    /// `cipher` and `mac_cipher` must be keyed independently
+    pub fn new(cipher: C, mac_cipher: C) -> Self {
+        EncryptThenMac { cipher, mac: Cmac::new(mac_cipher) }
+    }
+
+    /// Tag length in bytes
+    pub fn tag_len(&self) -> usize {
+        C::BLOCK_SIZE
+    }
+
+    /// Encode nonce, associated data and ciphertext unambiguously for the MAC
+    fn mac_input(nonce: &[u8], associated_data: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+        let mut input = Vec::with_capacity(nonce.len() + 8 + associated_data.len() + ciphertext.len());
+        input.extend_from_slice(nonce);
+        input.extend_from_slice(&(associated_data.len() as u64).to_be_bytes());
+        input.extend_from_slice(associated_data);
+        input.extend_from_slice(ciphertext);
+        input
+    }
+
+    /// Encrypt `plaintext` and return `ciphertext || tag`
+    ///
+    /// The nonce is the initial CTR block and must never repeat under a key.
+    pub fn seal(&self, nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut sealed = plaintext.to_vec();
+        Ctr::new(self.cipher.clone(), nonce).apply_keystream(&mut sealed);
+        let tag = self.mac.mac(&Self::mac_input(nonce, associated_data, &sealed));
+        sealed.extend_from_slice(&tag);
+        sealed
+    }
+
+    /// Verify the tag and, only if it is valid, decrypt
+    pub fn open(&self, nonce: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AuthenticationFailed> {
+        if sealed.len() < self.tag_len() {
+            return Err(AuthenticationFailed);
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - self.tag_len());
+        if !self.mac.verify(&Self::mac_input(nonce, associated_data, ciphertext), tag) {
+            return Err(AuthenticationFailed);
+        }
+        let mut plaintext = ciphertext.to_vec();
+        Ctr::new(self.cipher.clone(), nonce).apply_keystream(&mut plaintext);
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::Spn;
+
+    const NONCE: [u8; 2] = [0x12, 0x34];
+    const AD: &[u8] = b"header";
+    const PLAINTEXT: &[u8] = b"attack at dawn";
+
+    fn aead() -> EncryptThenMac<Spn> {
+        EncryptThenMac::new(Spn::new(0x1234_5678_90AB_CDEF_1234), Spn::new(0x0FED_CBA0_9876_5432_1ABC))
+    }
+
+    #[test]
+    fn seal_then_open_round_trips() {
+        let aead = aead();
+        let sealed = aead.seal(&NONCE, AD, PLAINTEXT);
+        assert_eq!(sealed.len(), PLAINTEXT.len() + aead.tag_len());
+        assert_eq!(aead.open(&NONCE, AD, &sealed).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn flipped_ciphertext_bit_is_rejected() {
+        let aead = aead();
+        let mut sealed = aead.seal(&NONCE, AD, PLAINTEXT);
+        sealed[3] ^= 0x10;
+        assert_eq!(aead.open(&NONCE, AD, &sealed), Err(AuthenticationFailed));
+    }
+
+    #[test]
+    fn flipped_tag_bit_is_rejected() {
+        let aead = aead();
+        let mut sealed = aead.seal(&NONCE, AD, PLAINTEXT);
+        *sealed.last_mut().unwrap() ^= 0x01;
+        assert_eq!(aead.open(&NONCE, AD, &sealed), Err(AuthenticationFailed));
+    }
+
+    #[test]
+    fn changed_associated_data_is_rejected() {
+        let aead = aead();
+        let sealed = aead.seal(&NONCE, AD, PLAINTEXT);
+        assert_eq!(aead.open(&NONCE, b"headers", &sealed), Err(AuthenticationFailed));
+    }
+
+    #[test]
+    fn changed_nonce_is_rejected() {
+        let aead = aead();
+        let sealed = aead.seal(&NONCE, AD, PLAINTEXT);
+        assert_eq!(aead.open(&[0x12, 0x35], AD, &sealed), Err(AuthenticationFailed));
+    }
+
+    #[test]
+    fn input_shorter_than_tag_is_rejected() {
+        let aead = aead();
+        assert_eq!(aead.open(&NONCE, AD, &[0xAB]), Err(AuthenticationFailed));
+        assert_eq!(aead.open(&NONCE, AD, &[]), Err(AuthenticationFailed));
+    }
+
+    #[test]
+    fn empty_plaintext_is_authenticated() {
+        let aead = aead();
+        let mut sealed = aead.seal(&NONCE, AD, &[]);
+        assert_eq!(sealed.len(), aead.tag_len());
+        assert_eq!(aead.open(&NONCE, AD, &sealed).unwrap(), Vec::<u8>::new());
+        sealed[0] ^= 0x80;
+        assert_eq!(aead.open(&NONCE, AD, &sealed), Err(AuthenticationFailed));
+    }
+}
@@ -0,0 +1,121 @@
+// Benchmarks
+// ----------
+//
+// Times every cipher backend on the same blocks and every attack on the same
+// pairs, so backend choices and slowdowns can be compared between builds. The
+// report is written as JSON by hand to keep the crate free of dependencies.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::differential::differential_counts;
+use crate::linear::linear_counts;
+use crate::pairs::{chosen_plaintext_pairs, known_plaintext_pairs};
+use crate::spn::{Backend, Spn};
+
+/// Master key used for every measurement
+const BENCH_KEY: u128 = 0x1234_5678_90AB_CDEF_1234;
+
+/// One timed run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    /// What was timed, e.g. "encrypt/lut" or "attack/linear"
+    pub name: String,
+    /// What `items` counts: "blocks" or "pairs"
+    pub unit: &'static str,
+    pub items: usize,
+    pub elapsed: Duration,
+}
+
+impl Measurement {
+    pub fn per_second(&self) -> f64 {
+        self.items as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+/// All measurements of one `run`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub measurements: Vec<Measurement>,
+}
+
+impl Report {
+    /// The report as a JSON object with one entry per measurement
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .measurements
+            .iter()
+            .map(|m| {
+                format!(
+                    "    {{\"name\": \"{}\", \"unit\": \"{}\", \"items\": {}, \"seconds\": {:.6}, \"per_second\": {:.1}}}",
+                    m.name,
+                    m.unit,
+                    m.items,
+                    m.elapsed.as_secs_f64(),
+                    m.per_second()
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"parallel\": {},\n  \"simd\": {},\n  \"measurements\": [\n{}\n  ]\n}}",
+            cfg!(feature = "parallel"),
+            cfg!(feature = "simd"),
+            entries.join(",\n")
+        )
+    }
+}
+
+fn time<T>(work: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    black_box(work());
+    start.elapsed()
+}
+
+/// Encrypt `num_blocks` blocks with each backend and run each attack over
+/// `num_pairs` pairs
+pub fn run(num_blocks: usize, num_pairs: usize) -> Report {
+    let scalar = Spn::new(BENCH_KEY);
+    let lut = scalar.clone().with_backend(Backend::Lut);
+    let blocks: Vec<u16> = (0..num_blocks).map(|i| i as u16).collect();
+    let mut measurements = Vec::new();
+    let mut record = |name: &str, unit, items, elapsed| {
+        measurements.push(Measurement { name: name.to_string(), unit, items, elapsed });
+    };
+
+    let elapsed = time(|| blocks.iter().map(|&b| scalar.encrypt(b)).fold(0, |acc, c| acc ^ c));
+    record("encrypt/scalar", "blocks", num_blocks, elapsed);
+
+    let elapsed = time(|| blocks.iter().map(|&b| lut.encrypt(b)).fold(0, |acc, c| acc ^ c));
+    record("encrypt/lut", "blocks", num_blocks, elapsed);
+
+    let constant_time = scalar.clone().with_backend(Backend::ConstantTime);
+    let elapsed = time(|| blocks.iter().map(|&b| constant_time.encrypt(b)).fold(0, |acc, c| acc ^ c));
+    record("encrypt/constant-time", "blocks", num_blocks, elapsed);
+
+    // Whole batches only; a trailing partial batch is not timed
+    let batched = num_blocks / 64 * 64;
+    let elapsed = time(|| {
+        blocks[..batched].chunks_exact(64).fold(0, |acc, chunk| {
+            let batch = scalar.encrypt_batch64(chunk.try_into().unwrap());
+            acc ^ batch.iter().fold(0, |acc, c| acc ^ c)
+        })
+    });
+    record("encrypt/bitslice", "blocks", batched, elapsed);
+
+    #[cfg(feature = "simd")]
+    {
+        let mut vectorized = blocks.clone();
+        let elapsed = time(|| crate::simd::encrypt_blocks(&mut vectorized, scalar.round_keys()));
+        record("encrypt/simd", "blocks", num_blocks, elapsed);
+    }
+
+    let known = known_plaintext_pairs(&lut, num_pairs, 1);
+    let elapsed = time(|| linear_counts(&known, 0x0B00, 0x0400, 2));
+    record("attack/linear", "pairs", num_pairs, elapsed);
+
+    let chosen = chosen_plaintext_pairs(&lut, 0x0040, num_pairs, 1);
+    let elapsed = time(|| differential_counts(&chosen, 0x0040, 0x0060, 1));
+    record("attack/differential", "pairs", num_pairs, elapsed);
+
+    Report { measurements }
+}
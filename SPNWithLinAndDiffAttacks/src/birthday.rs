@@ -0,0 +1,117 @@
+// Birthday-Bound Attacks on Small-Block Modes (Sweet32)
+// -----------------------------------------------------
+//
+// With a 16-bit block, CBC ciphertext blocks start colliding after about
+// 2^8 blocks. A collision c_i = c_j means E^-1 was applied to equal inputs,
+// so p_i ^ c_{i-1} = p_j ^ c_{j-1} and the attacker learns p_i ^ p_j.
+// CTR never repeats a keystream block within one counter period, which leaks
+// the opposite way: every known-plaintext block rules out one value for a
+// secret block, and after a full period only the secret remains.
+
+use std::collections::HashMap;
+
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::Spn;
+
+/// Plaintext XOR learned from one CBC ciphertext collision
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLeak {
+    pub first: usize,
+    pub second: usize,
+    pub plaintext_xor: u16,
+}
+
+/// Outcome of encrypting a stream of random blocks under CBC
+#[derive(Clone, Debug)]
+pub struct CbcBirthdayReport {
+    pub blocks_encrypted: usize,
+    /// Index of the block that completed the first collision, if any
+    pub first_collision_at: Option<usize>,
+    pub leaks: Vec<CollisionLeak>,
+    /// How many leaked XORs match the real plaintexts (should be all of them)
+    pub correct_leaks: usize,
+}
+
+impl CbcBirthdayReport {
+    pub fn bytes_encrypted(&self) -> usize {
+        self.blocks_encrypted * 2
+    }
+}
+
+/// Encrypt `num_blocks` random plaintext blocks in CBC and harvest the
+/// plaintext XORs revealed by ciphertext collisions
+pub fn cbc_birthday_experiment(cipher: &Spn, iv: u16, num_blocks: usize, seed: u64) -> CbcBirthdayReport {
+    let mut rng = SplitMix64::new(seed);
+    let plaintexts: Vec<u16> = (0..num_blocks).map(|_| rng.next_u16()).collect();
+
+    let mut ciphertexts = Vec::with_capacity(num_blocks);
+    let mut chain = iv;
+    for &p in &plaintexts {
+        chain = cipher.encrypt(p ^ chain);
+        ciphertexts.push(chain);
+    }
+
+    // The attacker only sees the IV and ciphertexts from here on
+    let previous = |i: usize| if i == 0 { iv } else { ciphertexts[i - 1] };
+    let mut seen: HashMap<u16, usize> = HashMap::new();
+    let mut leaks = Vec::new();
+    for (j, &c) in ciphertexts.iter().enumerate() {
+        match seen.get(&c) {
+            Some(&i) => leaks.push(CollisionLeak {
+                first: i,
+                second: j,
+                plaintext_xor: previous(i) ^ previous(j),
+            }),
+            None => {
+                seen.insert(c, j);
+            }
+        }
+    }
+
+    let correct_leaks = leaks
+        .iter()
+        .filter(|leak| plaintexts[leak.first] ^ plaintexts[leak.second] == leak.plaintext_xor)
+        .count();
+    CbcBirthdayReport {
+        blocks_encrypted: num_blocks,
+        first_collision_at: leaks.first().map(|leak| leak.second),
+        leaks,
+        correct_leaks,
+    }
+}
+
+/// Outcome of recovering a secret CTR block from known-plaintext traffic
+#[derive(Clone, Debug)]
+pub struct CtrEliminationReport {
+    /// Known-plaintext blocks observed besides the secret one
+    pub known_blocks: usize,
+    /// Candidate values for the secret block that were never ruled out
+    pub candidates_left: usize,
+    /// The secret, once it is the only candidate left
+    pub recovered: Option<u16>,
+}
+
+/// Encrypt a secret block followed by `known_blocks` random known blocks in
+/// CTR mode and eliminate candidates: since keystream blocks never repeat,
+/// the secret cannot be `c_secret ^ p_i ^ c_i` for any known block `i`
+pub fn ctr_elimination_experiment(cipher: &Spn, nonce: u16, secret: u16, known_blocks: usize, seed: u64) -> CtrEliminationReport {
+    assert!(known_blocks < 1 << 16, "the counter would wrap and repeat keystream");
+    let mut rng = SplitMix64::new(seed);
+    let secret_ciphertext = secret ^ cipher.encrypt(nonce);
+
+    let mut possible = vec![true; 1 << 16];
+    for i in 1..=known_blocks {
+        let p = rng.next_u16();
+        let c = p ^ cipher.encrypt(nonce.wrapping_add(i as u16));
+        // k_i = p ^ c is a keystream block the secret was *not* encrypted with
+        possible[(secret_ciphertext ^ p ^ c) as usize] = false;
+    }
+
+    let candidates_left = possible.iter().filter(|&&p| p).count();
+    let recovered = if candidates_left == 1 {
+        possible.iter().position(|&p| p).map(|v| v as u16)
+    } else {
+        None
+    };
+    CtrEliminationReport { known_blocks, candidates_left, recovered }
+}
@@ -0,0 +1,207 @@
+// Bitsliced SPN
+// -------------
+//
+// 64 blocks are processed at once by storing bit i of every block in lane
+// word `slices[i]` (bit j of the word belongs to block j). The S-box becomes
+// its algebraic normal form evaluated with AND/XOR on whole words, the P-box
+// is just a reordering of the 16 words, and a key bit is XORed by inverting
+// the corresponding word through a mask, so nothing branches on the key.
+
+/// Transpose a 64x64 bit matrix in place: bit c of word r swaps with bit r
+/// of word c. Recursive block swapping does it in 6 passes of 32 swaps.
+fn transpose64(a: &mut [u64; 64]) {
+    let mut width = 32;
+    let mut mask: u64 = 0x0000_0000_FFFF_FFFF;
+    while width != 0 {
+        let mut k = 0;
+        while k < 64 {
+            let t = ((a[k] >> width) ^ a[k + width]) & mask;
+            a[k] ^= t << width;
+            a[k + width] ^= t;
+            k = (k + width + 1) & !width;
+        }
+        width >>= 1;
+        mask ^= mask << width;
+    }
+}
+
+/// Transpose 64 blocks into 16 bit-slices
+fn to_slices(blocks: &[u16; 64]) -> [u64; 16] {
+    let mut matrix = [0u64; 64];
+    for (row, &block) in matrix.iter_mut().zip(blocks) {
+        *row = block as u64;
+    }
+    transpose64(&mut matrix);
+    let mut slices = [0u64; 16];
+    slices.copy_from_slice(&matrix[..16]);
+    slices
+}
+
+/// Transpose 16 bit-slices back into 64 blocks
+fn from_slices(slices: &[u64; 16]) -> [u16; 64] {
+    let mut matrix = [0u64; 64];
+    matrix[..16].copy_from_slice(slices);
+    transpose64(&mut matrix);
+    let mut blocks = [0u16; 64];
+    for (block, &row) in blocks.iter_mut().zip(&matrix) {
+        *block = row as u16;
+    }
+    blocks
+}
+
+/// XOR a round key into every block: flip the slices of the set key bits,
+/// through an all-ones or all-zero mask rather than a branch on the key
+fn add_round_key(slices: &mut [u64; 16], round_key: u16) {
+    for (i, slice) in slices.iter_mut().enumerate() {
+        *slice ^= 0u64.wrapping_sub(((round_key >> i) & 1) as u64);
+    }
+}
+
+/// PRESENT S-box on four slices (x0 = least significant bit), from its ANF:
+/// y0 = x0 + x2 + x1x2 + x3
+/// y1 = x1 + x3 + x1x3 + x2x3 + x0x1x2 + x0x1x3 + x0x2x3
+/// y2 = 1 + x2 + x3 + x0x1 + x0x3 + x1x3 + x0x1x3 + x0x2x3
+/// y3 = 1 + x0 + x1 + x3 + x1x2 + x0x1x2 + x0x1x3 + x0x2x3
+pub fn sbox_slices(x0: u64, x1: u64, x2: u64, x3: u64) -> [u64; 4] {
+    let x01 = x0 & x1;
+    let x03 = x0 & x3;
+    let x12 = x1 & x2;
+    let x13 = x1 & x3;
+    let x23 = x2 & x3;
+    let x012 = x01 & x2;
+    let x013 = x01 & x3;
+    let x023 = x0 & x23;
+    [
+        x0 ^ x2 ^ x12 ^ x3,
+        x1 ^ x3 ^ x13 ^ x23 ^ x012 ^ x013 ^ x023,
+        !(x2 ^ x3 ^ x01 ^ x03 ^ x13 ^ x013 ^ x023),
+        !(x0 ^ x1 ^ x3 ^ x12 ^ x012 ^ x013 ^ x023),
+    ]
+}
+
+/// Inverse PRESENT S-box on four slices, from its ANF:
+/// y0 = 1 + x0 + x2 + x1x3
+/// y1 = x0 + x1 + x3 + x0x2 + x1x3 + x2x3 + x0x1x2 + x0x1x3 + x0x2x3
+/// y2 = 1 + x3 + x0x1 + x0x2 + x0x3 + x1x2 + x1x3 + x0x1x2 + x0x1x3 + x0x2x3
+/// y3 = x0 + x1 + x2 + x3 + x0x1 + x0x1x2 + x0x2x3
+pub fn sbox_inv_slices(x0: u64, x1: u64, x2: u64, x3: u64) -> [u64; 4] {
+    let x01 = x0 & x1;
+    let x02 = x0 & x2;
+    let x03 = x0 & x3;
+    let x12 = x1 & x2;
+    let x13 = x1 & x3;
+    let x23 = x2 & x3;
+    let x012 = x01 & x2;
+    let x013 = x01 & x3;
+    let x023 = x0 & x23;
+    [
+        !(x0 ^ x2 ^ x13),
+        x0 ^ x1 ^ x3 ^ x02 ^ x13 ^ x23 ^ x012 ^ x013 ^ x023,
+        !(x3 ^ x01 ^ x02 ^ x03 ^ x12 ^ x13 ^ x012 ^ x013 ^ x023),
+        x0 ^ x1 ^ x2 ^ x3 ^ x01 ^ x012 ^ x023,
+    ]
+}
+
+fn sbox_layer(slices: &mut [u64; 16]) {
+    for nibble in slices.chunks_exact_mut(4) {
+        let y = sbox_slices(nibble[0], nibble[1], nibble[2], nibble[3]);
+        nibble.copy_from_slice(&y);
+    }
+}
+
+fn sbox_inv_layer(slices: &mut [u64; 16]) {
+    for nibble in slices.chunks_exact_mut(4) {
+        let y = sbox_inv_slices(nibble[0], nibble[1], nibble[2], nibble[3]);
+        nibble.copy_from_slice(&y);
+    }
+}
+
+/// Bit i moves to (i % 4) * 4 + i / 4, so slice i moves the same way
+fn pbox(slices: &[u64; 16]) -> [u64; 16] {
+    let mut output = [0u64; 16];
+    for (i, &slice) in slices.iter().enumerate() {
+        output[(i % 4) * 4 + i / 4] = slice;
+    }
+    output
+}
+
+/// Encrypt 64 blocks at once; same result as calling `spn::encrypt` on each
+pub fn encrypt_batch64(plaintexts: &[u16; 64], round_keys: &[u16]) -> [u16; 64] {
+    let mut slices = to_slices(plaintexts);
+    add_round_key(&mut slices, round_keys[0]);
+    for &round_key in &round_keys[1..4] {
+        sbox_layer(&mut slices);
+        slices = pbox(&slices);
+        add_round_key(&mut slices, round_key);
+    }
+    sbox_layer(&mut slices);
+    add_round_key(&mut slices, round_keys[4]);
+    from_slices(&slices)
+}
+
+/// Decrypt 64 blocks at once; same result as calling `spn::decrypt` on each
+pub fn decrypt_batch64(ciphertexts: &[u16; 64], round_keys: &[u16]) -> [u16; 64] {
+    let mut slices = to_slices(ciphertexts);
+    add_round_key(&mut slices, round_keys[4]);
+    sbox_inv_layer(&mut slices);
+    for &round_key in round_keys[1..4].iter().rev() {
+        add_round_key(&mut slices, round_key);
+        slices = pbox(&slices); // P-box is its own inverse
+        sbox_inv_layer(&mut slices);
+    }
+    add_round_key(&mut slices, round_keys[0]);
+    from_slices(&slices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{RandomSource, SplitMix64};
+    use crate::spn::{self, Backend, Spn};
+    use crate::testkit::check_block_cipher;
+
+    #[test]
+    fn batches_match_scalar() {
+        let mut rng = SplitMix64::new(1);
+        for _ in 0..32 {
+            let round_keys: [u16; 5] = std::array::from_fn(|_| rng.next_u16());
+            let plaintexts: [u16; 64] = std::array::from_fn(|_| rng.next_u16());
+            let ciphertexts = encrypt_batch64(&plaintexts, &round_keys);
+            for (&p, &c) in plaintexts.iter().zip(&ciphertexts) {
+                assert_eq!(c, spn::encrypt(p, &round_keys));
+            }
+            assert_eq!(decrypt_batch64(&ciphertexts, &round_keys), plaintexts);
+        }
+    }
+
+    #[test]
+    fn sbox_slices_match_the_table() {
+        // Slice j of x carries bit j of every input nibble 0..16
+        let x: [u64; 4] = std::array::from_fn(|j| (0..16u64).fold(0, |acc, v| acc | ((v >> j) & 1) << v));
+        let y = sbox_slices(x[0], x[1], x[2], x[3]);
+        let back = sbox_inv_slices(y[0], y[1], y[2], y[3]);
+        for v in 0..16 {
+            let out = (0..4).fold(0, |acc, j| acc | (((y[j] >> v) & 1) as u8) << j);
+            assert_eq!(out, spn::SBOX[v]);
+            assert_eq!((0..4).fold(0, |acc, j| acc | ((back[j] >> v) & 1) << j), v as u64);
+        }
+    }
+
+    #[test]
+    fn batched_backends_match_scalar_with_a_tail() {
+        // 200 blocks: three whole batches and a tail of 8
+        let mut rng = SplitMix64::new(2);
+        let blocks: Vec<u16> = (0..200).map(|_| rng.next_u16()).collect();
+        let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+        let expected: Vec<u16> = blocks.iter().map(|&b| cipher.encrypt(b)).collect();
+        for backend in [Backend::Scalar, Backend::Lut, Backend::ConstantTime] {
+            let cipher = cipher.clone().with_backend(backend);
+            let mut batch = blocks.clone();
+            cipher.encrypt_blocks(&mut batch);
+            assert_eq!(batch, expected, "{:?}", backend);
+            cipher.decrypt_blocks(&mut batch);
+            assert_eq!(batch, blocks, "{:?}", backend);
+            check_block_cipher(&cipher, 20, &mut rng).unwrap_or_else(|e| panic!("{:?}: {}", backend, e));
+        }
+    }
+}
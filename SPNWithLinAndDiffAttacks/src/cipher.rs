@@ -0,0 +1,30 @@
+/// A keyed block cipher operating in place on byte blocks
+///
+/// Modes of operation and the constructions built on top of them only see
+/// this trait, so any cipher in the crate can be plugged into them.
+pub trait BlockCipher {
+    /// Block size in bytes
+    const BLOCK_SIZE: usize;
+
+    /// Encrypt exactly one block of `BLOCK_SIZE` bytes in place
+    fn encrypt_block(&self, block: &mut [u8]);
+
+    /// Decrypt exactly one block of `BLOCK_SIZE` bytes in place
+    fn decrypt_block(&self, block: &mut [u8]);
+
+    /// Encrypt consecutive blocks in place; `data` is a whole number of
+    /// blocks. Ciphers with a faster batch path override this.
+    fn encrypt_blocks(&self, data: &mut [u8]) {
+        for block in data.chunks_exact_mut(Self::BLOCK_SIZE) {
+            self.encrypt_block(block);
+        }
+    }
+
+    /// Decrypt consecutive blocks in place; `data` is a whole number of
+    /// blocks
+    fn decrypt_blocks(&self, data: &mut [u8]) {
+        for block in data.chunks_exact_mut(Self::BLOCK_SIZE) {
+            self.decrypt_block(block);
+        }
+    }
+}
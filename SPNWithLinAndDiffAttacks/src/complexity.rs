@@ -0,0 +1,244 @@
+// Attack Complexity Estimates
+// ---------------------------
+//
+// Planning numbers for an attack before running it: how many pairs the
+// trail calls for, how many oracle queries that is, the memory for pairs,
+// counters and tables, and the CPU time, from per-item timings measured by
+// `bench::run` on this machine. The data follows the usual rules of thumb,
+// c / bias^2 known plaintexts for a linear trail and c / p chosen pairs for
+// a differential one, with `DATA_FACTOR` as c. Each active nibble of the
+// mask (difference) entering the last S-box layer is attacked on its own, as
+// `linear_counts` and `differential_counts` do; the key bits left after that
+// are searched exhaustively, and the whole report is set against a plain
+// exhaustive search of the 80-bit master key.
+
+use crate::bench::{self, Report};
+use crate::results::{json_number, json_string, Trail, SCHEMA_VERSION};
+use crate::sbox::Sbox;
+use crate::spn::nibble;
+use crate::walkthrough::{differential_trail, linear_trail};
+
+/// c in N = c / bias^2 and N = c / p; 8 gives a high success rate
+pub const DATA_FACTOR: f64 = 8.0;
+
+/// Bits of the master key
+pub const KEY_BITS: u32 = 80;
+
+/// Distinct plaintexts of the 16-bit block
+pub const CODEBOOK: f64 = 65536.0;
+
+/// Seconds per item of the operations an attack is made of
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    /// One encryption with the fastest backend
+    pub encryption: f64,
+    /// One known pair through `linear_counts` for one nibble
+    pub linear_pair: f64,
+    /// One chosen pair through `differential_counts` for one nibble
+    pub differential_pair: f64,
+}
+
+impl Timing {
+    /// Per-item times from a benchmark report; `None` if it lacks an
+    /// encryption or attack measurement
+    pub fn from_report(report: &Report) -> Option<Self> {
+        let seconds_per_item = |name: &str| {
+            report.measurements.iter().find(|m| m.name == name).map(|m| 1.0 / m.per_second())
+        };
+        let encryption = report
+            .measurements
+            .iter()
+            .filter(|m| m.name.starts_with("encrypt/") && m.items > 0)
+            .map(|m| 1.0 / m.per_second())
+            .min_by(f64::total_cmp)?;
+        Some(Timing {
+            encryption,
+            linear_pair: seconds_per_item("attack/linear")?,
+            differential_pair: seconds_per_item("attack/differential")?,
+        })
+    }
+
+    /// Run a short benchmark and take the timings from it
+    pub fn measure() -> Self {
+        Self::from_report(&bench::run(1 << 20, 1 << 18)).expect("the benchmark times every operation")
+    }
+}
+
+/// Time, data and memory of one attack
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexityReport {
+    /// The configured trail with its bias or probability filled in
+    pub trail: Trail,
+    /// Last-round key nibbles attacked, one after the other
+    pub nibbles: Vec<usize>,
+    pub pairs: f64,
+    /// Encryptions requested from the oracle: one per known pair, two per
+    /// chosen pair
+    pub oracle_queries: f64,
+    /// More distinct plaintexts than the block has, so the attack cannot
+    /// get the data it needs
+    pub exceeds_codebook: bool,
+    pub pair_bytes: f64,
+    pub counter_bytes: usize,
+    /// Partial decryption table
+    pub table_bytes: usize,
+    pub data_seconds: f64,
+    pub counting_seconds: f64,
+    /// Key bits left for exhaustive search after the attack
+    pub remaining_key_bits: u32,
+    pub remaining_search_seconds: f64,
+    pub exhaustive_search_seconds: f64,
+}
+
+impl ComplexityReport {
+    pub fn memory_bytes(&self) -> f64 {
+        self.pair_bytes + (self.counter_bytes + self.table_bytes) as f64
+    }
+
+    pub fn total_seconds(&self) -> f64 {
+        self.data_seconds + self.counting_seconds + self.remaining_search_seconds
+    }
+
+    /// How many times faster than exhaustive search the attack is
+    pub fn speedup(&self) -> f64 {
+        self.exhaustive_search_seconds / self.total_seconds()
+    }
+
+    pub fn to_json(&self) -> String {
+        let nibbles: Vec<String> = self.nibbles.iter().map(usize::to_string).collect();
+        format!(
+            "{{\n  \"schema\": \"spn-attacks/complexity-report\",\n  \"version\": {},\n  \"trail\": {},\n  \
+             \"nibbles\": [{}],\n  \"data\": {{\"pairs\": {}, \"oracle_queries\": {}, \"query_type\": {}, \"exceeds_codebook\": {}}},\n  \
+             \"memory\": {{\"pairs\": {}, \"counters\": {}, \"tables\": {}, \"total\": {}}},\n  \
+             \"seconds\": {{\"data\": {}, \"counting\": {}, \"remaining_search\": {}, \"total\": {}}},\n  \
+             \"remaining_key_bits\": {},\n  \"exhaustive_search_seconds\": {},\n  \"speedup\": {}\n}}",
+            SCHEMA_VERSION,
+            self.trail.to_json(),
+            nibbles.join(", "),
+            json_number(self.pairs),
+            json_number(self.oracle_queries),
+            json_string(if matches!(self.trail, Trail::Linear { .. }) { "known" } else { "chosen" }),
+            self.exceeds_codebook,
+            json_number(self.pair_bytes),
+            self.counter_bytes,
+            self.table_bytes,
+            json_number(self.memory_bytes()),
+            json_number(self.data_seconds),
+            json_number(self.counting_seconds),
+            json_number(self.remaining_search_seconds),
+            json_number(self.total_seconds()),
+            self.remaining_key_bits,
+            json_number(self.exhaustive_search_seconds),
+            json_number(self.speedup())
+        )
+    }
+}
+
+/// Estimate the cost of attacking every active nibble at the end of `trail`
+/// with the PRESENT S-box. A missing bias or probability is taken from the
+/// best trail between the two masks (differences); `None` if there is none
+/// or the end mask (difference) is zero, leaving no nibble to attack.
+pub fn estimate(trail: Trail, timing: &Timing) -> Option<ComplexityReport> {
+    let sbox = Sbox::present();
+    let (trail, end, weight) = match trail {
+        Trail::Linear { alpha, beta, bias } => {
+            // The trail search gives the correlation, twice the bias
+            let bias = bias.or_else(|| Some(linear_trail(sbox, alpha, beta)?.weight / 2.0))?;
+            (Trail::Linear { alpha, beta, bias: Some(bias) }, beta, bias)
+        }
+        Trail::Differential { delta_p, delta_u, probability } => {
+            let probability = probability.or_else(|| Some(differential_trail(sbox, delta_p, delta_u)?.weight))?;
+            (Trail::Differential { delta_p, delta_u, probability: Some(probability) }, delta_u, probability)
+        }
+    };
+    if weight == 0.0 || end == 0 {
+        return None;
+    }
+    let nibbles: Vec<usize> = (0..4).filter(|&idx| nibble(end, idx) != 0).collect();
+    let linear = matches!(trail, Trail::Linear { .. });
+    let (pairs, queries_per_pair, bytes_per_pair, seconds_per_pair) = if linear {
+        (DATA_FACTOR / (weight * weight), 1.0, 4.0, timing.linear_pair)
+    } else {
+        (DATA_FACTOR / weight.abs(), 2.0, 8.0, timing.differential_pair)
+    };
+    let remaining_key_bits = KEY_BITS - 4 * nibbles.len() as u32;
+    Some(ComplexityReport {
+        trail,
+        pairs,
+        oracle_queries: queries_per_pair * pairs,
+        exceeds_codebook: pairs > if linear { CODEBOOK } else { CODEBOOK / 2.0 },
+        pair_bytes: bytes_per_pair * pairs,
+        counter_bytes: 16 * std::mem::size_of::<u64>() * nibbles.len(),
+        table_bytes: 16 * 16,
+        data_seconds: queries_per_pair * pairs * timing.encryption,
+        counting_seconds: pairs * seconds_per_pair * nibbles.len() as f64,
+        remaining_search_seconds: 2f64.powi(remaining_key_bits as i32) * timing.encryption,
+        exhaustive_search_seconds: 2f64.powi(KEY_BITS as i32) * timing.encryption,
+        remaining_key_bits,
+        nibbles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Measurement;
+    use std::time::Duration;
+
+    const TIMING: Timing = Timing { encryption: 1e-8, linear_pair: 1e-9, differential_pair: 2e-9 };
+
+    fn measurement(name: &str, items: usize, millis: u64) -> Measurement {
+        Measurement { name: name.to_string(), unit: "blocks", items, elapsed: Duration::from_millis(millis) }
+    }
+
+    #[test]
+    fn timing_takes_the_fastest_encryption() {
+        let report = Report {
+            measurements: vec![
+                measurement("encrypt/scalar", 1000, 4),
+                measurement("encrypt/lut", 1000, 1),
+                measurement("attack/linear", 500, 1),
+                measurement("attack/differential", 250, 1),
+            ],
+        };
+        let timing = Timing::from_report(&report).unwrap();
+        assert_eq!(timing.encryption, 1e-6);
+        assert_eq!(timing.linear_pair, 2e-6);
+        assert_eq!(timing.differential_pair, 4e-6);
+    }
+
+    #[test]
+    fn timing_needs_every_measurement() {
+        let report = Report { measurements: vec![measurement("encrypt/lut", 1000, 1), measurement("attack/linear", 500, 1)] };
+        assert_eq!(Timing::from_report(&report), None);
+    }
+
+    #[test]
+    fn estimates_the_textbook_linear_trail() {
+        let report = estimate(Trail::Linear { alpha: 0x0B00, beta: 0x0400, bias: None }, &TIMING).unwrap();
+        assert_eq!(report.trail, Trail::Linear { alpha: 0x0B00, beta: 0x0400, bias: Some(-1.0 / 128.0) });
+        assert_eq!(report.nibbles, [2]);
+        assert_eq!(report.pairs, DATA_FACTOR * 128.0 * 128.0);
+        assert_eq!(report.oracle_queries, report.pairs);
+        assert!(report.exceeds_codebook);
+        assert_eq!(report.remaining_key_bits, KEY_BITS - 4);
+    }
+
+    #[test]
+    fn estimates_a_given_probability() {
+        let trail = Trail::Differential { delta_p: 0x0040, delta_u: 0x0606, probability: Some(1.0 / 1024.0) };
+        let report = estimate(trail, &TIMING).unwrap();
+        assert_eq!(report.nibbles, [0, 2]);
+        assert_eq!(report.pairs, DATA_FACTOR * 1024.0);
+        assert_eq!(report.oracle_queries, 2.0 * report.pairs);
+        assert!(!report.exceeds_codebook);
+        assert_eq!(report.counting_seconds, report.pairs * TIMING.differential_pair * 2.0);
+    }
+
+    #[test]
+    fn no_estimate_without_a_trail_or_an_active_nibble() {
+        assert_eq!(estimate(Trail::Linear { alpha: 0x0000, beta: 0x0000, bias: None }, &TIMING), None);
+        assert_eq!(estimate(Trail::Linear { alpha: 0x0B00, beta: 0x0000, bias: Some(0.1) }, &TIMING), None);
+        assert_eq!(estimate(Trail::Linear { alpha: 0x0000, beta: 0x0400, bias: None }, &TIMING), None);
+    }
+}
@@ -0,0 +1,133 @@
+// Correlation and Differential Power Analysis
+// -------------------------------------------
+//
+// The first-round S-box output is SBOX[p ^ k0] nibble by nibble, so for each
+// guess of one whitening-key nibble the attacker can predict its leakage from
+// the plaintext alone. CPA correlates that prediction with the measured
+// sample across all traces; the right guess correlates best. DPA splits the
+// traces by one predicted bit and compares the mean sample of both halves.
+//
+// Both only look at the plaintext nibble, so the traces are first summarised
+// into 16 bins per plaintext nibble value and every candidate is scored from
+// the bins.
+
+use crate::leakage::{LeakageModel, LeakageOracle, LeakyEncryption, FIRST_SBOX_OUTPUT};
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::{nibble, Spn, SBOX};
+
+/// Per plaintext-nibble value: number of traces, sum of samples, sum of
+/// squared samples
+struct Bins {
+    count: [f64; 16],
+    sum: [f64; 16],
+    sum_sq: [f64; 16],
+}
+
+fn bin_samples(traces: &[LeakyEncryption], nibble_idx: usize, sample: usize) -> Bins {
+    let mut bins = Bins { count: [0.0; 16], sum: [0.0; 16], sum_sq: [0.0; 16] };
+    for trace in traces {
+        let p = nibble(trace.plaintext, nibble_idx) as usize;
+        let y = trace.samples[sample];
+        bins.count[p] += 1.0;
+        bins.sum[p] += y;
+        bins.sum_sq[p] += y * y;
+    }
+    bins
+}
+
+/// Predicted leakage of nibble `nibble_idx` of the first-round S-box output
+/// for plaintext nibble `p` under key guess `k`; the other nibbles only add
+/// noise
+fn hypothesis(model: &LeakageModel, nibble_idx: usize, p: usize, k: usize) -> f64 {
+    let x = (p ^ k) as u16;
+    let y = SBOX[x as usize] as u16;
+    let shift = 4 * nibble_idx;
+    model.leak_state(x << shift, y << shift)
+}
+
+/// Sort candidate scores, best (largest) first
+fn rank(scores: [f64; 16]) -> [(u8, f32); 16] {
+    let mut ranking: [(u8, f32); 16] = std::array::from_fn(|candidate| (candidate as u8, scores[candidate] as f32));
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranking
+}
+
+/// Pearson correlation between the predicted leakage and the first-round
+/// S-box output sample, for each candidate of whitening-key nibble
+/// `nibble_idx`
+pub fn cpa_correlations(traces: &[LeakyEncryption], model: LeakageModel, nibble_idx: usize) -> [f64; 16] {
+    let bins = bin_samples(traces, nibble_idx, FIRST_SBOX_OUTPUT);
+    let n: f64 = bins.count.iter().sum();
+    let sum_y: f64 = bins.sum.iter().sum();
+    let sum_y2: f64 = bins.sum_sq.iter().sum();
+    let var_y = n * sum_y2 - sum_y * sum_y;
+
+    std::array::from_fn(|k| {
+        let (mut sum_h, mut sum_h2, mut sum_hy) = (0.0, 0.0, 0.0);
+        for p in 0..16 {
+            let h = hypothesis(&model, nibble_idx, p, k);
+            sum_h += bins.count[p] * h;
+            sum_h2 += bins.count[p] * h * h;
+            sum_hy += bins.sum[p] * h;
+        }
+        let denominator = ((n * sum_h2 - sum_h * sum_h) * var_y).sqrt();
+        if denominator > 0.0 { (n * sum_hy - sum_h * sum_y) / denominator } else { 0.0 }
+    })
+}
+
+/// Candidates for whitening-key nibble `nibble_idx` by |correlation|, best
+/// first
+pub fn cpa_ranking(traces: &[LeakyEncryption], model: LeakageModel, nibble_idx: usize) -> [(u8, f32); 16] {
+    rank(cpa_correlations(traces, model, nibble_idx).map(f64::abs))
+}
+
+/// Recover whitening-key nibble `nibble_idx` by CPA
+pub fn cpa_attack(traces: &[LeakyEncryption], model: LeakageModel, nibble_idx: usize) -> u8 {
+    cpa_ranking(traces, model, nibble_idx)[0].0
+}
+
+/// Recover the whole whitening key (round key 0) nibble by nibble
+pub fn cpa_recover_whitening_key(traces: &[LeakyEncryption], model: LeakageModel) -> u16 {
+    (0..4).fold(0, |key, idx| key | (cpa_attack(traces, model, idx) as u16) << (4 * idx))
+}
+
+/// Difference of means: traces are split by bit `bit` of the predicted
+/// S-box output nibble, and candidates are ranked by |mean(1) - mean(0)|
+pub fn dpa_ranking(traces: &[LeakyEncryption], nibble_idx: usize, bit: u32) -> [(u8, f32); 16] {
+    let bins = bin_samples(traces, nibble_idx, FIRST_SBOX_OUTPUT);
+    rank(std::array::from_fn(|k| {
+        let (mut count, mut sum) = ([0.0; 2], [0.0; 2]);
+        for p in 0..16 {
+            let side = ((SBOX[p ^ k] >> bit) & 1) as usize;
+            count[side] += bins.count[p];
+            sum[side] += bins.sum[p];
+        }
+        if count[0] == 0.0 || count[1] == 0.0 {
+            return 0.0;
+        }
+        (sum[1] / count[1] - sum[0] / count[0]).abs()
+    }))
+}
+
+/// Fraction of `trials` independent CPA runs, each on `traces_per_trial`
+/// fresh random traces at signal-to-noise ratio `snr`, that recover the
+/// whole whitening key
+pub fn cpa_success_rate(cipher: &Spn, model: LeakageModel, snr: f64, traces_per_trial: usize, trials: usize, seed: u64) -> f64 {
+    let root = SplitMix64::new(seed);
+    let successes = (0..trials)
+        .filter(|&trial| {
+            let mut rng = root.split(trial as u64);
+            let mut device = LeakageOracle::with_snr(cipher.clone(), model, snr, rng.next_u64());
+            let traces = device.collect((0..traces_per_trial).map(|_| rng.next_u16()));
+            cpa_recover_whitening_key(&traces, model) == cipher.round_keys()[0]
+        })
+        .count();
+    successes as f64 / trials.max(1) as f64
+}
+
+/// `cpa_success_rate` at every SNR in `snrs`, as (snr, success rate)
+pub fn snr_sweep(cipher: &Spn, model: LeakageModel, snrs: &[f64], traces_per_trial: usize, trials: usize, seed: u64) -> Vec<(f64, f64)> {
+    snrs.iter()
+        .map(|&snr| (snr, cpa_success_rate(cipher, model, snr, traces_per_trial, trials, seed)))
+        .collect()
+}
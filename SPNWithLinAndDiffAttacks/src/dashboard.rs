@@ -0,0 +1,117 @@
+// Live Attack Dashboard
+// ---------------------
+//
+// A full-screen terminal view of attacks in progress: pairs processed, rate
+// and estimated time remaining, and for every attacked nibble the 16
+// candidate scores as bars with the current leader highlighted. It redraws in
+// place with ANSI escape sequences, which every terminal emulator (and the
+// Windows 10+ console) understands, so no terminal library is needed. Feed
+// incremental attacks between calls to `Dashboard::draw`.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Bar width for the largest score
+const BAR_WIDTH: usize = 16;
+
+/// Columns one nibble panel takes, gap included
+const PANEL_WIDTH: usize = 30;
+
+/// Current state of one nibble's attack
+#[derive(Clone, Debug, PartialEq)]
+pub struct NibbleView {
+    pub label: String,
+    /// All candidates, best first
+    pub ranking: [(u8, f32); 16],
+    /// The right nibble, highlighted when known
+    pub actual: Option<u8>,
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// One screenful: a status line, then the panels side by side, candidates
+/// listed in key order so the bars stay put while they grow
+pub fn frame(processed: u64, total: u64, elapsed: Duration, views: &[NibbleView]) -> String {
+    let rate = processed as f64 / elapsed.as_secs_f64().max(1e-9);
+    let eta = if processed == 0 { "--".to_string() } else { format_duration(Duration::from_secs_f64(total.saturating_sub(processed) as f64 / rate)) };
+    let mut screen = format!(
+        "\x1b[1mSPN attack dashboard\x1b[0m   {}/{} pairs ({:.0}%)   {:.0} pairs/s   elapsed {}   remaining {}\n\n",
+        processed,
+        total,
+        100.0 * processed as f64 / total.max(1) as f64,
+        rate,
+        format_duration(elapsed),
+        eta
+    );
+    for view in views {
+        let header = format!("{} leader {:X}", view.label, view.ranking[0].0);
+        screen.push_str(&format!("\x1b[1m{:<width$}\x1b[0m", header, width = PANEL_WIDTH));
+    }
+    screen.push('\n');
+    for candidate in 0..16u8 {
+        for view in views {
+            let top = view.ranking[0].1.max(f32::MIN_POSITIVE);
+            let score = view.ranking.iter().find(|&&(key, _)| key == candidate).map_or(0.0, |&(_, score)| score);
+            let bar = "█".repeat((BAR_WIDTH as f32 * score / top).round() as usize);
+            let line = format!("{:X} {:<width$} {:.5}", candidate, bar, score, width = BAR_WIDTH);
+            let padding = " ".repeat(PANEL_WIDTH.saturating_sub(line.chars().count()));
+            let (start, end) = match (candidate == view.ranking[0].0, view.actual == Some(candidate)) {
+                (_, true) => ("\x1b[32m", "\x1b[0m"),
+                (true, false) => ("\x1b[33m", "\x1b[0m"),
+                _ => ("", ""),
+            };
+            screen.push_str(&format!("{}{}{}{}", start, line, end, padding));
+        }
+        screen.push('\n');
+    }
+    screen.push_str("\nyellow: leader, green: right key\n");
+    screen
+}
+
+/// Redraws `frame` in place on a terminal
+pub struct Dashboard<W: Write> {
+    out: W,
+    total: u64,
+    started: Instant,
+}
+
+impl<W: Write> Dashboard<W> {
+    /// Clear the screen and hide the cursor; `total` is the number of pairs
+    /// the run will process
+    pub fn new(mut out: W, total: u64) -> io::Result<Self> {
+        out.write_all(b"\x1b[?25l\x1b[2J")?;
+        Ok(Dashboard { out, total, started: Instant::now() })
+    }
+
+    pub fn draw(&mut self, processed: u64, views: &[NibbleView]) -> io::Result<()> {
+        let screen = frame(processed, self.total, self.started.elapsed(), views);
+        // Home the cursor and clear to the end of each line instead of the
+        // whole screen, which flickers
+        self.out.write_all(b"\x1b[H")?;
+        self.out.write_all(screen.replace('\n', "\x1b[K\n").as_bytes())?;
+        self.out.flush()
+    }
+
+    /// Show the cursor again and hand back the output
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(b"\x1b[?25h")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_survives_overshooting_the_total() {
+        let view = NibbleView { label: "K5,1".to_string(), ranking: std::array::from_fn(|k| (k as u8, 0.0)), actual: None };
+        let screen = frame(1200, 1000, Duration::from_secs(3), &[view]);
+        assert!(screen.contains("1200/1000 pairs"));
+        assert!(screen.contains("remaining 0:00:00"));
+    }
+}
@@ -0,0 +1,344 @@
+// Streaming Binary Datasets
+// -------------------------
+//
+// One compact binary layout for all bulk data the crate produces (known- and
+// chosen-plaintext pairs and leakage traces), written and read as a stream,
+// so hundreds of millions of records go through pipes and compressors
+// without being held in memory or turned into text. A 32-byte header records
+// what produced the data:
+//
+//   0..8    magic "SPNDATA\0"
+//   8..10   format version (1)
+//   10      kind: 0 = known pairs, 1 = chosen pairs, 2 = traces
+//   11      reserved (0)
+//   12..14  delta_p (chosen pairs only)
+//   14..16  reserved (0)
+//   16..24  config hash: cipher (and leakage model) settings, see `ConfigHasher`
+//   24..32  generation seed
+//
+// Records follow back to back until the end of the stream, all
+// little-endian: (p, c) in 4 bytes, (p1, c1, c2) in 6 bytes with
+// p2 = p1 ^ delta_p, and (p, c, 12 samples as f32) in 52 bytes. Samples are
+// narrowed to f32, which keeps well over the precision any simulated noise
+// leaves. There is no record count, so a writer never seeks; `pair_file` is
+// the seekable, memory-mapped alternative for pairs. Writing or reading a
+// record of another kind than the header's fails with `InvalidInput`.
+
+use std::io::{self, Read, Write};
+
+use crate::leakage::{LeakageModel, LeakyEncryption, TRACE_LEN};
+use crate::spn::{pbox, SBOX};
+
+const MAGIC: &[u8; 8] = b"SPNDATA\0";
+const VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 32;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A record of another kind than the dataset holds was pushed or requested
+fn wrong_kind(records: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("this dataset does not hold {}", records))
+}
+
+/// 64-bit FNV-1a over the settings that determine a dataset, so a file can
+/// be matched against the configuration that is supposed to reproduce it
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigHasher(u64);
+
+impl Default for ConfigHasher {
+    fn default() -> Self {
+        ConfigHasher(0xCBF2_9CE4_8422_2325)
+    }
+}
+
+impl ConfigHasher {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+
+    /// The S-box, the P-box and the round keys
+    pub fn cipher(round_keys: &[u16]) -> Self {
+        let mut hasher = ConfigHasher::default();
+        hasher.write(&SBOX);
+        for bit in 0..16 {
+            hasher.write(&pbox(1 << bit).to_le_bytes());
+        }
+        for key in round_keys {
+            hasher.write(&key.to_le_bytes());
+        }
+        hasher
+    }
+
+    /// `cipher` plus the leakage model and noise level of a trace oracle
+    pub fn leakage(round_keys: &[u16], model: &LeakageModel, noise_sigma: f64) -> Self {
+        let mut hasher = Self::cipher(round_keys);
+        match model {
+            LeakageModel::HammingWeight => hasher.write(&[0]),
+            LeakageModel::HammingDistance => hasher.write(&[1]),
+            LeakageModel::Identity => hasher.write(&[2]),
+            LeakageModel::WeightedBits(weights) => {
+                hasher.write(&[3]);
+                for weight in weights {
+                    hasher.write(&weight.to_le_bytes());
+                }
+            }
+        }
+        hasher.write(&noise_sigma.to_le_bytes());
+        hasher
+    }
+}
+
+/// What a dataset holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatasetKind {
+    KnownPairs,
+    ChosenPairs { delta_p: u16 },
+    Traces,
+}
+
+impl DatasetKind {
+    /// Bytes per record
+    pub fn record_len(self) -> usize {
+        match self {
+            DatasetKind::KnownPairs => 4,
+            DatasetKind::ChosenPairs { .. } => 6,
+            DatasetKind::Traces => 4 + 4 * TRACE_LEN,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DatasetHeader {
+    pub kind: DatasetKind,
+    pub config_hash: u64,
+    pub seed: u64,
+}
+
+impl DatasetHeader {
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[0..8].copy_from_slice(MAGIC);
+        bytes[8..10].copy_from_slice(&VERSION.to_le_bytes());
+        let (kind, delta_p) = match self.kind {
+            DatasetKind::KnownPairs => (0, 0),
+            DatasetKind::ChosenPairs { delta_p } => (1, delta_p),
+            DatasetKind::Traces => (2, 0),
+        };
+        bytes[10] = kind;
+        bytes[12..14].copy_from_slice(&delta_p.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.config_hash.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.seed.to_le_bytes());
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8; HEADER_LEN]) -> io::Result<Self> {
+        if &bytes[0..8] != MAGIC {
+            return Err(invalid("not a dataset"));
+        }
+        if u16::from_le_bytes([bytes[8], bytes[9]]) != VERSION {
+            return Err(invalid("unsupported dataset version"));
+        }
+        let kind = match bytes[10] {
+            0 => DatasetKind::KnownPairs,
+            1 => DatasetKind::ChosenPairs { delta_p: u16::from_le_bytes([bytes[12], bytes[13]]) },
+            2 => DatasetKind::Traces,
+            _ => return Err(invalid("unknown dataset kind")),
+        };
+        let long = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        Ok(DatasetHeader { kind, config_hash: long(16), seed: long(24) })
+    }
+}
+
+/// Writes the header, then records as they are pushed; wrap files in a
+/// `BufWriter`
+pub struct DatasetWriter<W: Write> {
+    out: W,
+    header: DatasetHeader,
+    count: u64,
+}
+
+impl<W: Write> DatasetWriter<W> {
+    pub fn new(mut out: W, header: DatasetHeader) -> io::Result<Self> {
+        out.write_all(&header.to_bytes())?;
+        Ok(DatasetWriter { out, header, count: 0 })
+    }
+
+    pub fn push_known(&mut self, (plain, cipher): (u16, u16)) -> io::Result<()> {
+        if self.header.kind != DatasetKind::KnownPairs {
+            return Err(wrong_kind("known pairs"));
+        }
+        let mut record = [0u8; 4];
+        record[0..2].copy_from_slice(&plain.to_le_bytes());
+        record[2..4].copy_from_slice(&cipher.to_le_bytes());
+        self.push(&record)
+    }
+
+    /// Only p1 is stored; `p2` must be `p1 ^ delta_p`
+    pub fn push_chosen(&mut self, (p1, p2, c1, c2): (u16, u16, u16, u16)) -> io::Result<()> {
+        let DatasetKind::ChosenPairs { delta_p } = self.header.kind else {
+            return Err(wrong_kind("chosen pairs"));
+        };
+        if p1 ^ p2 != delta_p {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "pair does not have the dataset's input difference"));
+        }
+        let mut record = [0u8; 6];
+        for (i, word) in [p1, c1, c2].iter().enumerate() {
+            record[2 * i..2 * i + 2].copy_from_slice(&word.to_le_bytes());
+        }
+        self.push(&record)
+    }
+
+    pub fn push_trace(&mut self, trace: &LeakyEncryption) -> io::Result<()> {
+        if self.header.kind != DatasetKind::Traces {
+            return Err(wrong_kind("traces"));
+        }
+        let mut record = [0u8; 4 + 4 * TRACE_LEN];
+        record[0..2].copy_from_slice(&trace.plaintext.to_le_bytes());
+        record[2..4].copy_from_slice(&trace.ciphertext.to_le_bytes());
+        for (i, &sample) in trace.samples.iter().enumerate() {
+            record[4 + 4 * i..8 + 4 * i].copy_from_slice(&(sample as f32).to_le_bytes());
+        }
+        self.push(&record)
+    }
+
+    fn push(&mut self, record: &[u8]) -> io::Result<()> {
+        self.out.write_all(record)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Flush and hand back the stream with the number of records written
+    pub fn finish(mut self) -> io::Result<(W, u64)> {
+        self.out.flush()?;
+        Ok((self.out, self.count))
+    }
+}
+
+/// Reads the header up front, then records one at a time; wrap files in a
+/// `BufReader`
+pub struct DatasetReader<R: Read> {
+    input: R,
+    header: DatasetHeader,
+}
+
+impl<R: Read> DatasetReader<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut bytes = [0u8; HEADER_LEN];
+        input.read_exact(&mut bytes).map_err(|_| invalid("not a dataset"))?;
+        let header = DatasetHeader::parse(&bytes)?;
+        Ok(DatasetReader { input, header })
+    }
+
+    pub fn header(&self) -> &DatasetHeader {
+        &self.header
+    }
+
+    /// Fill `record`; `false` at a clean end of stream, an error if it ends
+    /// inside a record
+    fn read_record(&mut self, record: &mut [u8]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < record.len() {
+            match self.input.read(&mut record[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(invalid("dataset ends inside a record")),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn next_known(&mut self) -> io::Result<Option<(u16, u16)>> {
+        if self.header.kind != DatasetKind::KnownPairs {
+            return Err(wrong_kind("known pairs"));
+        }
+        let mut r = [0u8; 4];
+        Ok(self.read_record(&mut r)?.then(|| (u16::from_le_bytes([r[0], r[1]]), u16::from_le_bytes([r[2], r[3]]))))
+    }
+
+    /// The next chosen pair as (p1, p2, c1, c2)
+    pub fn next_chosen(&mut self) -> io::Result<Option<(u16, u16, u16, u16)>> {
+        let DatasetKind::ChosenPairs { delta_p } = self.header.kind else {
+            return Err(wrong_kind("chosen pairs"));
+        };
+        let mut r = [0u8; 6];
+        Ok(self.read_record(&mut r)?.then(|| {
+            let p1 = u16::from_le_bytes([r[0], r[1]]);
+            (p1, p1 ^ delta_p, u16::from_le_bytes([r[2], r[3]]), u16::from_le_bytes([r[4], r[5]]))
+        }))
+    }
+
+    pub fn next_trace(&mut self) -> io::Result<Option<LeakyEncryption>> {
+        if self.header.kind != DatasetKind::Traces {
+            return Err(wrong_kind("traces"));
+        }
+        let mut r = [0u8; 4 + 4 * TRACE_LEN];
+        Ok(self.read_record(&mut r)?.then(|| LeakyEncryption {
+            plaintext: u16::from_le_bytes([r[0], r[1]]),
+            ciphertext: u16::from_le_bytes([r[2], r[3]]),
+            samples: std::array::from_fn(|i| f32::from_le_bytes(r[4 + 4 * i..8 + 4 * i].try_into().unwrap()) as f64),
+        }))
+    }
+
+    /// Remaining known pairs as an iterator
+    pub fn known_pairs(mut self) -> impl Iterator<Item = io::Result<(u16, u16)>> {
+        std::iter::from_fn(move || self.next_known().transpose())
+    }
+
+    /// Remaining chosen pairs as an iterator
+    pub fn chosen_pairs(mut self) -> impl Iterator<Item = io::Result<(u16, u16, u16, u16)>> {
+        std::iter::from_fn(move || self.next_chosen().transpose())
+    }
+
+    /// Remaining traces as an iterator
+    pub fn traces(mut self) -> impl Iterator<Item = io::Result<LeakyEncryption>> {
+        std::iter::from_fn(move || self.next_trace().transpose())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(kind: DatasetKind) -> DatasetHeader {
+        DatasetHeader { kind, config_hash: 1, seed: 2 }
+    }
+
+    #[test]
+    fn chosen_pairs_round_trip() {
+        let mut writer = DatasetWriter::new(Vec::new(), header(DatasetKind::ChosenPairs { delta_p: 0x0040 })).unwrap();
+        writer.push_chosen((0x1234, 0x1274, 0xAAAA, 0xBBBB)).unwrap();
+        let (bytes, count) = writer.finish().unwrap();
+        assert_eq!(count, 1);
+        let pairs: Vec<_> = DatasetReader::new(&bytes[..]).unwrap().chosen_pairs().collect::<io::Result<_>>().unwrap();
+        assert_eq!(pairs, [(0x1234, 0x1274, 0xAAAA, 0xBBBB)]);
+    }
+
+    #[test]
+    fn records_of_another_kind_are_rejected() {
+        let mut writer = DatasetWriter::new(Vec::new(), header(DatasetKind::KnownPairs)).unwrap();
+        assert_eq!(writer.push_chosen((1, 2, 3, 4)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        writer.push_known((1, 2)).unwrap();
+        let (bytes, _) = writer.finish().unwrap();
+        let mut reader = DatasetReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.next_chosen().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.next_trace().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.next_known().unwrap(), Some((1, 2)));
+    }
+
+    #[test]
+    fn chosen_pairs_must_have_the_header_difference() {
+        let mut writer = DatasetWriter::new(Vec::new(), header(DatasetKind::ChosenPairs { delta_p: 0x0040 })).unwrap();
+        assert_eq!(writer.push_chosen((0x1234, 0x1235, 0, 0)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(writer.push_known((1, 2)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
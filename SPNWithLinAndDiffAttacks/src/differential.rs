@@ -0,0 +1,205 @@
+// Differential Attack Implementation
+// ---------------------------------
+
+use crate::columns::{self, ChosenPairs};
+use crate::sbox::Sbox;
+use crate::spn::{nibble, partial_decryption_table};
+
+/// Compute the probability of an S-box differential
+/// `delta_in`: input difference (4 bits), `delta_out`: output difference (4 bits)
+/// Returns: probability = count / 16, read from the shared DDT
+pub fn diff_prob_sbox(delta_in: u8, delta_out: u8) -> f32 {
+    Sbox::present().differential_probability(delta_in, delta_out)
+}
+
+/// Count, for each candidate key nibble, the pairs whose partial decryption
+/// shows the expected difference
+///
+/// With the `parallel` feature the pairs are split across threads, each
+/// with its own counters, and the counters are summed at the end.
+pub fn differential_counts(
+    pairs: &[(u16, u16, u16, u16)],
+    delta_p: u16,
+    delta_u: u16,
+    nibble_idx: usize,
+) -> [u64; 16] {
+    #[cfg(feature = "parallel")]
+    return crate::parallel::count_in_parallel(pairs, |chunk| {
+        differential_counts_from_iter(chunk.iter().copied(), delta_p, delta_u, nibble_idx)
+    });
+    #[cfg(not(feature = "parallel"))]
+    differential_counts_from_iter(pairs.iter().copied(), delta_p, delta_u, nibble_idx)
+}
+
+/// `differential_counts` over pairs stored column-wise
+pub fn differential_counts_columns(pairs: &ChosenPairs, delta_p: u16, delta_u: u16, nibble_idx: usize) -> [u64; 16] {
+    #[cfg(feature = "parallel")]
+    return crate::parallel::count_ranges_in_parallel(pairs.len(), |range| {
+        differential_counts_from_iter(pairs.iter_range(range), delta_p, delta_u, nibble_idx)
+    });
+    #[cfg(not(feature = "parallel"))]
+    differential_counts_from_iter(pairs.iter_range(0..pairs.len()), delta_p, delta_u, nibble_idx)
+}
+
+/// `differential_counts` over pairs from any source, e.g. a file being
+/// streamed
+pub fn differential_counts_from_iter(
+    pairs: impl Iterator<Item = (u16, u16, u16, u16)>,
+    delta_p: u16,
+    delta_u: u16,
+    nibble_idx: usize,
+) -> [u64; 16] {
+    // Extract target nibble from expected difference
+    let delta_u_nibble = nibble(delta_u, nibble_idx);
+    // Inverse S-box of (c ^ candidate), built once instead of per pair
+    let decryptions = partial_decryption_table();
+
+    // Each pair packs to its two target ciphertext nibbles
+    let pack = |(p1, p2, c1, c2): (u16, u16, u16, u16)| {
+        // Filter pairs with correct input difference
+        if p1 ^ p2 != delta_p {
+            return None;
+        }
+        let c1_nib = nibble(c1, nibble_idx);
+        let c2_nib = nibble(c2, nibble_idx);
+        Some((c1_nib << 4) | c2_nib)
+    };
+    columns::count_chunked(pairs, pack, |chunk, counts| {
+        for (candidate, decrypt) in decryptions.iter().enumerate() {
+            // Check output difference
+            let matches = chunk
+                .iter()
+                .filter(|&&packed| decrypt[(packed >> 4) as usize] ^ decrypt[(packed & 0xF) as usize] == delta_u_nibble)
+                .count();
+            counts[candidate] += matches as u32;
+        }
+    })
+}
+
+/// Perform a differential attack to recover part of the last round key
+/// `pairs`: vector of (plaintext1, plaintext2, ciphertext1, ciphertext2) tuples
+/// `delta_p`: input difference for plaintexts
+/// `delta_u`: expected difference before last S-box
+/// `nibble_idx`: target nibble index in the last round key
+/// Returns: candidate key nibble with the highest count
+pub fn differential_attack(
+    pairs: &[(u16, u16, u16, u16)],
+    delta_p: u16,
+    delta_u: u16,
+    nibble_idx: usize,
+) -> u8 {
+    let counts = differential_counts(pairs, delta_p, delta_u, nibble_idx);
+
+    // Find candidate with the highest count
+    counts
+        .iter()
+        .enumerate()
+        .max_by_key(|&(_, count)| count)
+        .map(|(candidate, _)| candidate as u8)
+        .unwrap()
+}
+
+/// All candidates for nibble `nibble_idx` with their fraction of right
+/// pairs among the pairs with input difference `delta_p`, best first
+pub fn differential_ranking(
+    pairs: &[(u16, u16, u16, u16)],
+    delta_p: u16,
+    delta_u: u16,
+    nibble_idx: usize,
+) -> [(u8, f32); 16] {
+    let counts = differential_counts(pairs, delta_p, delta_u, nibble_idx);
+    let total = pairs.iter().filter(|&&(p1, p2, _, _)| p1 ^ p2 == delta_p).count().max(1) as f32;
+    let mut ranking: [(u8, f32); 16] =
+        std::array::from_fn(|candidate| (candidate as u8, counts[candidate] as f32 / total));
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranking
+}
+
+/// Differential attack that keeps its counters between pairs, for oracles
+/// that hand out pairs one at a time
+///
+/// Scores are the fraction of right pairs counted for each candidate.
+#[derive(Clone, Debug)]
+pub struct IncrementalDifferentialAttack {
+    delta_p: u16,
+    delta_u_nibble: u8,
+    nibble_idx: usize,
+    decryptions: [[u8; 16]; 16],
+    counts: [u64; 16],
+    pairs_seen: u64,
+}
+
+impl IncrementalDifferentialAttack {
+    pub fn new(delta_p: u16, delta_u: u16, nibble_idx: usize) -> Self {
+        IncrementalDifferentialAttack {
+            delta_p,
+            delta_u_nibble: nibble(delta_u, nibble_idx),
+            nibble_idx,
+            decryptions: partial_decryption_table(),
+            counts: [0; 16],
+            pairs_seen: 0,
+        }
+    }
+
+    /// Update the counters with one (p1, p2, c1, c2) pair; pairs without
+    /// the input difference `delta_p` are ignored
+    pub fn feed(&mut self, (p1, p2, c1, c2): (u16, u16, u16, u16)) {
+        if p1 ^ p2 != self.delta_p {
+            return;
+        }
+        let c1_nib = nibble(c1, self.nibble_idx) as usize;
+        let c2_nib = nibble(c2, self.nibble_idx) as usize;
+        for (count, decrypt) in self.counts.iter_mut().zip(&self.decryptions) {
+            if decrypt[c1_nib] ^ decrypt[c2_nib] == self.delta_u_nibble {
+                *count += 1;
+            }
+        }
+        self.pairs_seen += 1;
+    }
+
+    /// Pairs fed with the right input difference
+    pub fn pairs_seen(&self) -> u64 {
+        self.pairs_seen
+    }
+
+    pub fn counts(&self) -> &[u64; 16] {
+        &self.counts
+    }
+
+    /// All candidates with their fraction of right pairs, best first
+    pub fn current_ranking(&self) -> [(u8, f32); 16] {
+        let total = self.pairs_seen.max(1) as f32;
+        let mut ranking: [(u8, f32); 16] =
+            std::array::from_fn(|candidate| (candidate as u8, self.counts[candidate] as f32 / total));
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranking
+    }
+
+    /// Whether the leader's count exceeds the runner-up's by at least `z`
+    /// standard deviations, treating both counts as Poisson
+    pub fn is_separated(&self, z: f64) -> bool {
+        let mut counts = self.counts;
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        let (leader, runner_up) = (counts[0] as f64, counts[1] as f64);
+        leader > runner_up && leader - runner_up >= z * (leader + runner_up).sqrt()
+    }
+}
+
+/// Find best differential characteristic for S-box
+pub fn find_best_differential() -> (u8, u8, f32) {
+    let mut best_prob = -1.0;
+    let mut best_input = 0;
+    let mut best_output = 0;
+
+    for input_diff in 1..16u8 {
+        for output_diff in 0..16u8 {
+            let prob = diff_prob_sbox(input_diff, output_diff);
+            if prob > best_prob {
+                best_prob = prob;
+                best_input = input_diff;
+                best_output = output_diff;
+            }
+        }
+    }
+    (best_input, best_output, best_prob)
+}
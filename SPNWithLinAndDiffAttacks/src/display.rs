@@ -0,0 +1,282 @@
+// Notebook Display
+// ----------------
+//
+// Readable renderings of the analysis tables, trails, rankings and attack
+// results: `Display` for terminals and `to_html` for notebooks. In an evcxr
+// (Jupyter) notebook a value with an `evcxr_display` method is shown through
+// it, so `TableView::lat(&sbox)` as the last expression of a cell renders as
+// a shaded HTML table instead of nested arrays.
+
+use std::fmt;
+
+use crate::complexity::ComplexityReport;
+use crate::results::{AttackResult, Trail};
+use crate::sbox::Sbox;
+
+/// Hand `html` to evcxr, which picks it up from standard output
+fn emit_html(html: &str) {
+    println!("EVCXR_BEGIN_CONTENT text/html\n{}\nEVCXR_END_CONTENT", html);
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// A 16x16 S-box table with named rows and columns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableView {
+    pub title: &'static str,
+    pub row_label: &'static str,
+    pub column_label: &'static str,
+    pub entries: [[i32; 16]; 16],
+}
+
+impl TableView {
+    /// LAT: rows are input masks, columns output masks, entries count - 8
+    pub fn lat(sbox: &Sbox) -> Self {
+        let entries = sbox.lat().map(|row| row.map(i32::from));
+        TableView { title: "Linear approximation table", row_label: "a", column_label: "b", entries }
+    }
+
+    /// DDT: rows are input differences, columns output differences
+    pub fn ddt(sbox: &Sbox) -> Self {
+        let entries = sbox.ddt().map(|row| row.map(i32::from));
+        TableView { title: "Difference distribution table", row_label: "din", column_label: "dout", entries }
+    }
+
+    /// BCT: rows are input differences, columns output differences
+    pub fn bct(sbox: &Sbox) -> Self {
+        let entries = sbox.bct().map(|row| row.map(i32::from));
+        TableView { title: "Boomerang connectivity table", row_label: "din", column_label: "dout", entries }
+    }
+
+    /// Largest magnitude outside row and column 0, which are trivial in all
+    /// three tables
+    fn max_nontrivial(&self) -> i32 {
+        self.entries[1..].iter().flat_map(|row| row[1..].iter()).map(|e| e.abs()).max().unwrap_or(0)
+    }
+
+    /// HTML table, cells shaded by magnitude relative to the largest
+    /// nontrivial entry
+    pub fn to_html(&self) -> String {
+        let max = self.max_nontrivial().max(1) as f64;
+        let mut html = format!(
+            "<table style=\"border-collapse: collapse; font-family: monospace\">\n<caption>{}</caption>\n<tr><th>{}\\{}</th>",
+            self.title, self.row_label, self.column_label
+        );
+        html.extend((0..16).map(|column| format!("<th>{:X}</th>", column)));
+        html.push_str("</tr>\n");
+        for (row, entries) in self.entries.iter().enumerate() {
+            html.push_str(&format!("<tr><th>{:X}</th>", row));
+            for (column, &entry) in entries.iter().enumerate() {
+                let strength = if row == 0 || column == 0 { 0.0 } else { (entry.abs() as f64 / max).min(1.0) };
+                html.push_str(&format!(
+                    "<td style=\"text-align: right; padding: 0 4px; background: rgba(220, 60, 40, {:.2})\">{}</td>",
+                    0.6 * strength,
+                    entry
+                ));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>");
+        html
+    }
+
+    pub fn evcxr_display(&self) {
+        emit_html(&self.to_html());
+    }
+}
+
+impl fmt::Display for TableView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({} down, {} across)", self.title, self.row_label, self.column_label)?;
+        write!(f, "   ")?;
+        for column in 0..16 {
+            write!(f, " {:>3X}", column)?;
+        }
+        for (row, entries) in self.entries.iter().enumerate() {
+            write!(f, "\n{:>2X} ", row)?;
+            for entry in entries {
+                write!(f, " {:>3}", entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Key candidates, best first, optionally marking the right one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankingView {
+    pub ranking: [(u8, f32); 16],
+    pub actual: Option<u8>,
+}
+
+impl RankingView {
+    pub fn new(ranking: [(u8, f32); 16]) -> Self {
+        RankingView { ranking, actual: None }
+    }
+
+    pub fn with_actual(mut self, actual: u8) -> Self {
+        self.actual = Some(actual);
+        self
+    }
+
+    fn top_score(&self) -> f32 {
+        self.ranking.iter().fold(0.0, |max, &(_, score)| max.max(score.abs()))
+    }
+
+    /// HTML table with one bar per candidate, the right key in bold
+    pub fn to_html(&self) -> String {
+        let top = self.top_score().max(f32::MIN_POSITIVE);
+        let mut html = String::from(
+            "<table style=\"font-family: monospace\">\n<tr><th>rank</th><th>key</th><th>score</th><th></th></tr>\n",
+        );
+        for (rank, &(key, score)) in self.ranking.iter().enumerate() {
+            let weight = if self.actual == Some(key) { "bold" } else { "normal" };
+            html.push_str(&format!(
+                "<tr style=\"font-weight: {}\"><td>{}</td><td>{:X}</td><td>{:.5}</td>\
+                 <td><div style=\"width: {:.0}px; height: 0.8em; background: steelblue\"></div></td></tr>\n",
+                weight,
+                rank + 1,
+                key,
+                score,
+                200.0 * score.abs() / top
+            ));
+        }
+        html.push_str("</table>");
+        html
+    }
+
+    pub fn evcxr_display(&self) {
+        emit_html(&self.to_html());
+    }
+}
+
+impl fmt::Display for RankingView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let top = self.top_score().max(f32::MIN_POSITIVE);
+        for (rank, &(key, score)) in self.ranking.iter().enumerate() {
+            let bar = "#".repeat((30.0 * score.abs() / top).round() as usize);
+            if rank > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:>2}. {:X}  {:>9.5}  {}", rank + 1, key, score, bar)?;
+            if self.actual == Some(key) {
+                write!(f, "{}  <- right key", " ".repeat(30 - bar.len()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Trail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Trail::Linear { alpha, beta, bias } => {
+                write!(f, "linear: plaintext mask {:04X} -> last-round input mask {:04X}", alpha, beta)?;
+                bias.map_or(Ok(()), |bias| write!(f, ", bias {:.5}", bias))
+            }
+            Trail::Differential { delta_p, delta_u, probability } => {
+                write!(f, "differential: plaintext difference {:04X} -> last-round input difference {:04X}", delta_p, delta_u)?;
+                probability.map_or(Ok(()), |p| write!(f, ", probability {:.5}", p))
+            }
+        }
+    }
+}
+
+impl Trail {
+    pub fn to_html(&self) -> String {
+        format!("<p style=\"font-family: monospace\">{}</p>", escape(&self.to_string()))
+    }
+
+    pub fn evcxr_display(&self) {
+        emit_html(&self.to_html());
+    }
+}
+
+impl AttackResult {
+    fn ranking_view(&self) -> RankingView {
+        RankingView { ranking: self.ranking, actual: self.actual }
+    }
+
+    fn headline(&self) -> String {
+        let mut headline = format!(
+            "{} attack on round key {} nibble {} with {} {}: recovered {:X}",
+            self.attack.name(),
+            self.round_key,
+            self.nibble_idx,
+            self.data,
+            self.data_unit,
+            self.recovered()
+        );
+        match (self.actual, self.rank_of_actual()) {
+            (Some(actual), Some(0)) => headline.push_str(&format!(" (right, actual {:X})", actual)),
+            (Some(actual), Some(rank)) => headline.push_str(&format!(" (wrong, actual {:X} ranked {})", actual, rank + 1)),
+            _ => {}
+        }
+        headline
+    }
+
+    pub fn to_html(&self) -> String {
+        let trail = self.trail.map_or(String::new(), |trail| trail.to_html());
+        format!("<h4>{}</h4>\n{}\n{}", escape(&self.headline()), trail, self.ranking_view().to_html())
+    }
+
+    pub fn evcxr_display(&self) {
+        emit_html(&self.to_html());
+    }
+}
+
+impl fmt::Display for AttackResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.headline())?;
+        if let Some(trail) = self.trail {
+            writeln!(f, "{}", trail)?;
+        }
+        write!(f, "{}", self.ranking_view())
+    }
+}
+
+/// `count` as a power of two, e.g. "2^13.4"
+fn log2(count: f64) -> String {
+    format!("2^{:.1}", count.log2())
+}
+
+/// `seconds` in the largest unit that keeps it above one
+fn duration(seconds: f64) -> String {
+    const UNITS: [(&str, f64); 7] =
+        [("years", 31_557_600.0), ("days", 86_400.0), ("hours", 3_600.0), ("minutes", 60.0), ("s", 1.0), ("ms", 1e-3), ("µs", 1e-6)];
+    let (unit, size) = UNITS.iter().find(|&&(_, size)| seconds >= size).unwrap_or(&("ns", 1e-9));
+    format!("{:.1} {}", seconds / size, unit)
+}
+
+impl fmt::Display for ComplexityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let query_type = if matches!(self.trail, Trail::Linear { .. }) { "known" } else { "chosen" };
+        writeln!(f, "{}", self.trail)?;
+        writeln!(f, "Nibbles attacked:   {:?}", self.nibbles)?;
+        write!(f, "Data:               {} pairs, {} {} plaintext queries", log2(self.pairs), log2(self.oracle_queries), query_type)?;
+        if self.exceeds_codebook {
+            write!(f, " (more than the 2^16 codebook)")?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Memory:             {:.0} bytes ({:.0} pairs, {} counters, {} tables)",
+            self.memory_bytes(),
+            self.pair_bytes,
+            self.counter_bytes,
+            self.table_bytes
+        )?;
+        writeln!(f, "Collecting data:    {}", duration(self.data_seconds))?;
+        writeln!(f, "Counting:           {}", duration(self.counting_seconds))?;
+        writeln!(f, "Remaining {} bits:  {}", self.remaining_key_bits, duration(self.remaining_search_seconds))?;
+        writeln!(f, "Total:              {}", duration(self.total_seconds()))?;
+        write!(
+            f,
+            "Exhaustive search:  {} (the attack is {} times faster)",
+            duration(self.exhaustive_search_seconds),
+            log2(self.speedup())
+        )
+    }
+}
@@ -0,0 +1,269 @@
+// Differential Fault Analysis
+// ---------------------------
+//
+// A fault XORed into one nibble before the round-3 S-box layer changes that
+// S-box's output by some unknown nonzero value. The P-box then sends bit b of
+// nibble s to bit s of nibble b, so before the last S-box layer every nibble
+// differs from the correct run by either nothing or exactly bit s. For each
+// nibble of the last round key, only guesses that turn the correct/faulty
+// ciphertext nibbles back into such a single-bit difference survive, and a
+// handful of faulty encryptions leave one guess per nibble.
+//
+// Besides bit flips, a fault can force bits to fixed values (stuck-at), replace
+// a byte of the state with a random value, or skip a whole layer as an
+// instruction skip would. Skipping the last key addition hands over the last
+// round key outright: the correct and faulty ciphertexts differ by exactly it.
+
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::{nibble, pbox, sbox_layer, Spn, SBOX_INV};
+
+/// The steps of a round; round 0 is the whitening key addition alone and the
+/// last round (4) has no P-box
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Sbox,
+    Pbox,
+    KeyAddition,
+}
+
+/// What the fault does to the state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultModel {
+    /// XOR a mask into the state
+    Flip(u16),
+    /// Force the bits in `mask` to the matching bits of `value`
+    StuckAt { mask: u16, value: u16 },
+    /// Replace byte `byte_idx` (0 = low byte) with a fresh random value
+    RandomByte(usize),
+    /// Do not execute the layer at all
+    Skip,
+}
+
+/// A fault hitting the state right before `layer` of `round`, or skipping
+/// that layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fault {
+    pub round: usize,
+    pub layer: Layer,
+    pub model: FaultModel,
+}
+
+impl Fault {
+    /// Panics unless `round` (0-4) has `layer`
+    pub fn at(round: usize, layer: Layer, model: FaultModel) -> Self {
+        let exists = match layer {
+            Layer::Sbox => (1..=4).contains(&round),
+            Layer::Pbox => (1..=3).contains(&round),
+            Layer::KeyAddition => round <= 4,
+        };
+        assert!(exists, "round {} has no {:?} layer", round, layer);
+        if let FaultModel::RandomByte(byte_idx) = model {
+            assert!(byte_idx < 2, "the state has two bytes");
+        }
+        Fault { round, layer, model }
+    }
+
+    /// XOR `mask` into the state before the S-box layer of `round`
+    /// (1-3 are the full rounds, 4 the final S-box layer)
+    pub fn new(round: usize, mask: u16) -> Self {
+        Self::at(round, Layer::Sbox, FaultModel::Flip(mask))
+    }
+
+    /// Flip a single bit of the state
+    pub fn bit(round: usize, bit: u32) -> Self {
+        Self::new(round, 1 << bit)
+    }
+
+    /// XOR `value` into nibble `nibble_idx`
+    pub fn nibble(round: usize, nibble_idx: usize, value: u8) -> Self {
+        Self::new(round, ((value & 0xF) as u16) << (4 * nibble_idx))
+    }
+
+    pub fn stuck_at(round: usize, layer: Layer, mask: u16, value: u16) -> Self {
+        Self::at(round, layer, FaultModel::StuckAt { mask, value })
+    }
+
+    pub fn random_byte(round: usize, layer: Layer, byte_idx: usize) -> Self {
+        Self::at(round, layer, FaultModel::RandomByte(byte_idx))
+    }
+
+    pub fn skip(round: usize, layer: Layer) -> Self {
+        Self::at(round, layer, FaultModel::Skip)
+    }
+
+    /// Apply the fault to `state` and then run `layer` on it unless skipped
+    fn strike(&self, state: u16, rng: &mut SplitMix64, layer: impl Fn(u16) -> u16) -> u16 {
+        match self.model {
+            FaultModel::Flip(mask) => layer(state ^ mask),
+            FaultModel::StuckAt { mask, value } => layer((state & !mask) | (value & mask)),
+            FaultModel::RandomByte(byte_idx) => {
+                let mask = 0xFFu16 << (8 * byte_idx);
+                layer((state & !mask) | (rng.next_u16() & mask))
+            }
+            FaultModel::Skip => state,
+        }
+    }
+}
+
+/// `spn::encrypt` with `fault` injected; `rng` supplies random fault values
+pub fn encrypt_with_fault(plaintext: u16, round_keys: &[u16], fault: Fault, rng: &mut SplitMix64) -> u16 {
+    let mut step = |round: usize, layer: Layer, state: u16, apply: &dyn Fn(u16) -> u16| {
+        if (fault.round, fault.layer) == (round, layer) {
+            fault.strike(state, rng, apply)
+        } else {
+            apply(state)
+        }
+    };
+    let mut state = step(0, Layer::KeyAddition, plaintext, &|s| s ^ round_keys[0]);
+    for (round, &round_key) in (1..).zip(&round_keys[1..5]) {
+        state = step(round, Layer::Sbox, state, &sbox_layer);
+        if round < 4 {
+            state = step(round, Layer::Pbox, state, &pbox);
+        }
+        state = step(round, Layer::KeyAddition, state, &|s| s ^ round_key);
+    }
+    state
+}
+
+/// A device that can be made to glitch: encrypts once normally and once with
+/// a fault
+#[derive(Clone, Debug)]
+pub struct FaultyDevice {
+    cipher: Spn,
+    rng: SplitMix64,
+}
+
+impl FaultyDevice {
+    pub fn new(cipher: Spn, seed: u64) -> Self {
+        FaultyDevice { cipher, rng: SplitMix64::new(seed) }
+    }
+
+    pub fn encrypt_with_fault(&mut self, plaintext: u16, fault: Fault) -> u16 {
+        encrypt_with_fault(plaintext, self.cipher.round_keys(), fault, &mut self.rng)
+    }
+
+    /// (correct, faulty) ciphertexts of `plaintext` under `fault`
+    pub fn pair_with_fault(&mut self, plaintext: u16, fault: Fault) -> (u16, u16) {
+        (self.cipher.encrypt(plaintext), self.encrypt_with_fault(plaintext, fault))
+    }
+
+    /// (correct, faulty) ciphertexts of `plaintext`, the fault being a random
+    /// nonzero value in nibble `nibble_idx` before the S-box layer of `round`
+    pub fn faulty_pair(&mut self, plaintext: u16, round: usize, nibble_idx: usize) -> (u16, u16) {
+        let value = 1 + (self.rng.next_u64() % 15) as u8;
+        self.pair_with_fault(plaintext, Fault::nibble(round, nibble_idx, value))
+    }
+}
+
+/// The last round key from a pair whose faulty run skipped the last key
+/// addition
+pub fn last_round_key_from_skip((correct, faulty): (u16, u16)) -> u16 {
+    correct ^ faulty
+}
+
+/// Last-round-key nibble guesses consistent with every faulty pair, for
+/// faults in a single nibble before the round-3 S-box layer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DfaResult {
+    /// Surviving guesses per nibble of the last round key
+    pub candidates: [Vec<u8>; 4],
+}
+
+impl DfaResult {
+    /// The last round key, once every nibble has a single candidate left
+    pub fn key(&self) -> Option<u16> {
+        self.candidates.iter().enumerate().try_fold(0u16, |key, (idx, candidates)| match candidates[..] {
+            [k] => Some(key | (k as u16) << (4 * idx)),
+            _ => None,
+        })
+    }
+
+    /// Remaining last-round key space
+    pub fn remaining_keys(&self) -> usize {
+        self.candidates.iter().map(Vec::len).product()
+    }
+}
+
+/// Recover the last round key from (correct, faulty) ciphertext pairs
+pub fn dfa_last_round_key(pairs: &[(u16, u16)]) -> DfaResult {
+    let candidates = std::array::from_fn(|idx| {
+        (0..16u8)
+            .filter(|&k| {
+                pairs.iter().all(|&(correct, faulty)| {
+                    let (c, f) = (nibble(correct, idx), nibble(faulty, idx));
+                    let difference = SBOX_INV[(c ^ k) as usize] ^ SBOX_INV[(f ^ k) as usize];
+                    // An untouched nibble says nothing about the key
+                    c == f || difference.is_power_of_two()
+                })
+            })
+            .collect()
+    });
+    DfaResult { candidates }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::sbox_inv_layer;
+
+    #[test]
+    fn dfa_recovers_the_last_round_key() {
+        let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+        let last_round_key = cipher.round_keys()[4];
+        let mut device = FaultyDevice::new(cipher, 3);
+        let mut rng = SplitMix64::new(4);
+        let mut pairs = Vec::new();
+        for i in 0..32 {
+            pairs.push(device.faulty_pair(rng.next_u16(), 3, i % 4));
+            let result = dfa_last_round_key(&pairs);
+            for (idx, candidates) in result.candidates.iter().enumerate() {
+                assert!(candidates.contains(&nibble(last_round_key, idx)));
+            }
+            if let Some(key) = result.key() {
+                assert_eq!(key, last_round_key);
+                assert_eq!(result.remaining_keys(), 1);
+                return;
+            }
+        }
+        panic!("32 faulty pairs left {} keys", dfa_last_round_key(&pairs).remaining_keys());
+    }
+
+    #[test]
+    fn each_fault_model_hits_the_state() {
+        let cipher = Spn::new(0x0FED_CBA0_9876_5432_1ABC);
+        let round_keys = cipher.round_keys();
+        let mut rng = SplitMix64::new(5);
+        for plaintext in [0x0000, 0x1234, 0xBEEF] {
+            let correct = cipher.encrypt(plaintext);
+            let mut last = |model| encrypt_with_fault(plaintext, round_keys, Fault::at(4, Layer::KeyAddition, model), &mut rng);
+            assert_eq!(last(FaultModel::Flip(0)), correct);
+            assert_eq!(last(FaultModel::Flip(0x0F01)), correct ^ 0x0F01);
+            assert_eq!(last(FaultModel::StuckAt { mask: 0xFFFF, value: 0x5A5A }), 0x5A5A ^ round_keys[4]);
+            assert_eq!(last(FaultModel::StuckAt { mask: 0, value: 0xFFFF }), correct);
+            assert_eq!((last(FaultModel::RandomByte(1)) ^ correct) & 0x00FF, 0);
+            assert_eq!((last(FaultModel::RandomByte(0)) ^ correct) & 0xFF00, 0);
+        }
+    }
+
+    #[test]
+    fn skipping_the_last_key_addition_reveals_the_round_key() {
+        let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+        let last_round_key = cipher.round_keys()[4];
+        let mut device = FaultyDevice::new(cipher, 6);
+        for plaintext in [0x0000, 0x8001, 0xFFFF] {
+            let pair = device.pair_with_fault(plaintext, Fault::skip(4, Layer::KeyAddition));
+            assert_eq!(last_round_key_from_skip(pair), last_round_key);
+        }
+    }
+
+    #[test]
+    fn skipping_a_layer_leaves_the_state_to_the_next_one() {
+        let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+        let round_keys = cipher.round_keys();
+        let mut rng = SplitMix64::new(7);
+        let plaintext = 0x4321;
+        let skipped = encrypt_with_fault(plaintext, round_keys, Fault::skip(4, Layer::Sbox), &mut rng);
+        let before_last_sbox = sbox_inv_layer(cipher.encrypt(plaintext) ^ round_keys[4]);
+        assert_eq!(skipped, before_last_sbox ^ round_keys[4]);
+    }
+}
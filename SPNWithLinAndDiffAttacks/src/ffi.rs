@@ -0,0 +1,229 @@
+// C Interface
+// -----------
+//
+// A stable `extern "C"` API for C and C++ harnesses, declared in
+// `include/spn_attacks.h`. Build a static or shared library with
+//
+//   cargo rustc --lib --release --features ffi --crate-type staticlib
+//
+// Ciphers are opaque `SpnCipher` handles. Functions that can fail return an
+// `SPN_*` status code and write their results through out-parameters; the
+// single-block functions cannot fail and return the block directly. Bump
+// `SPN_ABI_VERSION` (and the header) whenever a signature or struct changes.
+
+use std::slice;
+
+use crate::differential::differential_counts_from_iter;
+use crate::linear::linear_counts_from_iter;
+use crate::spn::Spn;
+
+pub const SPN_ABI_VERSION: u32 = 1;
+
+pub const SPN_OK: i32 = 0;
+/// A required pointer was null
+pub const SPN_ERR_NULL: i32 = -1;
+/// An argument was out of range, e.g. a nibble index above 3
+pub const SPN_ERR_ARGUMENT: i32 = -2;
+
+/// Opaque cipher handle
+pub struct SpnCipher(Spn);
+
+/// Key candidates for one nibble, best first, with their scores
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpnRanking {
+    pub keys: [u8; 16],
+    pub scores: [f32; 16],
+}
+
+impl From<[(u8, f32); 16]> for SpnRanking {
+    fn from(ranking: [(u8, f32); 16]) -> Self {
+        SpnRanking { keys: ranking.map(|(key, _)| key), scores: ranking.map(|(_, score)| score) }
+    }
+}
+
+/// Candidates ordered by `score`, highest first
+fn ranking_by(counts: &[u64; 16], score: impl Fn(u64) -> f32) -> SpnRanking {
+    let mut ranking: [(u8, f32); 16] = std::array::from_fn(|key| (key as u8, score(counts[key])));
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranking.into()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn spn_abi_version() -> u32 {
+    SPN_ABI_VERSION
+}
+
+/// Cipher for the 80-bit master key in `key`, 10 bytes big-endian; null if
+/// `key` is null
+///
+/// # Safety
+/// `key` must be null or point to 10 readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_new(key: *const u8) -> *mut SpnCipher {
+    if key.is_null() {
+        return std::ptr::null_mut();
+    }
+    // SAFETY: guaranteed by the caller
+    let key = unsafe { slice::from_raw_parts(key, 10) };
+    let master_key = key.iter().fold(0u128, |acc, &byte| acc << 8 | byte as u128);
+    Box::into_raw(Box::new(SpnCipher(Spn::new(master_key))))
+}
+
+/// Cipher with five explicit round keys; null if `round_keys` is null
+///
+/// # Safety
+/// `round_keys` must be null or point to 5 readable words.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_from_round_keys(round_keys: *const u16) -> *mut SpnCipher {
+    if round_keys.is_null() {
+        return std::ptr::null_mut();
+    }
+    // SAFETY: guaranteed by the caller
+    let round_keys = unsafe { slice::from_raw_parts(round_keys, 5) };
+    Box::into_raw(Box::new(SpnCipher(Spn::from_round_keys(round_keys.try_into().unwrap()))))
+}
+
+/// Release a cipher; null is ignored
+///
+/// # Safety
+/// `cipher` must be null or a handle from this API that is not used again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_free(cipher: *mut SpnCipher) {
+    if !cipher.is_null() {
+        // SAFETY: the handle was created by `Box::into_raw`
+        drop(unsafe { Box::from_raw(cipher) });
+    }
+}
+
+/// Copy the five round keys to `out`
+///
+/// # Safety
+/// `cipher` must be null or a live handle; `out` must be null or point to 5
+/// writable words.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_round_keys(cipher: *const SpnCipher, out: *mut u16) -> i32 {
+    if cipher.is_null() || out.is_null() {
+        return SPN_ERR_NULL;
+    }
+    // SAFETY: guaranteed by the caller
+    let (cipher, out) = unsafe { (&(*cipher).0, slice::from_raw_parts_mut(out, 5)) };
+    out.copy_from_slice(cipher.round_keys());
+    SPN_OK
+}
+
+/// # Safety
+/// `cipher` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_encrypt(cipher: *const SpnCipher, block: u16) -> u16 {
+    // SAFETY: guaranteed by the caller
+    unsafe { &(*cipher).0 }.encrypt(block)
+}
+
+/// # Safety
+/// `cipher` must be a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_decrypt(cipher: *const SpnCipher, block: u16) -> u16 {
+    // SAFETY: guaranteed by the caller
+    unsafe { &(*cipher).0 }.decrypt(block)
+}
+
+/// Encrypt `len` blocks in place
+///
+/// # Safety
+/// `cipher` must be null or a live handle; `blocks` must be null or point to
+/// `len` writable words.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_encrypt_blocks(cipher: *const SpnCipher, blocks: *mut u16, len: usize) -> i32 {
+    if cipher.is_null() || blocks.is_null() {
+        return SPN_ERR_NULL;
+    }
+    // SAFETY: guaranteed by the caller
+    let (cipher, blocks) = unsafe { (&(*cipher).0, slice::from_raw_parts_mut(blocks, len)) };
+    cipher.encrypt_blocks(blocks);
+    SPN_OK
+}
+
+/// Decrypt `len` blocks in place
+///
+/// # Safety
+/// As for `spn_cipher_encrypt_blocks`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_cipher_decrypt_blocks(cipher: *const SpnCipher, blocks: *mut u16, len: usize) -> i32 {
+    if cipher.is_null() || blocks.is_null() {
+        return SPN_ERR_NULL;
+    }
+    // SAFETY: guaranteed by the caller
+    let (cipher, blocks) = unsafe { (&(*cipher).0, slice::from_raw_parts_mut(blocks, len)) };
+    cipher.decrypt_blocks(blocks);
+    SPN_OK
+}
+
+/// Linear attack on `len` caller-supplied known-plaintext pairs, ranking the
+/// candidates for last-round-key nibble `nibble_idx` by bias magnitude
+///
+/// # Safety
+/// `plaintexts` and `ciphertexts` must be null or point to `len` readable
+/// words; `out` must be null or point to a writable `SpnRanking`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_linear_rank(
+    plaintexts: *const u16,
+    ciphertexts: *const u16,
+    len: usize,
+    alpha: u16,
+    beta: u16,
+    nibble_idx: u32,
+    out: *mut SpnRanking,
+) -> i32 {
+    if plaintexts.is_null() || ciphertexts.is_null() || out.is_null() {
+        return SPN_ERR_NULL;
+    }
+    if nibble_idx > 3 {
+        return SPN_ERR_ARGUMENT;
+    }
+    // SAFETY: guaranteed by the caller
+    let (plaintexts, ciphertexts) = unsafe { (slice::from_raw_parts(plaintexts, len), slice::from_raw_parts(ciphertexts, len)) };
+    let pairs = plaintexts.iter().copied().zip(ciphertexts.iter().copied());
+    let counts = linear_counts_from_iter(pairs, alpha, beta, nibble_idx as usize);
+    let total = len.max(1) as f32;
+    // SAFETY: guaranteed by the caller
+    unsafe { *out = ranking_by(&counts, |count| (count as f32 / total - 0.5).abs()) };
+    SPN_OK
+}
+
+/// Differential attack on `len` caller-supplied chosen-plaintext pairs,
+/// ranking the candidates for last-round-key nibble `nibble_idx` by their
+/// fraction of right pairs; pairs whose plaintexts do not differ by
+/// `delta_p` are skipped
+///
+/// # Safety
+/// The four arrays must be null or point to `len` readable words; `out` must
+/// be null or point to a writable `SpnRanking`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_differential_rank(
+    plaintexts1: *const u16,
+    plaintexts2: *const u16,
+    ciphertexts1: *const u16,
+    ciphertexts2: *const u16,
+    len: usize,
+    delta_p: u16,
+    delta_u: u16,
+    nibble_idx: u32,
+    out: *mut SpnRanking,
+) -> i32 {
+    let arrays = [plaintexts1, plaintexts2, ciphertexts1, ciphertexts2];
+    if arrays.iter().any(|array| array.is_null()) || out.is_null() {
+        return SPN_ERR_NULL;
+    }
+    if nibble_idx > 3 {
+        return SPN_ERR_ARGUMENT;
+    }
+    // SAFETY: guaranteed by the caller
+    let [p1, p2, c1, c2] = arrays.map(|array| unsafe { slice::from_raw_parts(array, len) });
+    let pairs = (0..len).map(|i| (p1[i], p2[i], c1[i], c2[i]));
+    let counts = differential_counts_from_iter(pairs, delta_p, delta_u, nibble_idx as usize);
+    let right_pairs = (0..len).filter(|&i| p1[i] ^ p2[i] == delta_p).count().max(1) as f32;
+    // SAFETY: guaranteed by the caller
+    unsafe { *out = ranking_by(&counts, |count| count as f32 / right_pairs) };
+    SPN_OK
+}
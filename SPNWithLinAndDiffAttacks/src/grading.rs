@@ -0,0 +1,385 @@
+// Assignment Grading
+// ------------------
+//
+// Challenges for a course assignment and an autograder for the answers. A
+// challenge is fixed by its attack and a seed, and the seed picks the secret
+// master key: it is as secret as the key itself, so choose it at random and
+// keep it with the grader. The plaintexts of the data set handed to the
+// student as a dataset file (see `dataset`) come from a separate seed that is
+// unrelated to the key, and the header hashes only public settings. A
+// submission is a small text file:
+//
+//   # Linear attack, seed 42
+//   key = F4?B
+//   round = 0B00 0400
+//   round = 0400 0400
+//   round = 0400 0400
+//   bias = 0.0078
+//
+// `key` is the last round key, with `?` for nibbles not attempted. Each
+// `round` gives the mask (difference) entering and leaving one S-box layer
+// of the trail, first round first; a differential trail must start at the
+// challenge's plaintext difference. `bias` (or `probability` for a
+// differential) is the value the student derived for the trail. The grade
+// report lists every nibble, whether the trail is connected and uses only
+// possible S-box transitions, and whether the claimed bias is within a
+// relative tolerance of the piling-up value of the trail. Reports are JSON
+// in the `results` style, specified in `schema/grade-report.schema.json`.
+
+use std::io::{self, Write};
+
+use crate::dataset::{ConfigHasher, DatasetHeader, DatasetKind, DatasetWriter};
+use crate::pairs::{chosen_plaintext_pairs, known_plaintext_pairs};
+use crate::results::{json_number, json_option, json_string, AttackKind, SCHEMA_VERSION};
+use crate::rng::SplitMix64;
+use crate::sbox::Sbox;
+use crate::spn::{nibble, pbox, random_master_key, Spn};
+
+/// S-box layers a trail crosses before the attacked last layer
+pub const TRAIL_ROUNDS: usize = 3;
+
+/// Plaintext difference of differential challenges
+pub const CHALLENGE_DELTA_P: u16 = 0x0040;
+
+/// Pairs in a challenge data set
+pub const CHALLENGE_PAIRS: usize = 20000;
+
+/// Relative error allowed on a claimed bias or probability
+pub const DEFAULT_BIAS_TOLERANCE: f64 = 0.1;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A generated assignment: one secret key and the data to attack it with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    pub attack: AttackKind,
+    pub seed: u64,
+}
+
+impl Challenge {
+    pub fn new(attack: AttackKind, seed: u64) -> Self {
+        assert!(
+            matches!(attack, AttackKind::Linear | AttackKind::Differential),
+            "challenges are linear or differential"
+        );
+        Challenge { attack, seed }
+    }
+
+    /// The cipher under the secret key
+    pub fn cipher(&self) -> Spn {
+        Spn::new(random_master_key(&mut SplitMix64::new(self.seed).split(0)))
+    }
+
+    /// Hash of the public settings of the data set: the S-box, the P-box,
+    /// the attack, the pair count and the plaintext difference, but no key
+    pub fn config_hash(&self) -> u64 {
+        let mut hasher = ConfigHasher::cipher(&[]);
+        hasher.write(self.attack.name().as_bytes());
+        hasher.write(&(CHALLENGE_PAIRS as u64).to_le_bytes());
+        hasher.write(&CHALLENGE_DELTA_P.to_le_bytes());
+        hasher.finish()
+    }
+
+    /// Write the student's data set: known pairs for a linear challenge,
+    /// chosen pairs with `CHALLENGE_DELTA_P` for a differential one
+    ///
+    /// `data_seed` picks the plaintexts and is stored in the header, so it
+    /// must not be derived from the challenge seed; a fresh random value
+    /// will do.
+    pub fn write_dataset<W: Write>(&self, out: W, data_seed: u64) -> io::Result<W> {
+        let cipher = self.cipher();
+        let kind = match self.attack {
+            AttackKind::Linear => DatasetKind::KnownPairs,
+            _ => DatasetKind::ChosenPairs { delta_p: CHALLENGE_DELTA_P },
+        };
+        let header = DatasetHeader { kind, config_hash: self.config_hash(), seed: data_seed };
+        let mut writer = DatasetWriter::new(out, header)?;
+        if self.attack == AttackKind::Linear {
+            for pair in known_plaintext_pairs(&cipher, CHALLENGE_PAIRS, data_seed) {
+                writer.push_known(pair)?;
+            }
+        } else {
+            for pair in chosen_plaintext_pairs(&cipher, CHALLENGE_DELTA_P, CHALLENGE_PAIRS, data_seed) {
+                writer.push_chosen(pair)?;
+            }
+        }
+        Ok(writer.finish()?.0)
+    }
+}
+
+/// A student's answer; every part is optional
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Submission {
+    /// Nibbles of the last round key, least significant first
+    pub key: [Option<u8>; 4],
+    /// S-box layer (input, output) masks or differences, first round first
+    pub trail: Vec<(u16, u16)>,
+    /// Bias of a linear trail, probability of a differential one
+    pub bias: Option<f64>,
+}
+
+impl Submission {
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut submission = Submission::default();
+        let (mut has_key, mut has_bias) = (false, false);
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (name, value) = line.split_once('=').ok_or_else(|| invalid("expected name = value"))?;
+            let value = value.trim();
+            match name.trim() {
+                "key" => {
+                    if std::mem::replace(&mut has_key, true) {
+                        return Err(invalid("key given twice"));
+                    }
+                    let digits: Vec<char> = value.chars().collect();
+                    if digits.len() != 4 {
+                        return Err(invalid("key must be four hex digits or ?"));
+                    }
+                    for (idx, digit) in digits.iter().rev().enumerate() {
+                        submission.key[idx] = match digit {
+                            '?' => None,
+                            _ => Some(digit.to_digit(16).ok_or_else(|| invalid("key must be four hex digits or ?"))? as u8),
+                        };
+                    }
+                }
+                "round" => {
+                    let mut masks = value.split_whitespace().map(|mask| u16::from_str_radix(mask, 16));
+                    match (masks.next(), masks.next(), masks.next()) {
+                        (Some(Ok(input)), Some(Ok(output)), None) => submission.trail.push((input, output)),
+                        _ => return Err(invalid("round must be two 16-bit hex values")),
+                    }
+                }
+                "bias" | "probability" => {
+                    if std::mem::replace(&mut has_bias, true) {
+                        return Err(invalid("bias given twice"));
+                    }
+                    submission.bias = Some(value.parse().map_err(|_| invalid("bias must be a number"))?);
+                }
+                _ => return Err(invalid("unknown field, expected key, round, bias or probability")),
+            }
+        }
+        Ok(submission)
+    }
+}
+
+/// Verdict on a claimed trail
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrailCheck {
+    Missing,
+    /// `weight` is the piling-up bias of a linear trail or the probability
+    /// of a differential one; `end` is the mask (difference) entering the
+    /// last S-box layer
+    Valid { weight: f64, active_sboxes: usize, end: u16 },
+    Invalid(String),
+}
+
+/// Check `trail` against the PRESENT S-box: `TRAIL_ROUNDS` layers, each
+/// input the P-box image of the previous output, and every S-box transition
+/// possible (nonzero LAT or DDT entry, active on both sides or on neither)
+pub fn check_trail(attack: AttackKind, trail: &[(u16, u16)]) -> TrailCheck {
+    if trail.is_empty() {
+        return TrailCheck::Missing;
+    }
+    if trail.len() != TRAIL_ROUNDS {
+        return TrailCheck::Invalid(format!("{} rounds, expected {}", trail.len(), TRAIL_ROUNDS));
+    }
+    if attack == AttackKind::Differential && trail[0].0 != CHALLENGE_DELTA_P {
+        return TrailCheck::Invalid(format!("starts at {:04X}, the challenge difference is {:04X}", trail[0].0, CHALLENGE_DELTA_P));
+    }
+    let sbox = Sbox::present();
+    let (mut weight, mut active_sboxes) = (1.0, 0);
+    for (round, &(input, output)) in trail.iter().enumerate() {
+        if input == 0 {
+            return TrailCheck::Invalid(format!("round {} has no active S-box", round + 1));
+        }
+        if round > 0 && input != pbox(trail[round - 1].1) {
+            return TrailCheck::Invalid(format!("round {} input {:04X} is not the P-box image {:04X}", round + 1, input, pbox(trail[round - 1].1)));
+        }
+        for idx in 0..4 {
+            let (a, b) = (nibble(input, idx) as usize, nibble(output, idx) as usize);
+            let entry = match attack {
+                AttackKind::Linear => sbox.lat()[a][b] as f64 / 8.0,
+                _ => sbox.ddt()[a][b] as f64 / 16.0,
+            };
+            if entry == 0.0 || (a == 0) != (b == 0) {
+                return TrailCheck::Invalid(format!("round {} S-box {}: {:X} -> {:X} is impossible", round + 1, idx, a, b));
+            }
+            active_sboxes += (a != 0) as usize;
+            weight *= entry;
+        }
+    }
+    // Correlations multiply; the bias is half the correlation
+    if attack == AttackKind::Linear {
+        weight /= 2.0;
+    }
+    TrailCheck::Valid { weight, active_sboxes, end: pbox(trail[TRAIL_ROUNDS - 1].1) }
+}
+
+/// Verdict on a claimed bias or probability, compared in absolute value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BiasCheck {
+    Missing,
+    /// No valid trail to compare with
+    Unchecked { claimed: f64 },
+    Checked { claimed: f64, expected: f64, within_tolerance: bool },
+}
+
+/// Everything the autograder found in one submission
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradeReport {
+    pub challenge: Challenge,
+    /// Per nibble of the last round key: `None` if not attempted, else
+    /// whether it is right
+    pub nibbles: [Option<bool>; 4],
+    pub trail: TrailCheck,
+    pub bias: BiasCheck,
+}
+
+impl GradeReport {
+    pub fn correct_nibbles(&self) -> usize {
+        self.nibbles.iter().filter(|&&nibble| nibble == Some(true)).count()
+    }
+
+    /// One point per right nibble, two for a valid trail and one for a bias
+    /// within tolerance, out of seven
+    pub fn points(&self) -> (u32, u32) {
+        let trail = if matches!(self.trail, TrailCheck::Valid { .. }) { 2 } else { 0 };
+        let bias = matches!(self.bias, BiasCheck::Checked { within_tolerance: true, .. }) as u32;
+        (self.correct_nibbles() as u32 + trail + bias, 7)
+    }
+
+    pub fn to_json(&self) -> String {
+        let nibbles: Vec<String> = self.nibbles.iter().map(|&nibble| json_option(nibble)).collect();
+        let trail = match &self.trail {
+            TrailCheck::Missing => "{\"status\": \"missing\"}".to_string(),
+            TrailCheck::Valid { weight, active_sboxes, end } => format!(
+                "{{\"status\": \"valid\", \"weight\": {}, \"active_sboxes\": {}, \"end\": {}}}",
+                json_number(*weight),
+                active_sboxes,
+                end
+            ),
+            TrailCheck::Invalid(reason) => format!("{{\"status\": \"invalid\", \"reason\": {}}}", json_string(reason)),
+        };
+        let bias = match self.bias {
+            BiasCheck::Missing => "{\"status\": \"missing\"}".to_string(),
+            BiasCheck::Unchecked { claimed } => format!("{{\"status\": \"unchecked\", \"claimed\": {}}}", json_number(claimed)),
+            BiasCheck::Checked { claimed, expected, within_tolerance } => format!(
+                "{{\"status\": \"{}\", \"claimed\": {}, \"expected\": {}}}",
+                if within_tolerance { "within_tolerance" } else { "outside_tolerance" },
+                json_number(claimed),
+                json_number(expected)
+            ),
+        };
+        let (points, max_points) = self.points();
+        format!(
+            "{{\n  \"schema\": \"spn-attacks/grade-report\",\n  \"version\": {},\n  \
+             \"challenge\": {{\"attack\": {}, \"seed\": {}}},\n  \"nibbles\": [{}],\n  \
+             \"correct_nibbles\": {},\n  \"trail\": {},\n  \"bias\": {},\n  \"points\": {},\n  \"max_points\": {}\n}}",
+            SCHEMA_VERSION,
+            json_string(self.challenge.attack.name()),
+            self.challenge.seed,
+            nibbles.join(", "),
+            self.correct_nibbles(),
+            trail,
+            bias,
+            points,
+            max_points
+        )
+    }
+}
+
+/// Grade `submission` for `challenge`; `bias_tolerance` is the relative
+/// error allowed on the claimed bias, e.g. `DEFAULT_BIAS_TOLERANCE`
+pub fn grade(challenge: &Challenge, submission: &Submission, bias_tolerance: f64) -> GradeReport {
+    let last_round_key = challenge.cipher().round_keys()[4];
+    let nibbles = std::array::from_fn(|idx| submission.key[idx].map(|claimed| claimed == nibble(last_round_key, idx)));
+    let trail = check_trail(challenge.attack, &submission.trail);
+    let bias = match (submission.bias, &trail) {
+        (None, _) => BiasCheck::Missing,
+        (Some(claimed), TrailCheck::Valid { weight, .. }) => {
+            let expected = weight.abs();
+            let within_tolerance = (claimed.abs() - expected).abs() <= bias_tolerance * expected;
+            BiasCheck::Checked { claimed, expected, within_tolerance }
+        }
+        (Some(claimed), _) => BiasCheck::Unchecked { claimed },
+    };
+    GradeReport { challenge: *challenge, nibbles, trail, bias }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::DatasetReader;
+
+    const LINEAR_TRAIL: [(u16, u16); 3] = [(0x0B00, 0x0400), (0x0400, 0x0400), (0x0400, 0x0400)];
+
+    #[test]
+    fn parses_a_submission() {
+        let text = "# Linear attack, seed 42\nkey = F4?B\nround = 0B00 0400\nround = 0400 0400\nround = 0400 0400\nbias = 0.0078\n";
+        let submission = Submission::parse(text).unwrap();
+        assert_eq!(submission.key, [Some(0xB), None, Some(0x4), Some(0xF)]);
+        assert_eq!(submission.trail, LINEAR_TRAIL);
+        assert_eq!(submission.bias, Some(0.0078));
+    }
+
+    #[test]
+    fn rejects_malformed_submissions() {
+        for text in ["key = F4B", "key = F4?G", "key = 1234\nkey = 1234", "round = 0B00", "bias = high", "nonce = 1", "key"] {
+            assert_eq!(Submission::parse(text).unwrap_err().kind(), io::ErrorKind::InvalidData, "{}", text);
+        }
+    }
+
+    #[test]
+    fn checks_the_linear_trail() {
+        let TrailCheck::Valid { weight, active_sboxes, end } = check_trail(AttackKind::Linear, &LINEAR_TRAIL) else {
+            panic!("the trail is valid");
+        };
+        assert_eq!(weight.abs(), 1.0 / 128.0);
+        assert_eq!(active_sboxes, 3);
+        assert_eq!(end, 0x0400);
+        assert_eq!(check_trail(AttackKind::Linear, &[]), TrailCheck::Missing);
+    }
+
+    #[test]
+    fn rejects_a_broken_pbox_link() {
+        let trail = [(0x0B00, 0x0400), (0x0200, 0x0400), (0x0400, 0x0400)];
+        assert!(matches!(check_trail(AttackKind::Linear, &trail), TrailCheck::Invalid(reason) if reason.contains("P-box")));
+    }
+
+    #[test]
+    fn rejects_an_impossible_transition() {
+        // LAT[1][1] is zero for the PRESENT S-box
+        let trail = [(0x0100, 0x0100), (0x0010, 0x0400), (0x0400, 0x0400)];
+        assert!(matches!(check_trail(AttackKind::Linear, &trail), TrailCheck::Invalid(reason) if reason.contains("impossible")));
+    }
+
+    #[test]
+    fn grades_a_full_answer() {
+        let challenge = Challenge::new(AttackKind::Linear, 42);
+        let last_round_key = challenge.cipher().round_keys()[4];
+        let submission = Submission {
+            key: std::array::from_fn(|idx| Some(nibble(last_round_key, idx))),
+            trail: LINEAR_TRAIL.to_vec(),
+            bias: Some(0.0078),
+        };
+        let report = grade(&challenge, &submission, DEFAULT_BIAS_TOLERANCE);
+        assert_eq!(report.points(), (7, 7));
+
+        let wrong = Submission { key: [Some(nibble(last_round_key, 0) ^ 1), None, None, None], trail: Vec::new(), bias: Some(0.5) };
+        let report = grade(&challenge, &wrong, DEFAULT_BIAS_TOLERANCE);
+        assert_eq!(report.nibbles, [Some(false), None, None, None]);
+        assert_eq!(report.bias, BiasCheck::Unchecked { claimed: 0.5 });
+        assert_eq!(report.points(), (0, 7));
+    }
+
+    #[test]
+    fn dataset_header_does_not_depend_on_the_key() {
+        let header = |seed| {
+            let bytes = Challenge::new(AttackKind::Differential, seed).write_dataset(Vec::new(), 7).unwrap();
+            *DatasetReader::new(&bytes[..]).unwrap().header()
+        };
+        assert_eq!(header(1), header(2));
+        assert_eq!(header(1).seed, 7);
+    }
+}
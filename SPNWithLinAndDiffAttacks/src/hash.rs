@@ -0,0 +1,115 @@
+// Block-Cipher-Based Hashing
+// --------------------------
+//
+// A compression function built from the 16-bit SPN, iterated with the
+// Merkle-Damgard construction. The digest is only 16 bits, so a birthday
+// search finds a collision after roughly 2^8 messages.
+
+use std::collections::HashMap;
+
+use crate::spn::Spn;
+
+/// Length of the Merkle-Damgard length field in bytes
+const LENGTH_FIELD: usize = 8;
+
+/// How the cipher is turned into a compression function
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// H_i = E_{m_i}(H_{i-1}) ^ H_{i-1}, the message block is the 80-bit key
+    DaviesMeyer,
+    /// H_i = E_{g(H_{i-1})}(m_i) ^ m_i, the chaining value is the key
+    MatyasMeyerOseas,
+}
+
+impl Compression {
+    /// Message block size in bytes
+    pub fn block_size(self) -> usize {
+        match self {
+            Compression::DaviesMeyer => 10,
+            Compression::MatyasMeyerOseas => 2,
+        }
+    }
+
+    /// Apply the compression function to one message block
+    pub fn compress(self, chaining: u16, block: &[u8]) -> u16 {
+        assert_eq!(block.len(), self.block_size());
+        match self {
+            Compression::DaviesMeyer => {
+                let key = block.iter().fold(0u128, |acc, &b| (acc << 8) | b as u128);
+                Spn::new(key).encrypt(chaining) ^ chaining
+            }
+            Compression::MatyasMeyerOseas => {
+                let m = u16::from_be_bytes([block[0], block[1]]);
+                // g: use the chaining value as every round key
+                Spn::from_round_keys([chaining; 5]).encrypt(m) ^ m
+            }
+        }
+    }
+}
+
+/// Merkle-Damgard hash with length strengthening
+#[derive(Clone, Copy, Debug)]
+pub struct MdHash {
+    compression: Compression,
+    iv: u16,
+}
+
+impl MdHash {
+    pub fn new(compression: Compression) -> Self {
+        MdHash { compression, iv: 0x6A09 }
+    }
+
+    pub fn with_iv(compression: Compression, iv: u16) -> Self {
+        MdHash { compression, iv }
+    }
+
+    /// Pad with 0x80, zeros, and the 64-bit message length in bits
+    pub fn pad(&self, message: &[u8]) -> Vec<u8> {
+        let block_size = self.compression.block_size();
+        let mut padded = message.to_vec();
+        padded.push(0x80);
+        while !(padded.len() + LENGTH_FIELD).is_multiple_of(block_size) {
+            padded.push(0);
+        }
+        padded.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+        padded
+    }
+
+    pub fn hash(&self, message: &[u8]) -> u16 {
+        self.pad(message)
+            .chunks_exact(self.compression.block_size())
+            .fold(self.iv, |h, block| self.compression.compress(h, block))
+    }
+}
+
+/// Two distinct messages with the same digest
+#[derive(Clone, Debug)]
+pub struct Collision {
+    pub first: Vec<u8>,
+    pub second: Vec<u8>,
+    pub digest: u16,
+    /// Number of messages hashed before the collision appeared
+    pub attempts: u64,
+}
+
+/// Birthday search: hash distinct messages until two digests coincide
+///
+/// Messages are the 8-byte big-endian encodings of `seed`, `seed + 1`, ...
+/// so different seeds give independent runs.
+pub fn find_collision(hash: &MdHash, seed: u64) -> Collision {
+    let mut seen: HashMap<u16, u64> = HashMap::new();
+    for attempts in 1.. {
+        let counter = seed.wrapping_add(attempts - 1);
+        let digest = hash.hash(&counter.to_be_bytes());
+        if let Some(&previous) = seen.get(&digest) {
+            return Collision {
+                first: previous.to_be_bytes().to_vec(),
+                second: counter.to_be_bytes().to_vec(),
+                digest,
+                attempts,
+            };
+        }
+        seen.insert(digest, counter);
+    }
+    unreachable!("a 16-bit digest collides within 2^16 + 1 messages")
+}
@@ -0,0 +1,267 @@
+// Known-Answer Tests
+// ------------------
+//
+// Test vectors in the layout of NIST's CAVP response files, so ports of the
+// cipher to other languages can be checked against this crate with the
+// parsers they already have:
+//
+//   # comment
+//   [ENCRYPT]
+//
+//   COUNT = 0
+//   KEY = 1234567890ABCDEF1234
+//   PLAINTEXT = 0000
+//   CIPHERTEXT = 73C4
+//
+// KEY is the 80-bit master key, blocks are 16-bit, all in big-endian hex.
+// Vectors under [DECRYPT] list the ciphertext first, as CAVP files do. Every
+// implementation of the cipher in the crate can be verified against a file.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::masked::MaskedSpn;
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::{random_master_key, Backend, Spn};
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Which way a vector is applied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// One key, plaintext, ciphertext triple
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KatVector {
+    pub direction: Direction,
+    pub count: usize,
+    pub key: u128,
+    pub plaintext: u16,
+    pub ciphertext: u16,
+}
+
+/// `count` encryption and `count` decryption vectors: the all-zero and
+/// all-one keys and blocks first, then random keys and blocks from `seed`
+pub fn generate(count: usize, seed: u64) -> Vec<KatVector> {
+    let mut rng = SplitMix64::new(seed);
+    let fixed = [(0, 0x0000), ((1 << 80) - 1, 0xFFFF)];
+    let inputs: Vec<(u128, u16)> = fixed
+        .into_iter()
+        .chain(std::iter::repeat_with(|| (random_master_key(&mut rng), rng.next_u16())))
+        .take(count)
+        .collect();
+    let mut vectors = Vec::with_capacity(2 * count);
+    for direction in [Direction::Encrypt, Direction::Decrypt] {
+        for (i, &(key, block)) in inputs.iter().enumerate() {
+            let cipher = Spn::new(key);
+            let (plaintext, ciphertext) = match direction {
+                Direction::Encrypt => (block, cipher.encrypt(block)),
+                Direction::Decrypt => (cipher.decrypt(block), block),
+            };
+            vectors.push(KatVector { direction, count: i, key, plaintext, ciphertext });
+        }
+    }
+    vectors
+}
+
+/// Response-file text for `vectors`, a section header whenever the
+/// direction changes
+pub fn to_rsp(vectors: &[KatVector]) -> String {
+    let mut rsp = String::from("# 16-bit SPN known-answer tests\n# KEY is the 80-bit master key\n");
+    let mut direction = None;
+    for vector in vectors {
+        if direction != Some(vector.direction) {
+            direction = Some(vector.direction);
+            let name = if vector.direction == Direction::Encrypt { "ENCRYPT" } else { "DECRYPT" };
+            write!(rsp, "\n[{}]\n", name).unwrap();
+        }
+        write!(rsp, "\nCOUNT = {}\nKEY = {:020X}\n", vector.count, vector.key).unwrap();
+        match vector.direction {
+            Direction::Encrypt => writeln!(rsp, "PLAINTEXT = {:04X}\nCIPHERTEXT = {:04X}", vector.plaintext, vector.ciphertext),
+            Direction::Decrypt => writeln!(rsp, "CIPHERTEXT = {:04X}\nPLAINTEXT = {:04X}", vector.ciphertext, vector.plaintext),
+        }
+        .unwrap();
+    }
+    rsp
+}
+
+/// Vectors from response-file text. Fields may come in any order; a vector
+/// ends once it has all four, and vectors before any section header are
+/// encryptions.
+pub fn parse_rsp(text: &str) -> io::Result<Vec<KatVector>> {
+    let mut vectors = Vec::new();
+    let mut direction = Direction::Encrypt;
+    let (mut count, mut key, mut plaintext, mut ciphertext) = (None, None, None, None);
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            direction = match section {
+                "ENCRYPT" => Direction::Encrypt,
+                "DECRYPT" => Direction::Decrypt,
+                _ => return Err(invalid(&format!("unknown section [{}]", section))),
+            };
+            continue;
+        }
+        let (name, value) = line.split_once('=').ok_or_else(|| invalid(&format!("expected NAME = VALUE, got {}", line)))?;
+        let (name, value) = (name.trim(), value.trim());
+        let bad = || invalid(&format!("bad {} {}", name, value));
+        let hex = |digits: usize| value.len() == digits && value.bytes().all(|b| b.is_ascii_hexdigit());
+        match name {
+            "COUNT" => count = Some(value.parse().map_err(|_| bad())?),
+            "KEY" => {
+                if !hex(20) {
+                    return Err(bad());
+                }
+                key = Some(u128::from_str_radix(value, 16).map_err(|_| bad())?);
+            }
+            "PLAINTEXT" | "CIPHERTEXT" => {
+                if !hex(4) {
+                    return Err(bad());
+                }
+                let block = u16::from_str_radix(value, 16).map_err(|_| bad())?;
+                *(if name == "PLAINTEXT" { &mut plaintext } else { &mut ciphertext }) = Some(block);
+            }
+            _ => return Err(invalid(&format!("unknown field {}", name))),
+        }
+        if let (Some(c), Some(k), Some(p), Some(x)) = (count, key, plaintext, ciphertext) {
+            vectors.push(KatVector { direction, count: c, key: k, plaintext: p, ciphertext: x });
+            (count, key, plaintext, ciphertext) = (None, None, None, None);
+        }
+    }
+    if count.is_some() || key.is_some() || plaintext.is_some() || ciphertext.is_some() {
+        return Err(invalid("incomplete vector at end of file"));
+    }
+    Ok(vectors)
+}
+
+pub fn write_kat(path: &Path, vectors: &[KatVector]) -> io::Result<()> {
+    fs::write(path, to_rsp(vectors))
+}
+
+pub fn read_kat(path: &Path) -> io::Result<Vec<KatVector>> {
+    parse_rsp(&fs::read_to_string(path)?)
+}
+
+/// The implementations of the cipher in the crate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Implementation {
+    Scalar,
+    Lut,
+    ConstantTime,
+    Bitsliced,
+    Masked,
+}
+
+impl Implementation {
+    pub const ALL: [Implementation; 5] = [
+        Implementation::Scalar,
+        Implementation::Lut,
+        Implementation::ConstantTime,
+        Implementation::Bitsliced,
+        Implementation::Masked,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Implementation::Scalar => "scalar",
+            Implementation::Lut => "lut",
+            Implementation::ConstantTime => "constant-time",
+            Implementation::Bitsliced => "bitsliced",
+            Implementation::Masked => "masked",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|implementation| implementation.name() == name)
+    }
+
+    /// Apply `vector` in its direction and return the output block
+    pub fn apply(self, vector: &KatVector) -> u16 {
+        let cipher = Spn::new(vector.key);
+        let (input, encrypt) = match vector.direction {
+            Direction::Encrypt => (vector.plaintext, true),
+            Direction::Decrypt => (vector.ciphertext, false),
+        };
+        let backend = |backend| {
+            let cipher = cipher.clone().with_backend(backend);
+            if encrypt { cipher.encrypt(input) } else { cipher.decrypt(input) }
+        };
+        match self {
+            Implementation::Scalar => backend(Backend::Scalar),
+            Implementation::Lut => backend(Backend::Lut),
+            Implementation::ConstantTime => backend(Backend::ConstantTime),
+            Implementation::Bitsliced => {
+                let blocks = [input; 64];
+                if encrypt { cipher.encrypt_batch64(&blocks)[0] } else { cipher.decrypt_batch64(&blocks)[0] }
+            }
+            Implementation::Masked => {
+                let masked = MaskedSpn::new(cipher.round_keys().try_into().unwrap(), vector.key as u64);
+                if encrypt { masked.encrypt(input) } else { masked.decrypt(input) }
+            }
+        }
+    }
+}
+
+/// Vectors `implementation` gets wrong, with the block it produced
+pub fn verify(vectors: &[KatVector], implementation: Implementation) -> Vec<(KatVector, u16)> {
+    vectors
+        .iter()
+        .filter_map(|vector| {
+            let output = implementation.apply(vector);
+            let expected = match vector.direction {
+                Direction::Encrypt => vector.ciphertext,
+                Direction::Decrypt => vector.plaintext,
+            };
+            (output != expected).then_some((*vector, output))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written by `SPNWithLinAndDiffAttacks kat 8 vectors/spn16.rsp`
+    const REFERENCE: &str = include_str!("../vectors/spn16.rsp");
+
+    #[test]
+    fn reference_file_matches_the_generator() {
+        assert_eq!(parse_rsp(REFERENCE).unwrap(), generate(8, 1));
+        assert_eq!(to_rsp(&generate(8, 1)), REFERENCE);
+    }
+
+    #[test]
+    fn round_trips_through_rsp() {
+        let vectors = generate(20, 7);
+        assert_eq!(vectors.len(), 40);
+        assert_eq!(parse_rsp(&to_rsp(&vectors)).unwrap(), vectors);
+    }
+
+    #[test]
+    fn every_implementation_passes_the_reference() {
+        let vectors = parse_rsp(REFERENCE).unwrap();
+        for implementation in Implementation::ALL {
+            assert_eq!(verify(&vectors, implementation), [], "{}", implementation.name());
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_fields() {
+        let vector = |key: &str, plaintext: &str| format!("COUNT = 0\nKEY = {}\nPLAINTEXT = {}\nCIPHERTEXT = 0000\n", key, plaintext);
+        assert!(parse_rsp(&vector("1234567890ABCDEF1234", "0000")).is_ok());
+        assert!(parse_rsp(&vector("+234567890ABCDEF1234", "0000")).is_err());
+        assert!(parse_rsp(&vector("1234567890ABCDEF123", "0000")).is_err());
+        assert!(parse_rsp(&vector("1234567890ABCDEF1234", "+000")).is_err());
+        assert!(parse_rsp("[MONTE]\n").is_err());
+        assert!(parse_rsp("COUNT = 0\nKEY = 1234567890ABCDEF1234\n").is_err());
+    }
+}
@@ -0,0 +1,186 @@
+// Power-Leakage Simulation
+// ------------------------
+//
+// A device's power draw at each step of an encryption correlates with the
+// data it handles. The oracle below models that: every encryption also yields
+// one sample per intermediate state, either the Hamming weight of the state
+// (how many bits are set, as when a register is precharged to zero) or the
+// Hamming distance from the previous state (how many bits flip, as when a
+// register is overwritten), plus Gaussian noise.
+
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::{pbox, sbox_layer, Spn};
+
+/// Intermediate states recorded per encryption: the whitened input, then
+/// S-box, P-box and key addition for rounds 1-3, then the final S-box layer
+/// and key addition
+pub const TRACE_LEN: usize = 12;
+
+/// Index of the first-round S-box output, the usual side-channel target
+pub const FIRST_SBOX_OUTPUT: usize = 1;
+
+/// Every state the cipher passes through, in order
+pub fn intermediate_states(plaintext: u16, round_keys: &[u16]) -> [u16; TRACE_LEN] {
+    let mut states = [0u16; TRACE_LEN];
+    let mut state = plaintext ^ round_keys[0];
+    states[0] = state;
+    for (round, &round_key) in round_keys[1..4].iter().enumerate() {
+        state = sbox_layer(state);
+        states[1 + 3 * round] = state;
+        state = pbox(state);
+        states[2 + 3 * round] = state;
+        state ^= round_key;
+        states[3 + 3 * round] = state;
+    }
+    state = sbox_layer(state);
+    states[10] = state;
+    states[11] = state ^ round_keys[4];
+    states
+}
+
+/// A cipher implementation whose intermediate states can be probed
+pub trait LeakyCipher {
+    /// Encrypt and return the ciphertext together with the value the device
+    /// holds at each of the `TRACE_LEN` steps
+    fn encrypt_observed(&self, plaintext: u16) -> (u16, [u16; TRACE_LEN]);
+}
+
+impl LeakyCipher for Spn {
+    fn encrypt_observed(&self, plaintext: u16) -> (u16, [u16; TRACE_LEN]) {
+        let states = intermediate_states(plaintext, self.round_keys());
+        (states[TRACE_LEN - 1], states)
+    }
+}
+
+/// What a sample is proportional to
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LeakageModel {
+    /// Bits set in the state
+    #[default]
+    HammingWeight,
+    /// Bits flipped from the previous state (the plaintext for the first)
+    HammingDistance,
+    /// The state value itself, as with a perfect probe on the data bus;
+    /// the high nibble dominates, so the low ones are hard to attack
+    Identity,
+    /// Sum of per-bit weights of the set bits (index 0 = least significant
+    /// bit), for wires that do not all draw the same current
+    WeightedBits([f64; 16]),
+}
+
+impl LeakageModel {
+    /// Noise-free leakage of a single state, `previous` being the state
+    /// before it
+    #[inline]
+    pub fn leak_state(&self, previous: u16, state: u16) -> f64 {
+        match self {
+            LeakageModel::HammingWeight => state.count_ones() as f64,
+            LeakageModel::HammingDistance => (state ^ previous).count_ones() as f64,
+            LeakageModel::Identity => state as f64,
+            LeakageModel::WeightedBits(weights) => {
+                (0..16).filter(|&bit| (state >> bit) & 1 == 1).map(|bit| weights[bit]).sum()
+            }
+        }
+    }
+
+    /// Noise-free leakage of every intermediate state
+    pub fn leak(&self, plaintext: u16, states: &[u16; TRACE_LEN]) -> [f64; TRACE_LEN] {
+        let mut previous = plaintext;
+        states.map(|state| {
+            let value = self.leak_state(previous, state);
+            previous = state;
+            value
+        })
+    }
+
+    /// Variance of the leakage of a uniformly random state, the signal part
+    /// of the signal-to-noise ratio
+    pub fn signal_variance(&self) -> f64 {
+        match self {
+            // 16 independent fair bits
+            LeakageModel::HammingWeight | LeakageModel::HammingDistance => 4.0,
+            // Uniform over 0..2^16
+            LeakageModel::Identity => (65536.0f64 * 65536.0 - 1.0) / 12.0,
+            LeakageModel::WeightedBits(weights) => weights.iter().map(|w| w * w).sum::<f64>() / 4.0,
+        }
+    }
+
+    /// Noise standard deviation giving `snr` = signal variance / noise
+    /// variance; an infinite SNR means no noise
+    pub fn noise_sigma_for_snr(&self, snr: f64) -> f64 {
+        assert!(snr > 0.0, "SNR must be positive");
+        (self.signal_variance() / snr).sqrt()
+    }
+}
+
+/// One encryption as seen by an attacker probing the power line
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeakyEncryption {
+    pub plaintext: u16,
+    pub ciphertext: u16,
+    pub samples: [f64; TRACE_LEN],
+}
+
+/// Encryption oracle that also returns simulated power samples
+#[derive(Clone, Debug)]
+pub struct LeakageOracle<C = Spn, R = SplitMix64> {
+    cipher: C,
+    model: LeakageModel,
+    noise_sigma: f64,
+    rng: R,
+    queries: u64,
+}
+
+impl<C: LeakyCipher> LeakageOracle<C> {
+    /// `noise_sigma` is the standard deviation of the Gaussian noise added to
+    /// every sample; `seed` makes the noise reproducible
+    pub fn new(cipher: C, model: LeakageModel, noise_sigma: f64, seed: u64) -> Self {
+        Self::with_rng(cipher, model, noise_sigma, SplitMix64::new(seed))
+    }
+
+    /// Noise set from a target signal-to-noise ratio instead of a standard
+    /// deviation
+    pub fn with_snr(cipher: C, model: LeakageModel, snr: f64, seed: u64) -> Self {
+        Self::new(cipher, model, model.noise_sigma_for_snr(snr), seed)
+    }
+}
+
+impl<C: LeakyCipher, R: RandomSource> LeakageOracle<C, R> {
+    /// `new` with the noise drawn from `rng`
+    pub fn with_rng(cipher: C, model: LeakageModel, noise_sigma: f64, rng: R) -> Self {
+        assert!(noise_sigma >= 0.0, "noise standard deviation must not be negative");
+        LeakageOracle { cipher, model, noise_sigma, rng, queries: 0 }
+    }
+
+    pub fn model(&self) -> LeakageModel {
+        self.model
+    }
+
+    /// Signal variance over noise variance
+    pub fn snr(&self) -> f64 {
+        self.model.signal_variance() / (self.noise_sigma * self.noise_sigma)
+    }
+
+    pub fn noise_sigma(&self) -> f64 {
+        self.noise_sigma
+    }
+
+    pub fn queries(&self) -> u64 {
+        self.queries
+    }
+
+    pub fn encrypt(&mut self, plaintext: u16) -> LeakyEncryption {
+        self.queries += 1;
+        let (ciphertext, states) = self.cipher.encrypt_observed(plaintext);
+        let mut samples = self.model.leak(plaintext, &states);
+        for sample in &mut samples {
+            *sample += self.noise_sigma * self.rng.next_gaussian();
+        }
+        LeakyEncryption { plaintext, ciphertext, samples }
+    }
+
+    /// Encrypt every plaintext and keep the traces
+    pub fn collect(&mut self, plaintexts: impl IntoIterator<Item = u16>) -> Vec<LeakyEncryption> {
+        plaintexts.into_iter().map(|plaintext| self.encrypt(plaintext)).collect()
+    }
+}
@@ -0,0 +1,62 @@
+//! A toy 16-bit substitution-permutation network together with the linear
+//! and differential cryptanalysis that breaks it, plus the modes and
+//! constructions built on top of the cipher.
+
+pub mod aead;
+pub mod bench;
+pub mod birthday;
+pub mod bitslice;
+pub mod cipher;
+pub mod cmac;
+pub mod columns;
+pub mod complexity;
+pub mod constant_time;
+pub mod cpa;
+#[cfg(feature = "tui")]
+pub mod dashboard;
+pub mod dataset;
+pub mod differential;
+pub mod display;
+pub mod fault;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fpe;
+pub mod grading;
+pub mod hash;
+pub mod image;
+pub mod kat;
+pub mod kdf;
+pub mod leakage;
+pub mod linear;
+pub mod lut;
+pub mod masked;
+pub mod milp;
+pub mod modes;
+pub mod multi_key;
+pub mod nonce_reuse;
+pub mod oracle;
+pub mod padding;
+pub mod pair_file;
+pub mod pairs;
+pub mod padding_oracle;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod quiz;
+pub mod remote;
+pub mod results;
+pub mod rng;
+pub mod sat;
+pub mod sbox;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod spec_export;
+pub mod spn;
+pub mod sponge;
+pub mod template;
+pub mod testkit;
+pub mod timing;
+pub mod trace_io;
+pub mod tvla;
+pub mod walkthrough;
+#[cfg(feature = "wasm")]
+pub mod wasm;
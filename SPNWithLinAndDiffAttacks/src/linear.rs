@@ -0,0 +1,175 @@
+// Linear Attack Implementation
+// ----------------------------
+
+use crate::columns::{self, KnownPairs};
+use crate::sbox::Sbox;
+use crate::spn::{nibble, partial_decryption_table};
+
+/// Compute the bias of a linear approximation for the S-box
+/// `a`: input mask (4 bits), `b`: output mask (4 bits)
+/// Returns: bias = (count_matches / 16.0) - 0.5, read from the shared LAT
+pub fn linear_bias_sbox(a: u8, b: u8) -> f32 {
+    Sbox::present().linear_bias(a, b)
+}
+
+/// Count, for each candidate key nibble (0-15), the pairs for which the
+/// linear approximation holds
+///
+/// With the `parallel` feature the pairs are split across threads, each
+/// with its own counters, and the counters are summed at the end.
+pub fn linear_counts(pairs: &[(u16, u16)], alpha: u16, beta: u16, nibble_idx: usize) -> [u64; 16] {
+    #[cfg(feature = "parallel")]
+    return crate::parallel::count_in_parallel(pairs, |chunk| linear_counts_from_iter(chunk.iter().copied(), alpha, beta, nibble_idx));
+    #[cfg(not(feature = "parallel"))]
+    linear_counts_from_iter(pairs.iter().copied(), alpha, beta, nibble_idx)
+}
+
+/// `linear_counts` over pairs stored column-wise
+pub fn linear_counts_columns(pairs: &KnownPairs, alpha: u16, beta: u16, nibble_idx: usize) -> [u64; 16] {
+    #[cfg(feature = "parallel")]
+    return crate::parallel::count_ranges_in_parallel(pairs.len(), |range| {
+        linear_counts_from_iter(pairs.iter_range(range), alpha, beta, nibble_idx)
+    });
+    #[cfg(not(feature = "parallel"))]
+    linear_counts_from_iter(pairs.iter_range(0..pairs.len()), alpha, beta, nibble_idx)
+}
+
+/// <beta_nibble, SBOX_INV[c ^ candidate]> for every candidate (row) and
+/// ciphertext nibble (column), so each pair only needs a lookup
+fn output_parities(beta_nibble: u8) -> [[u8; 16]; 16] {
+    partial_decryption_table().map(|row| row.map(|v| ((beta_nibble & v).count_ones() % 2) as u8))
+}
+
+/// `linear_counts` over pairs from any source, e.g. a file being streamed
+pub fn linear_counts_from_iter(pairs: impl Iterator<Item = (u16, u16)>, alpha: u16, beta: u16, nibble_idx: usize) -> [u64; 16] {
+    let parities = output_parities(nibble(beta, nibble_idx));
+
+    // Each pair packs to <alpha, plain> in bit 4 and the target ciphertext
+    // nibble below it
+    let pack = |(plain, cipher): (u16, u16)| {
+        let alpha_dot = ((alpha & plain).count_ones() % 2) as u8;
+        let cipher_nibble = nibble(cipher, nibble_idx);
+        Some((alpha_dot << 4) | cipher_nibble)
+    };
+    columns::count_chunked(pairs, pack, |chunk, counts| {
+        for (candidate, parity) in parities.iter().enumerate() {
+            // Check if linear approximation holds (mod 2)
+            let holds = chunk.iter().filter(|&&packed| packed >> 4 == parity[(packed & 0xF) as usize]).count();
+            counts[candidate] += holds as u32;
+        }
+    })
+}
+
+/// Perform a linear attack to recover part of the last round key
+/// `pairs`: vector of (plaintext, ciphertext) pairs
+/// `alpha`: input mask for plaintext
+/// `beta`: mask for the input to the last S-box layer
+/// `nibble_idx`: which nibble (0-3) of the last round key to attack
+/// Returns: candidate key nibble with the highest bias magnitude
+pub fn linear_attack(pairs: &[(u16, u16)], alpha: u16, beta: u16, nibble_idx: usize) -> u8 {
+    let counts = linear_counts(pairs, alpha, beta, nibble_idx);
+
+    // Find candidate with bias closest to expected (max deviation from 50%)
+    let total = pairs.len() as f32;
+    let mut best_bias = -1.0;
+    let mut best_candidate = 0;
+    for (candidate, &count) in counts.iter().enumerate() {
+        let bias = (count as f32 / total - 0.5).abs();
+        if bias > best_bias {
+            best_bias = bias;
+            best_candidate = candidate;
+        }
+    }
+    best_candidate as u8
+}
+
+/// All candidates for nibble `nibble_idx` with their bias magnitude
+/// |count / n - 0.5|, best first
+pub fn linear_ranking(pairs: &[(u16, u16)], alpha: u16, beta: u16, nibble_idx: usize) -> [(u8, f32); 16] {
+    let counts = linear_counts(pairs, alpha, beta, nibble_idx);
+    let total = pairs.len().max(1) as f32;
+    let mut ranking: [(u8, f32); 16] =
+        std::array::from_fn(|candidate| (candidate as u8, (counts[candidate] as f32 / total - 0.5).abs()));
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranking
+}
+
+/// Linear attack that keeps its counters between pairs, for oracles that
+/// hand out pairs one at a time
+///
+/// Scores are the bias magnitude |count / n - 0.5| of each candidate.
+#[derive(Clone, Debug)]
+pub struct IncrementalLinearAttack {
+    alpha: u16,
+    nibble_idx: usize,
+    parities: [[u8; 16]; 16],
+    counts: [u64; 16],
+    pairs_seen: u64,
+}
+
+impl IncrementalLinearAttack {
+    pub fn new(alpha: u16, beta: u16, nibble_idx: usize) -> Self {
+        let parities = output_parities(nibble(beta, nibble_idx));
+        IncrementalLinearAttack { alpha, nibble_idx, parities, counts: [0; 16], pairs_seen: 0 }
+    }
+
+    /// Update the counters with one (plaintext, ciphertext) pair
+    pub fn feed(&mut self, (plain, cipher): (u16, u16)) {
+        let alpha_dot = ((self.alpha & plain).count_ones() % 2) as u8;
+        let cipher_nibble = nibble(cipher, self.nibble_idx) as usize;
+        for (count, parity) in self.counts.iter_mut().zip(&self.parities) {
+            if alpha_dot == parity[cipher_nibble] {
+                *count += 1;
+            }
+        }
+        self.pairs_seen += 1;
+    }
+
+    pub fn pairs_seen(&self) -> u64 {
+        self.pairs_seen
+    }
+
+    pub fn counts(&self) -> &[u64; 16] {
+        &self.counts
+    }
+
+    /// All candidates with their bias magnitude, best first
+    pub fn current_ranking(&self) -> [(u8, f32); 16] {
+        let total = self.pairs_seen.max(1) as f32;
+        let mut ranking: [(u8, f32); 16] =
+            std::array::from_fn(|candidate| (candidate as u8, (self.counts[candidate] as f32 / total - 0.5).abs()));
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranking
+    }
+
+    /// Whether the leader's bias exceeds the runner-up's by at least `z`
+    /// standard deviations of a wrong-key count (sqrt(n) / 2)
+    pub fn is_separated(&self, z: f64) -> bool {
+        if self.pairs_seen == 0 {
+            return false;
+        }
+        let half = self.pairs_seen as f64 / 2.0;
+        let mut deviations = self.counts.map(|count| (count as f64 - half).abs());
+        deviations.sort_by(|a, b| b.total_cmp(a));
+        deviations[0] - deviations[1] >= z * (self.pairs_seen as f64).sqrt() / 2.0
+    }
+}
+
+/// Find best linear approximation for S-box
+pub fn find_best_linear_approximation() -> (u8, u8, f32) {
+    let mut best_bias = -1.0;
+    let mut best_input = 0;
+    let mut best_output = 0;
+
+    for input_mask in 1..16u8 {
+        for output_mask in 1..16u8 {
+            let bias = linear_bias_sbox(input_mask, output_mask).abs();
+            if bias > best_bias {
+                best_bias = bias;
+                best_input = input_mask;
+                best_output = output_mask;
+            }
+        }
+    }
+    (best_input, best_output, best_bias)
+}
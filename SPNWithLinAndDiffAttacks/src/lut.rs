@@ -0,0 +1,92 @@
+// Full-Round Lookup Tables
+// ------------------------
+//
+// The state is only 16 bits, so a whole unkeyed round (S-box layer then
+// P-box) fits in a 65,536-entry table, as does its inverse. The tables do not
+// depend on the key, so they are built once per process on first use and
+// shared by every cipher instance (2 x 128 KB). The final round has no P-box
+// and uses a 256-entry byte-wise S-box table instead.
+
+use std::sync::OnceLock;
+
+use crate::spn::{pbox, sbox_inv_layer, sbox_layer, SBOX, SBOX_INV};
+
+/// Key-independent tables for the whole round and the final S-box layer
+pub struct RoundTables {
+    /// pbox(sbox_layer(x))
+    pub round: Vec<u16>,
+    /// sbox_inv_layer(pbox(x)), the inverse of `round`
+    pub inverse_round: Vec<u16>,
+    /// Both nibbles of a byte through the S-box
+    pub sbox_byte: [u8; 256],
+    pub sbox_inv_byte: [u8; 256],
+}
+
+impl RoundTables {
+    fn build() -> Self {
+        let round = (0..=u16::MAX).map(|x| pbox(sbox_layer(x))).collect();
+        let inverse_round = (0..=u16::MAX).map(|x| sbox_inv_layer(pbox(x))).collect();
+        let mut sbox_byte = [0u8; 256];
+        let mut sbox_inv_byte = [0u8; 256];
+        for x in 0..256 {
+            sbox_byte[x] = (SBOX[x >> 4] << 4) | SBOX[x & 0xF];
+            sbox_inv_byte[x] = (SBOX_INV[x >> 4] << 4) | SBOX_INV[x & 0xF];
+        }
+        RoundTables { round, inverse_round, sbox_byte, sbox_inv_byte }
+    }
+
+    /// Final S-box layer via two byte lookups
+    #[inline]
+    pub fn sbox_layer(&self, state: u16) -> u16 {
+        let [hi, lo] = state.to_be_bytes();
+        u16::from_be_bytes([self.sbox_byte[hi as usize], self.sbox_byte[lo as usize]])
+    }
+
+    #[inline]
+    pub fn sbox_inv_layer(&self, state: u16) -> u16 {
+        let [hi, lo] = state.to_be_bytes();
+        u16::from_be_bytes([self.sbox_inv_byte[hi as usize], self.sbox_inv_byte[lo as usize]])
+    }
+}
+
+/// The shared tables, built on first call
+pub fn tables() -> &'static RoundTables {
+    static TABLES: OnceLock<RoundTables> = OnceLock::new();
+    TABLES.get_or_init(RoundTables::build)
+}
+
+/// Table-driven equivalent of `spn::encrypt`
+#[inline]
+pub fn encrypt(plaintext: u16, round_keys: &[u16]) -> u16 {
+    let tables = tables();
+    let mut state = plaintext ^ round_keys[0];
+    for &round_key in &round_keys[1..4] {
+        state = tables.round[state as usize] ^ round_key;
+    }
+    tables.sbox_layer(state) ^ round_keys[4]
+}
+
+/// Table-driven equivalent of `spn::decrypt`
+#[inline]
+pub fn decrypt(ciphertext: u16, round_keys: &[u16]) -> u16 {
+    let tables = tables();
+    let mut state = tables.sbox_inv_layer(ciphertext ^ round_keys[4]);
+    for &round_key in round_keys[1..4].iter().rev() {
+        state = tables.inverse_round[(state ^ round_key) as usize];
+    }
+    state ^ round_keys[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_tables_match_the_sbox_layers() {
+        let tables = tables();
+        for state in 0..=u16::MAX {
+            assert_eq!(tables.sbox_layer(state), sbox_layer(state));
+            assert_eq!(tables.sbox_inv_layer(state), sbox_inv_layer(state));
+        }
+    }
+}
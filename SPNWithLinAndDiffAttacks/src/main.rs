@@ -1,270 +1,59 @@
-// PRESENT S-box (4-bit to 4-bit)
-const SBOX: [u8; 16] = [
-    0xC, 0x5, 0x6, 0xB, 0x9, 0x0, 0xA, 0xD, 
-    0x3, 0xE, 0xF, 0x8, 0x4, 0x7, 0x1, 0x2,
-];
-
-// Inverse PRESENT S-box
-const SBOX_INV: [u8; 16] = [
-    0x5, 0xE, 0xF, 0x8, 0xC, 0x1, 0x2, 0xD, 
-    0xB, 0x4, 0x6, 0x3, 0x0, 0x7, 0x9, 0xA,
-];
-
-/// Apply the S-box to each nibble (4-bit chunk) in a 16-bit word
-fn sbox_layer(state: u16) -> u16 {
-    let mut output = 0;
-    for i in 0..4 {
-        let nibble = (state >> (i * 4)) as u8 & 0xF;
-        let substituted = SBOX[nibble as usize] as u16;
-        output |= substituted << (i * 4);
-    }
-    output
-}
-
-/// Apply the inverse S-box to each nibble in a 16-bit word
-fn sbox_inv_layer(state: u16) -> u16 {
-    let mut output = 0;
-    for i in 0..4 {
-        let nibble = (state >> (i * 4)) as u8 & 0xF;
-        let substituted = SBOX_INV[nibble as usize] as u16;
-        output |= substituted << (i * 4);
-    }
-    output
-}
-
-/// Bit permutation (transposition of a 4x4 bit matrix)
-fn pbox(state: u16) -> u16 {
-    let mut output = 0;
-    // Transpose bits: original bit i goes to position (i % 4) * 4 + (i / 4)
-    for i in 0..16 {
-        let bit = (state >> i) & 1;
-        let j = (i % 4) * 4 + (i / 4);
-        output |= bit << j;
-    }
-    output
-}
-
-/// Generate round keys from a master key (80 bits stored in u128)
-fn expand_key(master_key: u128, rounds: usize) -> Vec<u16> {
-    (0..rounds).map(|i| {
-        // Extract 16-bit chunks from the master key (shift right by 64, 48, 32, 16, 0 bits)
-        (master_key >> (80 - 16 * (i + 1))) as u16
-    }).collect()
-}
-
-/// Encrypt a 16-bit block using the SPN
-fn encrypt(plaintext: u16, round_keys: &[u16]) -> u16 {
-    let mut state = plaintext;
-    // Initial whitening
-    state ^= round_keys[0];
-    
-    // Rounds 1 to 3: S-box, P-box, XOR round key
-    for i in 1..4 {
-        state = sbox_layer(state);
-        state = pbox(state);
-        state ^= round_keys[i];
-    }
-    
-    // Final round: S-box and last key XOR (no P-box)
-    state = sbox_layer(state);
-    state ^= round_keys[4];
-    state
-}
-
-/// Decrypt a 16-bit block using the SPN
-fn decrypt(ciphertext: u16, round_keys: &[u16]) -> u16 {
-    let mut state = ciphertext;
-    // Reverse final round
-    state ^= round_keys[4];
-    state = sbox_inv_layer(state);
-    
-    // Rounds 3 to 1: XOR round key, inverse P-box, inverse S-box
-    for i in (1..4).rev() {
-        state ^= round_keys[i];
-        state = pbox(state); // P-box is its own inverse
-        state = sbox_inv_layer(state);
-    }
-    
-    // Reverse initial whitening
-    state ^= round_keys[0];
-    state
-}
-
-// Linear Attack Implementation
-// ----------------------------
-
-/// Compute the bias of a linear approximation for the S-box
-/// `a`: input mask (4 bits), `b`: output mask (4 bits)
-/// Returns: bias = (count_matches / 16.0) - 0.5
-fn linear_bias_sbox(a: u8, b: u8) -> f32 {
-    let mut count = 0;
-    for x in 0..16 {
-        // Compute <a, x> and <b, sbox(x)>
-        let input_dot = (a as u16 & x).count_ones() % 2;
-        let output_dot = (b as u16 & SBOX[x as usize] as u16).count_ones() % 2;
-        if input_dot == output_dot {
-            count += 1;
-        }
-    }
-    (count as f32 / 16.0) - 0.5
-}
-
-/// Perform a linear attack to recover part of the last round key
-/// `pairs`: vector of (plaintext, ciphertext) pairs
-/// `alpha`: input mask for plaintext
-/// `beta`: mask for the input to the last S-box layer
-/// `nibble_idx`: which nibble (0-3) of the last round key to attack
-/// Returns: candidate key nibble with the highest bias magnitude
-fn linear_attack(pairs: &[(u16, u16)], alpha: u16, beta: u16, nibble_idx: usize) -> u8 {
-    // Shift beta to align with the target nibble
-    let beta_nibble = ((beta >> (4 * nibble_idx)) & 0xF) as u8;
-    let mut counts = [0; 16]; // Counts for each candidate key nibble (0-15)
-    
-    for (plain, cipher) in pairs {
-        // Plaintext linear part: <alpha, plain>
-        let alpha_dot = (alpha & *plain).count_ones() % 2;
-        
-        // Test each candidate key for the target nibble
-        for candidate in 0..16 {
-            // Extract target ciphertext nibble and XOR candidate key
-            let cipher_nibble = (cipher >> (4 * nibble_idx)) & 0xF;
-            let u = cipher_nibble ^ candidate as u16;
-            // Apply inverse S-box to the nibble
-            let v = SBOX_INV[u as usize] as u16;
-            // Compute <beta_nibble, v>
-            let beta_dot = (beta_nibble as u16 & v).count_ones() % 2;
-            
-            // Check if linear approximation holds (mod 2)
-            if (alpha_dot + beta_dot) % 2 == 0 {
-                counts[candidate as usize] += 1;
-            }
-        }
-    }
-    
-    // Find candidate with bias closest to expected (max deviation from 50%)
-    let total = pairs.len() as f32;
-    let mut best_bias = -1.0;
-    let mut best_candidate = 0;
-    for (candidate, &count) in counts.iter().enumerate() {
-        let bias = (count as f32 / total - 0.5).abs();
-        if bias > best_bias {
-            best_bias = bias;
-            best_candidate = candidate;
-        }
-    }
-    best_candidate as u8
-}
-
-// Differential Attack Implementation
-// ---------------------------------
-
-/// Compute the probability of an S-box differential
-/// `delta_in`: input difference (4 bits), `delta_out`: output difference (4 bits)
-/// Returns: probability = count / 16
-fn diff_prob_sbox(delta_in: u8, delta_out: u8) -> f32 {
-    let mut count = 0;
-    for x in 0..16 {
-        if SBOX[x as usize] ^ SBOX[(x ^ delta_in) as usize] == delta_out {
-            count += 1;
-        }
-    }
-    count as f32 / 16.0
-}
-
-/// Perform a differential attack to recover part of the last round key
-/// `pairs`: vector of (plaintext1, plaintext2, ciphertext1, ciphertext2) tuples
-/// `delta_p`: input difference for plaintexts
-/// `delta_u`: expected difference before last S-box
-/// `nibble_idx`: target nibble index in the last round key
-/// Returns: candidate key nibble with the highest count
-fn differential_attack(
-    pairs: &[(u16, u16, u16, u16)],
-    delta_p: u16,
-    delta_u: u16,
-    nibble_idx: usize,
-) -> u8 {
-    // Extract target nibble from expected difference
-    let delta_u_nibble = (delta_u >> (4 * nibble_idx)) & 0xF;
-    let mut counts = [0; 16]; // Counts for each candidate key
-    
-    for (p1, p2, c1, c2) in pairs {
-        // Filter pairs with correct input difference
-        if p1 ^ p2 != delta_p {
-            continue;
-        }
-        
-        // Target nibble in ciphertexts
-        let c1_nib = (c1 >> (4 * nibble_idx)) & 0xF;
-        let c2_nib = (c2 >> (4 * nibble_idx)) & 0xF;
-        
-        // Test each candidate key
-        for candidate in 0..16 {
-            // Apply candidate key and inverse S-box
-            let v1 = SBOX_INV[(c1_nib ^ candidate) as usize];
-            let v2 = SBOX_INV[(c2_nib ^ candidate) as usize];
-            // Check output difference
-            if v1 ^ v2 == delta_u_nibble as u8 {
-                counts[candidate as usize] += 1;
-            }
-        }
-    }
-    
-    // Find candidate with the highest count
-    counts
-        .iter()
-        .enumerate()
-        .max_by_key(|&(_, count)| count)
-        .map(|(candidate, _)| candidate as u8)
-        .unwrap()
-}
+use std::path::Path;
 
-/// Find best linear approximation for S-box
-fn find_best_linear_approximation() -> (u8, u8, f32) {
-    let mut best_bias = -1.0;
-    let mut best_input = 0;
-    let mut best_output = 0;
-    
-    for input_mask in 1..16u8 {
-        for output_mask in 1..16u8 {
-            let bias = linear_bias_sbox(input_mask, output_mask).abs();
-            if bias > best_bias {
-                best_bias = bias;
-                best_input = input_mask;
-                best_output = output_mask;
-            }
-        }
-    }
-    (best_input, best_output, best_bias)
-}
-
-/// Find best differential characteristic for S-box
-fn find_best_differential() -> (u8, u8, f32) {
-    let mut best_prob = -1.0;
-    let mut best_input = 0;
-    let mut best_output = 0;
-    
-    for input_diff in 1..16u8 {
-        for output_diff in 0..16u8 {
-            let prob = diff_prob_sbox(input_diff, output_diff);
-            if prob > best_prob {
-                best_prob = prob;
-                best_input = input_diff;
-                best_output = output_diff;
-            }
-        }
-    }
-    (best_input, best_output, best_prob)
-}
+use spn_attacks::aead::EncryptThenMac;
+use spn_attacks::bench;
+use spn_attacks::birthday::{cbc_birthday_experiment, ctr_elimination_experiment};
+use spn_attacks::cipher::BlockCipher;
+use spn_attacks::complexity::{estimate, Timing, DATA_FACTOR};
+use spn_attacks::cpa::{cpa_recover_whitening_key, snr_sweep};
+use spn_attacks::dataset::{ConfigHasher, DatasetHeader, DatasetKind, DatasetReader, DatasetWriter};
+use spn_attacks::differential::{differential_attack, differential_ranking, find_best_differential};
+use spn_attacks::display::TableView;
+use spn_attacks::fault::{dfa_last_round_key, last_round_key_from_skip, Fault, FaultyDevice, Layer};
+use spn_attacks::grading::{grade, Challenge, Submission, DEFAULT_BIAS_TOLERANCE};
+use spn_attacks::hash::{find_collision, Compression, MdHash};
+use spn_attacks::image::write_mode_comparison;
+use spn_attacks::kat::{generate, read_kat, verify, write_kat, Implementation};
+use spn_attacks::leakage::{LeakageModel, LeakageOracle};
+use spn_attacks::linear::{find_best_linear_approximation, linear_attack, linear_ranking};
+use spn_attacks::masked::MaskedSpn;
+use spn_attacks::milp::{active_sbox_model, parse_solution, Propagation};
+use spn_attacks::modes::{Cbc, Ctr, Ecb};
+use spn_attacks::multi_key::{expected_trials, multi_target_experiment, MAX_KEY_BITS};
+use spn_attacks::nonce_reuse::{crib_drag, xor_ciphertexts};
+use spn_attacks::padding_oracle::{padding_oracle_attack, CbcPaddingServer};
+use spn_attacks::pairs::{
+    chosen_plaintext_pairs, chosen_plaintext_pairs_from, known_plaintext_pairs, known_plaintext_pairs_from, known_plaintext_pairs_with,
+};
+use spn_attacks::quiz::{mark, problem_set, problem_sheet, solution_sheet};
+use spn_attacks::remote::{OracleServer, RemoteOracle, ServerLimits};
+use spn_attacks::rng::{log_to_text, parse_log, RecordingRng, ReplayRng, SplitMix64};
+use spn_attacks::results::{AttackKind, AttackResult, ExperimentSummary, Trail};
+use spn_attacks::sat::{key_recovery_cnf, recover_round_keys};
+use spn_attacks::sbox::Sbox;
+use spn_attacks::spec_export::{python_script, sage_script};
+use spn_attacks::spn::{decrypt, encrypt, expand_key, nibble, pbox, random_master_key, Backend, Spn};
+use spn_attacks::template::Templates;
+use spn_attacks::testkit::{self, check_bit_permutation, check_block_cipher, check_ddt, check_lat, check_leaky_cipher, check_permutation, check_sbox_bijective, for_all};
+use spn_attacks::trace_io::{read_csv, read_npy, write_csv, write_npy};
+use spn_attacks::tvla::fixed_vs_random;
+use spn_attacks::walkthrough::{differential_trail, differential_walkthrough, linear_trail, linear_walkthrough};
 
 // Main Function for Demonstration
 // ------------------------------
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(command) = args.get(1) {
+        run_command(command, &args[2..]);
+        return;
+    }
+
     // Example master key (80 bits) and round key generation
     let master_key: u128 = 0x1234_5678_90AB_CDEF_1234;
     let round_keys = expand_key(master_key, 5);
     println!("Master Key: {:X}", master_key);
     println!("Round Keys: {:?}", round_keys.iter().map(|k| format!("{:04X}", k)).collect::<Vec<_>>());
-    
+
     // Test encryption/decryption
     let plaintext: u16 = 0xABCD;
     let ciphertext = encrypt(plaintext, &round_keys);
@@ -273,73 +62,650 @@ fn main() {
     println!("Ciphertext: {:04X}", ciphertext);
     println!("Decrypted:  {:04X}", decrypted);
     assert_eq!(plaintext, decrypted);
-    
+
     // Analyze S-box properties
     let (best_in_lin, best_out_lin, best_bias) = find_best_linear_approximation();
     println!("\nS-box Linear Analysis:");
     println!("Best linear approximation: input mask {:X}, output mask {:X}, bias: {:.4}", 
              best_in_lin, best_out_lin, best_bias);
-    
+
     let (best_in_diff, best_out_diff, best_prob) = find_best_differential();
     println!("Best differential characteristic: input diff {:X}, output diff {:X}, probability: {:.4}", 
              best_in_diff, best_out_diff, best_prob);
-    
+
     // Linear Attack Demo
     // -----------------
     // Use best linear approximation for attack
     let alpha = (best_in_lin as u16) << 4; // Apply to second nibble
     let beta = (best_out_lin as u16) << 8; // Apply to third nibble
     let nibble_idx = 2; // Target third nibble (0-3)
-    
+
     println!("\nUsing linear approximation with bias {:.4} for attack", best_bias);
     println!("Alpha mask: {:04X}, Beta mask: {:04X}, Target nibble: {}", alpha, beta, nibble_idx);
-    
-    // Generate plaintext-ciphertext pairs
+
+    // Generate plaintext-ciphertext pairs with the table-driven backend
+    let lut_cipher = Spn::new(master_key).with_backend(Backend::Lut);
     let num_pairs = 10000;
     let mut pairs = Vec::new();
     for i in 0..num_pairs {
         let plain = i as u16; // Simple plaintexts
-        let cipher = encrypt(plain, &round_keys);
+        let cipher = lut_cipher.encrypt(plain);
         pairs.push((plain, cipher));
     }
-    
+
     // Recover part of the last round key
     let recovered_nibble = linear_attack(&pairs, alpha, beta, nibble_idx);
     println!("\nLinear Attack Result:");
     println!("Recovered key nibble {}: {:X}", nibble_idx, recovered_nibble);
-    
+
     // Extract actual last round key nibble for verification
     let actual_key_nibble = (round_keys[4] >> (4 * nibble_idx)) & 0xF;
     println!("Actual key nibble {}:    {:X}", nibble_idx, actual_key_nibble);
-    
+
     // Differential Attack Demo
     // -----------------------
     // Use best differential characteristic for attack
     let delta_p = (best_in_diff as u16) << 4; // Apply to second nibble
     let delta_u = (best_out_diff as u16) << 4; // Apply to second nibble
     let nibble_idx = 1;   // Target second nibble
-    
+
     println!("\nUsing differential with probability {:.4} for attack", best_prob);
     println!("Input difference: {:04X}, Expected output difference: {:04X}, Target nibble: {}", 
              delta_p, delta_u, nibble_idx);
-    
+
     // Generate chosen plaintext pairs with fixed difference
     let num_pairs = 5000;
     let mut pairs = Vec::new();
     for i in 0..num_pairs {
         let p1 = i as u16;
         let p2 = p1 ^ delta_p;
-        let c1 = encrypt(p1, &round_keys);
-        let c2 = encrypt(p2, &round_keys);
+        let c1 = lut_cipher.encrypt(p1);
+        let c2 = lut_cipher.encrypt(p2);
         pairs.push((p1, p2, c1, c2));
     }
-    
+
     // Recover part of the last round key
     let recovered_nibble = differential_attack(&pairs, delta_p, delta_u, nibble_idx);
     println!("\nDifferential Attack Result:");
     println!("Recovered key nibble {}: {:X}", nibble_idx, recovered_nibble);
-    
+
     // Extract actual last round key nibble for verification
     let actual_key_nibble = (round_keys[4] >> (4 * nibble_idx)) & 0xF;
     println!("Actual key nibble {}:    {:X}", nibble_idx, actual_key_nibble);
+
+    // Modes of Operation Demo
+    // -----------------------
+    // A message of repeated blocks: ECB leaks the repetition, CBC hides it
+    let cipher = Spn::new(master_key);
+    let message = b"ABABABABABABABAB".to_vec();
+
+    let mut ecb_ciphertext = message.clone();
+    Ecb::new(cipher.clone()).encrypt(&mut ecb_ciphertext).unwrap();
+
+    let iv = [0x3C, 0x5A];
+    let mut cbc_ciphertext = message.clone();
+    Cbc::new(cipher.clone(), &iv).encrypt(&mut cbc_ciphertext).unwrap();
+
+    println!("\nModes of Operation ({}-byte blocks):", Spn::BLOCK_SIZE);
+    println!("Message:        {}", String::from_utf8_lossy(&message));
+    println!("ECB ciphertext: {}", to_hex(&ecb_ciphertext));
+    println!("CBC ciphertext: {}", to_hex(&cbc_ciphertext));
+
+    let mut decrypted = cbc_ciphertext;
+    Cbc::new(cipher, &iv).decrypt(&mut decrypted).unwrap();
+    assert_eq!(decrypted, message);
+
+    // Hash Collision Demo
+    // -------------------
+    // A 16-bit digest falls to a birthday search after about 2^8 messages
+    println!("\nDavies-Meyer Hash Collision:");
+    let hash = MdHash::new(Compression::DaviesMeyer);
+    let collision = find_collision(&hash, 0);
+    println!("H({}) = H({}) = {:04X} after {} messages",
+             to_hex(&collision.first), to_hex(&collision.second), collision.digest, collision.attempts);
+    assert_eq!(hash.hash(&collision.first), hash.hash(&collision.second));
+
+    // Authenticated Encryption Demo
+    // -----------------------------
+    // Encrypt-then-MAC rejects any change to the ciphertext or associated data
+    let aead = EncryptThenMac::new(Spn::new(master_key), Spn::new(0xFEDC_BA98_7654_3210_ABCD));
+    let nonce = [0x00, 0x01];
+    let sealed = aead.seal(&nonce, b"header", b"attack at dawn");
+    println!("\nEncrypt-then-MAC:");
+    println!("Sealed: {}", to_hex(&sealed));
+    assert_eq!(aead.open(&nonce, b"header", &sealed).unwrap(), b"attack at dawn");
+
+    let mut tampered = sealed.clone();
+    tampered[0] ^= 0x01;
+    println!("Flipped ciphertext bit accepted: {}", aead.open(&nonce, b"header", &tampered).is_ok());
+    println!("Changed header accepted:         {}", aead.open(&nonce, b"footer", &sealed).is_ok());
+    assert!(aead.open(&nonce, b"header", &tampered).is_err());
+    assert!(aead.open(&nonce, b"footer", &sealed).is_err());
+
+    // Birthday-Bound Demo (Sweet32)
+    // -----------------------------
+    let cipher = Spn::new(master_key);
+    let report = cbc_birthday_experiment(&cipher, 0x3C5A, 4096, 1);
+    println!("\nCBC with 16-bit blocks:");
+    println!("First ciphertext collision after {} blocks; {} collisions in {} bytes, {} plaintext XORs recovered correctly",
+             report.first_collision_at.map_or(0, |i| i + 1), report.leaks.len(), report.bytes_encrypted(), report.correct_leaks);
+    let report = ctr_elimination_experiment(&cipher, 0, 0xBEEF, 0xFFFF, 1);
+    println!("CTR: {} known blocks leave {} candidate(s) for the secret block: {:04X?}",
+             report.known_blocks, report.candidates_left, report.recovered);
+
+    // Nonce-Reuse Demo
+    // ----------------
+    // Two CTR messages under the same nonce: dragging " the " over their XOR
+    // exposes fragments of the other message
+    let mut first = b"meet me at the north gate".to_vec();
+    let mut second = b"bring the documents along".to_vec();
+    Ctr::new(cipher.clone(), &[0x00, 0x07]).apply_keystream(&mut first);
+    Ctr::new(cipher.clone(), &[0x00, 0x07]).apply_keystream(&mut second);
+    let xored = xor_ciphertexts(&first, &second);
+    println!("\nCTR nonce reuse, crib-dragging \" the \":");
+    for found in crib_drag(&xored, &[" the "], 1.0) {
+        println!("offset {:2}: other plaintext reads {:?}", found.offset, String::from_utf8_lossy(&found.revealed));
+    }
+
+    // Padding-Oracle Demo
+    // -------------------
+    let mut server = CbcPaddingServer::new(cipher.clone());
+    let iv = [0x91, 0x2E];
+    let target = server.encrypt(&iv, b"user=admin;pin=4321");
+    let recovered = padding_oracle_attack(&mut server, &iv, &target)
+        .unwrap_or_else(|e| exit_with_error(&format!("padding oracle: {}", e)));
+    println!("\nPadding oracle recovered {:?} with {} queries",
+             String::from_utf8_lossy(&recovered), server.queries());
+    assert_eq!(recovered, b"user=admin;pin=4321");
+
+    // Correlation Power Analysis Demo
+    // -------------------------------
+    // Noisy Hamming-weight traces of the first-round S-box output give away
+    // the whitening key nibble by nibble
+    let mut device = LeakageOracle::new(cipher.clone(), LeakageModel::HammingWeight, 2.0, 7);
+    let traces = device.collect((0..2000u16).map(|i| i.wrapping_mul(40503)));
+    let whitening_key = cpa_recover_whitening_key(&traces, LeakageModel::HammingWeight);
+    println!("\nCPA on {} traces: whitening key {:04X} (actual {:04X})",
+             traces.len(), whitening_key, cipher.round_keys()[0]);
+
+    // Template Attack Demo
+    // --------------------
+    // Profile a device we hold the key for, then match a few traces of the
+    // target device against the templates
+    let profiling_cipher = Spn::new(0xFEDC_BA98_7654_3210_ABCD);
+    let mut profiling_device = LeakageOracle::new(profiling_cipher.clone(), LeakageModel::HammingWeight, 2.0, 8);
+    let profiling_traces = profiling_device.collect((0..5000u16).map(|i| i.wrapping_mul(40503)));
+    let target_traces = &traces[..200];
+    let whitening_key = (0..4).fold(0u16, |key, idx| {
+        let templates = Templates::profile(&profiling_traces, profiling_cipher.round_keys()[0], idx, &[0, 1, 2]);
+        key | (templates.attack(target_traces) as u16) << (4 * idx)
+    });
+    println!("Template attack on {} traces: whitening key {:04X}", target_traces.len(), whitening_key);
+
+    // Leakage Assessment Demo
+    // -----------------------
+    // Fixed-vs-random t-test on the plain and the masked implementation
+    let round_keys: [u16; 5] = cipher.round_keys().try_into().unwrap();
+    let mut plain_device = LeakageOracle::new(cipher.clone(), LeakageModel::HammingWeight, 2.0, 9);
+    let mut masked_device = LeakageOracle::new(MaskedSpn::new(round_keys, 3), LeakageModel::HammingWeight, 2.0, 9);
+    for (name, report) in [
+        ("plain", fixed_vs_random(&mut plain_device, 0x0000, 5000, 4)),
+        ("masked", fixed_vs_random(&mut masked_device, 0x0000, 5000, 4)),
+    ] {
+        let report = report.unwrap_or_else(|| exit_with_error("TVLA: too few traces in a group"));
+        println!("TVLA {}: max |t| = {:.1}, leaking points {:?}", name, report.max_abs_t(), report.leaking_points());
+    }
+
+    // Differential Fault Analysis Demo
+    // --------------------------------
+    // Glitch one nibble before the round-3 S-boxes until one last round key
+    // remains
+    let mut device = FaultyDevice::new(cipher.clone(), 11);
+    let mut faulty_pairs = Vec::new();
+    let result = loop {
+        let i = faulty_pairs.len() as u16;
+        faulty_pairs.push(device.faulty_pair(i.wrapping_mul(40503), 3, (i % 4) as usize));
+        let result = dfa_last_round_key(&faulty_pairs);
+        if result.remaining_keys() <= 1 {
+            break result;
+        }
+    };
+    println!("\nDFA with {} faulty encryptions: last round key {:04X?} (actual {:04X})",
+             faulty_pairs.len(), result.key(), cipher.round_keys()[4]);
+    let skipped = device.pair_with_fault(0x0000, Fault::skip(4, Layer::KeyAddition));
+    println!("Skipping the last key addition: last round key {:04X}", last_round_key_from_skip(skipped));
+}
+
+/// Command-line entry points for the utilities that work on files
+fn run_command(command: &str, args: &[String]) {
+    match (command, args) {
+        ("ecb-image", [input, output_dir]) => {
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+            let written = write_mode_comparison(Path::new(input), Path::new(output_dir), &cipher, &[0x3C, 0x5A])
+                .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            for path in written {
+                println!("Wrote {}", path.display());
+            }
+        }
+        ("bench", []) => println!("{}", bench::run(1 << 22, 1 << 20).to_json()),
+        ("bench", [blocks, pairs]) => {
+            let parse = |arg: &String| arg.parse().unwrap_or_else(|_| exit_with_error("bench: counts must be whole numbers"));
+            println!("{}", bench::run(parse(blocks), parse(pairs)).to_json());
+        }
+        ("snr-sweep", []) => {
+            println!("snr,success_rate");
+            for (snr, rate) in cpa_snr_sweep().points {
+                println!("{},{:.2}", snr, rate);
+            }
+        }
+        ("snr-sweep", [format]) if format == "json" => println!("{}", cpa_snr_sweep().to_json()),
+        ("multi-key", [key_bits, rest @ ..]) if rest.len() <= 1 => {
+            let key_bits = key_bits.parse().ok().filter(|bits| (1..=MAX_KEY_BITS).contains(bits))
+                .unwrap_or_else(|| exit_with_error(&format!("multi-key: key bits must be between 1 and {}", MAX_KEY_BITS)));
+            let summary = multi_target_experiment(key_bits, &[1, 2, 4, 16, 64, 256], 16, 1);
+            match rest.first().map(String::as_str) {
+                Some("json") => println!("{}", summary.to_json()),
+                None => {
+                    println!("targets,mean_trials,expected_trials,gain");
+                    let single = summary.points[0].1;
+                    for &(targets, trials) in &summary.points {
+                        println!("{},{:.0},{:.0},{:.1}", targets, trials, expected_trials(key_bits, targets as usize), single / trials);
+                    }
+                }
+                Some(_) => exit_with_error("multi-key: the only output option is json"),
+            }
+        }
+        ("attack-json", [attack]) => println!("{}", demo_attack_result(attack).to_json()),
+        ("attack-report", [attack]) => println!("{}", demo_attack_result(attack)),
+        ("explain", [attack]) => {
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234).with_backend(Backend::Lut);
+            let last_round_key = cipher.round_keys()[4];
+            match attack.as_str() {
+                // As many pairs as the trail calls for: c / bias^2 or c / p
+                "linear" => {
+                    let trail = linear_trail(Sbox::present(), 0xA000, 0x2000).expect("the masks are connected");
+                    let bias = trail.weight / 2.0;
+                    let pairs = known_plaintext_pairs(&cipher, (DATA_FACTOR / (bias * bias)).ceil() as usize, 1);
+                    print!("{}", linear_walkthrough(&pairs, 0xA000, 0x2000, 3, Some(nibble(last_round_key, 3))));
+                }
+                "differential" => {
+                    let trail = differential_trail(Sbox::present(), 0x0007, 0x0009).expect("the differences are connected");
+                    let pairs = chosen_plaintext_pairs(&cipher, 0x0007, (DATA_FACTOR / trail.weight).ceil() as usize, 1);
+                    print!("{}", differential_walkthrough(&pairs, 0x0007, 0x0009, 0, Some(nibble(last_round_key, 0))));
+                }
+                _ => exit_with_error("explain: choose linear or differential"),
+            }
+        }
+        ("complexity", [attack, mask_in, mask_out, rest @ ..]) if rest.len() <= 1 => {
+            let mask = |text: &str| {
+                u16::from_str_radix(text, 16).unwrap_or_else(|_| exit_with_error("complexity: masks are 16-bit hex values"))
+            };
+            let (mask_in, mask_out) = (mask(mask_in), mask(mask_out));
+            let trail = match attack.as_str() {
+                "linear" => Trail::Linear { alpha: mask_in, beta: mask_out, bias: None },
+                "differential" => Trail::Differential { delta_p: mask_in, delta_u: mask_out, probability: None },
+                _ => exit_with_error("complexity: choose linear or differential"),
+            };
+            let report = estimate(trail, &Timing::measure())
+                .unwrap_or_else(|| exit_with_error("complexity: no trail connects the two masks"));
+            match rest.first().map(String::as_str) {
+                Some("json") => println!("{}", report.to_json()),
+                None => println!("{}", report),
+                Some(_) => exit_with_error("complexity: the only output option is json"),
+            }
+        }
+        ("table", [name]) => {
+            let sbox = Sbox::present();
+            let table = match name.as_str() {
+                "lat" => TableView::lat(sbox),
+                "ddt" => TableView::ddt(sbox),
+                "bct" => TableView::bct(sbox),
+                _ => exit_with_error("table: choose lat, ddt or bct"),
+            };
+            println!("{}", table);
+        }
+        ("oracle-server", [addr, rest @ ..]) if rest.len() <= 1 => {
+            let blocks_per_second = rest.first().map(|rate| {
+                rate.parse().ok().filter(|&rate: &f64| rate > 0.0)
+                    .unwrap_or_else(|| exit_with_error("oracle-server: the rate must be a positive number"))
+            });
+            // A fresh secret key per run
+            let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+            let mut rng = SplitMix64::new(seed);
+            let cipher = Spn::new(random_master_key(&mut rng));
+            let limits = ServerLimits { blocks_per_second, query_budget: None };
+            let server = OracleServer::bind(addr.as_str(), cipher, limits).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Encryption oracle listening on {}", server.local_addr().unwrap_or_else(|e| exit_with_error(&e.to_string())));
+            server.serve().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+        }
+        ("remote-attack", [addr]) => {
+            let mut oracle = RemoteOracle::connect(addr.as_str()).unwrap_or_else(|e| exit_with_error(&format!("remote-attack: {}", e)));
+            let known = known_plaintext_pairs_from(&mut oracle, 20000, 1).unwrap_or_else(|e| exit_with_error(&format!("remote-attack: {}", e)));
+            let ranking = linear_ranking(&known, 0x0B00, 0x0400, 2);
+            println!("Linear, last round key nibble 2: {:X?}", &ranking[..4]);
+            let chosen = chosen_plaintext_pairs_from(&mut oracle, 0x0040, 5000, 1).unwrap_or_else(|e| exit_with_error(&format!("remote-attack: {}", e)));
+            let ranking = differential_ranking(&chosen, 0x0040, 0x0060, 1);
+            println!("Differential, last round key nibble 1: {:X?}", &ranking[..4]);
+            println!("Server has answered {} blocks", oracle.server_queries().unwrap_or_else(|e| exit_with_error(&format!("remote-attack: {}", e))));
+        }
+        ("record-rng", [count, log]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("record-rng: count must be a whole number"));
+            let mut rng = RecordingRng::new(SplitMix64::new(1));
+            let pairs = known_plaintext_pairs_with(&Spn::new(0x1234_5678_90AB_CDEF_1234), count, &mut rng);
+            println!("Linear, last round key nibble 2: {:X?}", &linear_ranking(&pairs, 0x0B00, 0x0400, 2)[..4]);
+            std::fs::write(log, log_to_text(rng.log())).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Wrote {} draws to {}", rng.log().len(), log);
+        }
+        ("replay-rng", [log]) => {
+            let text = std::fs::read_to_string(log).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let mut rng = ReplayRng::new(parse_log(&text).unwrap_or_else(|e| exit_with_error(&format!("replay-rng: {}", e))));
+            let pairs = known_plaintext_pairs_with(&Spn::new(0x1234_5678_90AB_CDEF_1234), rng.remaining(), &mut rng);
+            println!("Linear, last round key nibble 2: {:X?}", &linear_ranking(&pairs, 0x0B00, 0x0400, 2)[..4]);
+        }
+        ("export-traces", [count, stem]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("export-traces: count must be a whole number"));
+            let mut device = LeakageOracle::new(Spn::new(0x1234_5678_90AB_CDEF_1234), LeakageModel::HammingWeight, 2.0, 7);
+            let traces = device.collect((0..count).map(|i| (i as u16).wrapping_mul(40503)));
+            let csv = Path::new(stem).with_extension("csv");
+            let mut written = write_npy(Path::new(stem), &traces).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            write_csv(&csv, &traces).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            written.push(csv);
+            for path in written {
+                println!("Wrote {}", path.display());
+            }
+        }
+        ("export-dataset", [kind, count, output]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("export-dataset: count must be a whole number"));
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+            let (kind, config_hash) = match kind.as_str() {
+                "known" => (DatasetKind::KnownPairs, ConfigHasher::cipher(cipher.round_keys()).finish()),
+                "chosen" => (DatasetKind::ChosenPairs { delta_p: 0x0040 }, ConfigHasher::cipher(cipher.round_keys()).finish()),
+                "traces" => (DatasetKind::Traces, ConfigHasher::leakage(cipher.round_keys(), &LeakageModel::HammingWeight, 2.0).finish()),
+                _ => exit_with_error("export-dataset: choose known, chosen or traces"),
+            };
+            let file = std::fs::File::create(output).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let header = DatasetHeader { kind, config_hash, seed: 1 };
+            let mut writer = DatasetWriter::new(std::io::BufWriter::new(file), header).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let written = match kind {
+                DatasetKind::KnownPairs => known_plaintext_pairs(&cipher, count, 1).into_iter().try_for_each(|pair| writer.push_known(pair)),
+                DatasetKind::ChosenPairs { delta_p } => {
+                    chosen_plaintext_pairs(&cipher, delta_p, count, 1).into_iter().try_for_each(|pair| writer.push_chosen(pair))
+                }
+                DatasetKind::Traces => {
+                    let mut device = LeakageOracle::new(cipher.clone(), LeakageModel::HammingWeight, 2.0, 1);
+                    (0..count).try_for_each(|i| writer.push_trace(&device.encrypt((i as u16).wrapping_mul(40503))))
+                }
+            };
+            written.and_then(|_| writer.finish()).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Wrote {} records to {}", count, output);
+        }
+        ("dataset-info", [input]) => {
+            let file = std::fs::File::open(input).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let reader = DatasetReader::new(std::io::BufReader::new(file)).unwrap_or_else(|e| exit_with_error(&format!("dataset-info: {}", e)));
+            let header = *reader.header();
+            let records = match header.kind {
+                DatasetKind::KnownPairs => reader.known_pairs().try_fold(0u64, |n, record| record.map(|_| n + 1)),
+                DatasetKind::ChosenPairs { .. } => reader.chosen_pairs().try_fold(0u64, |n, record| record.map(|_| n + 1)),
+                DatasetKind::Traces => reader.traces().try_fold(0u64, |n, record| record.map(|_| n + 1)),
+            }
+            .unwrap_or_else(|e| exit_with_error(&format!("dataset-info: {}", e)));
+            println!("{:?}, config hash {:016X}, seed {}, {} records", header.kind, header.config_hash, header.seed, records);
+        }
+        ("challenge", [attack, seed, output]) => {
+            let challenge = Challenge::new(challenge_attack("challenge", attack), parse_seed("challenge", seed));
+            let file = std::fs::File::create(output).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            // The plaintexts must not lead back to the challenge seed
+            let data_seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+            challenge.write_dataset(std::io::BufWriter::new(file), data_seed).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Wrote {}", output);
+        }
+        ("grade", [attack, seed, submission]) => {
+            let challenge = Challenge::new(challenge_attack("grade", attack), parse_seed("grade", seed));
+            let text = std::fs::read_to_string(submission).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let submission = Submission::parse(&text).unwrap_or_else(|e| exit_with_error(&format!("grade: {}", e)));
+            println!("{}", grade(&challenge, &submission, DEFAULT_BIAS_TOLERANCE).to_json());
+        }
+        ("cpa-traces", [input]) => {
+            let path = Path::new(input);
+            let traces = if path.extension().is_some_and(|ext| ext == "csv") { read_csv(path) } else { read_npy(path) }
+                .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let whitening_key = cpa_recover_whitening_key(&traces, LeakageModel::HammingWeight);
+            println!("CPA on {} traces: whitening key {:04X}", traces.len(), whitening_key);
+        }
+        ("quiz", [count, seed, problems, solutions]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("quiz: count must be a whole number"));
+            let seed = parse_seed("quiz", seed);
+            let questions = problem_set(count, seed);
+            std::fs::write(problems, problem_sheet(&questions, seed)).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            std::fs::write(solutions, solution_sheet(&questions, seed)).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Wrote {} and {}", problems, solutions);
+        }
+        ("quiz-mark", [count, seed, answers]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("quiz-mark: count must be a whole number"));
+            let questions = problem_set(count, parse_seed("quiz-mark", seed));
+            let text = std::fs::read_to_string(answers).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let marks = mark(&questions, &text).unwrap_or_else(|e| exit_with_error(&format!("quiz-mark: {}", e)));
+            for (i, right) in marks.iter().enumerate() {
+                println!("{}: {}", i + 1, if *right { "right" } else { "wrong" });
+            }
+            println!("{}/{} right", marks.iter().filter(|&&right| right).count(), marks.len());
+        }
+        ("spec", [flavour]) => {
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+            match flavour.as_str() {
+                "python" => print!("{}", python_script(&cipher)),
+                "sage" => print!("{}", sage_script(&cipher)),
+                _ => exit_with_error("spec: choose python or sage"),
+            }
+        }
+        ("kat", [count, output]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("kat: count must be a whole number"));
+            write_kat(Path::new(output), &generate(count, 1)).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Wrote {}", output);
+        }
+        ("verify-kat", [input, rest @ ..]) if rest.len() <= 1 => {
+            let vectors = read_kat(Path::new(input)).unwrap_or_else(|e| exit_with_error(&format!("verify-kat: {}", e)));
+            let implementations = match rest.first() {
+                Some(name) => vec![Implementation::from_name(name)
+                    .unwrap_or_else(|| exit_with_error("verify-kat: choose scalar, lut, constant-time, bitsliced or masked"))],
+                None => Implementation::ALL.to_vec(),
+            };
+            let mut failed = false;
+            for implementation in implementations {
+                let failures = verify(&vectors, implementation);
+                println!("{}: {}/{} vectors pass", implementation.name(), vectors.len() - failures.len(), vectors.len());
+                for (vector, output) in &failures {
+                    println!("  {:?} COUNT = {}: got {:04X}", vector.direction, vector.count, output);
+                }
+                failed |= !failures.is_empty();
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        ("selftest", rest) if rest.len() <= 1 => {
+            let cases: usize = rest.first().map_or(64, |cases| {
+                cases.parse().unwrap_or_else(|_| exit_with_error("selftest: cases must be a whole number"))
+            });
+            let mut failed = false;
+            let mut report = |name: &str, result: Result<(), String>| {
+                match &result {
+                    Ok(()) => println!("{}: ok", name),
+                    Err(message) => println!("{}: FAILED {}", name, message),
+                }
+                failed |= result.is_err();
+            };
+            let counterexample = |e: testkit::Counterexample<_>| format!("seed {} case {} input {:04X?}: {}", e.seed, e.case, e.input, e.message);
+            for backend in [Backend::Scalar, Backend::Lut, Backend::ConstantTime] {
+                let result = for_all(cases, 1, testkit::round_keys, |&keys| {
+                    check_block_cipher(&Spn::from_round_keys(keys).with_backend(backend), 4, &mut SplitMix64::new(keys[0] as u64))
+                });
+                report(&format!("round trip, {:?} backend", backend), result.map_err(counterexample));
+            }
+            let result = for_all(cases, 1, testkit::round_keys, |&keys| {
+                let reference = Spn::from_round_keys(keys);
+                let masked = MaskedSpn::new(keys, keys[1] as u64);
+                check_block_cipher(&masked, 4, &mut SplitMix64::new(keys[0] as u64))
+                    .and_then(|()| check_leaky_cipher(&masked, |p| reference.encrypt(p), 64, &mut SplitMix64::new(keys[2] as u64)))
+            });
+            report("round trip and observed states, masked", result.map_err(counterexample));
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+            report("encryption is a permutation", check_permutation(|p| cipher.encrypt(p), |c| cipher.decrypt(c)));
+            report("P-box is a bit permutation", check_bit_permutation(pbox, cases, &mut SplitMix64::new(1)));
+            let present = Sbox::present();
+            report("PRESENT S-box", check_sbox_bijective(present.table()).and_then(|()| check_lat(present)).and_then(|()| check_ddt(present)));
+            let result = for_all(cases, 1, testkit::sbox, |sbox| {
+                check_sbox_bijective(sbox.table()).and_then(|()| check_lat(sbox)).and_then(|()| check_ddt(sbox))
+            });
+            report("random S-boxes", result.map_err(|e| format!("seed {} case {} S-box {:X?}: {}", e.seed, e.case, e.input.table(), e.message)));
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        ("milp", [propagation, rounds]) => {
+            let propagation = match propagation.as_str() {
+                "differential" => Propagation::Differential,
+                "linear" => Propagation::Linear,
+                _ => exit_with_error("milp: choose differential or linear"),
+            };
+            let rounds = rounds.parse().ok().filter(|&r: &usize| r > 0)
+                .unwrap_or_else(|| exit_with_error("milp: rounds must be a positive whole number"));
+            print!("{}", active_sbox_model(Sbox::present(), propagation, rounds));
+        }
+        ("milp-trail", [rounds, solution]) => {
+            let rounds = rounds.parse().unwrap_or_else(|_| exit_with_error("milp-trail: rounds must be a whole number"));
+            let text = std::fs::read_to_string(solution).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let trail = parse_solution(&text, rounds).unwrap_or_else(|e| exit_with_error(&format!("milp-trail: {}", e)));
+            for (round, input) in trail.inputs.iter().enumerate() {
+                println!("Round {} S-box input: {:016b}", round + 1, input);
+            }
+            println!("Last S-box output:    {:016b}", trail.output);
+            println!("{} active S-boxes", trail.active_sboxes());
+        }
+        ("sat-cnf", [count, output]) => {
+            let count: usize = count.parse().unwrap_or_else(|_| exit_with_error("sat-cnf: count must be a whole number"));
+            let pairs = known_plaintext_pairs(&Spn::new(0x1234_5678_90AB_CDEF_1234), count, 1);
+            std::fs::write(output, key_recovery_cnf(&pairs).to_dimacs()).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("Wrote {}", output);
+        }
+        ("sat-attack", [solver, solver_args @ ..]) => {
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+            let pairs = known_plaintext_pairs(&cipher, 8, 1);
+            let solver_args: Vec<&str> = solver_args.iter().map(String::as_str).collect();
+            let cnf_path = std::env::temp_dir().join("spn_key_recovery.cnf");
+            match recover_round_keys(solver, &solver_args, &pairs, &cnf_path).unwrap_or_else(|e| exit_with_error(&format!("sat-attack: {}", e))) {
+                Some(round_keys) => println!("SAT on {} pairs: round keys {:04X?} (actual {:04X?})", pairs.len(), round_keys, cipher.round_keys()),
+                None => println!("SAT on {} pairs: the solver found no key", pairs.len()),
+            }
+        }
+        #[cfg(feature = "simd")]
+        ("bench-simd", []) => {
+            let round_keys = expand_key(0x1234_5678_90AB_CDEF_1234, 5);
+            let (scalar, simd) = spn_attacks::simd::compare_with_scalar(1 << 24, &round_keys);
+            println!("Scalar: {:?}, SIMD: {:?} ({:.1}x)", scalar, simd, scalar.as_secs_f64() / simd.as_secs_f64());
+        }
+        #[cfg(feature = "tui")]
+        ("watch", rest) if rest.len() <= 1 => {
+            use spn_attacks::dashboard::{Dashboard, NibbleView};
+            use spn_attacks::differential::IncrementalDifferentialAttack;
+            use spn_attacks::linear::IncrementalLinearAttack;
+
+            let pairs_per_second: f64 = rest.first().map_or(20000.0, |rate| {
+                rate.parse().ok().filter(|&rate: &f64| rate > 0.0)
+                    .unwrap_or_else(|| exit_with_error("watch: the rate must be a positive number"))
+            });
+            let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234).with_backend(Backend::Lut);
+            let last_round_key = cipher.round_keys()[4];
+            let total = 200_000;
+            let known = known_plaintext_pairs(&cipher, total, 1);
+            let chosen = chosen_plaintext_pairs(&cipher, 0x0040, total, 1);
+            let mut linear = IncrementalLinearAttack::new(0x0B00, 0x0400, 2);
+            let mut differential = IncrementalDifferentialAttack::new(0x0040, 0x0060, 1);
+            let mut dashboard = Dashboard::new(std::io::stdout(), total as u64).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            // Redraw ten times a second, feeding the pairs due in between
+            let step = ((pairs_per_second / 10.0).ceil() as usize).max(1);
+            for start in (0..total).step_by(step) {
+                let end = (start + step).min(total);
+                known[start..end].iter().for_each(|&pair| linear.feed(pair));
+                chosen[start..end].iter().for_each(|&pair| differential.feed(pair));
+                let views = [
+                    NibbleView { label: "linear K5[2]".to_string(), ranking: linear.current_ranking(), actual: Some(nibble(last_round_key, 2)) },
+                    NibbleView {
+                        label: "differential K5[1]".to_string(),
+                        ranking: differential.current_ranking(),
+                        actual: Some(nibble(last_round_key, 1)),
+                    },
+                ];
+                dashboard.draw(end as u64, &views).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            dashboard.finish().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+        }
+        _ => exit_with_error("usage: SPNWithLinAndDiffAttacks [ecb-image <input.pgm|ppm> <output-dir> | bench [<blocks> <pairs>] | snr-sweep [json] | multi-key <key-bits> [json] | attack-json <linear|differential> | attack-report <linear|differential> | explain <linear|differential> | complexity <linear|differential> <mask-in> <mask-out> [json] | table <lat|ddt|bct> | oracle-server <addr> [<blocks-per-second>] | remote-attack <addr> | record-rng <pairs> <log> | replay-rng <log> | export-traces <count> <stem> | cpa-traces <stem|traces.csv> | export-dataset <known|chosen|traces> <count> <output> | dataset-info <input> | challenge <linear|differential> <seed> <output> | grade <linear|differential> <seed> <submission> | quiz <count> <seed> <problems.md> <solutions.md> | quiz-mark <count> <seed> <answers> | spec <python|sage> | kat <count> <output.rsp> | verify-kat <input.rsp> [<implementation>] | selftest [<cases>] | milp <differential|linear> <rounds> | milp-trail <rounds> <solution-file> | sat-cnf <pairs> <output.cnf> | sat-attack <solver> [<solver-arg> ...] | bench-simd (needs the simd feature) | watch [<pairs-per-second>] (needs the tui feature)]"),
+    }
+}
+
+/// Linear or differential attack on one nibble of the demo key's last round
+/// key
+fn demo_attack_result(attack: &str) -> AttackResult {
+    let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234).with_backend(Backend::Lut);
+    let num_pairs = 20000;
+    let (kind, trail, nibble_idx, ranking) = match attack {
+        "linear" => {
+            let pairs = known_plaintext_pairs(&cipher, num_pairs, 1);
+            let trail = Trail::Linear { alpha: 0x0B00, beta: 0x0400, bias: None };
+            (AttackKind::Linear, trail, 2, linear_ranking(&pairs, 0x0B00, 0x0400, 2))
+        }
+        "differential" => {
+            let pairs = chosen_plaintext_pairs(&cipher, 0x0040, num_pairs, 1);
+            let trail = Trail::Differential { delta_p: 0x0040, delta_u: 0x0060, probability: None };
+            (AttackKind::Differential, trail, 1, differential_ranking(&pairs, 0x0040, 0x0060, 1))
+        }
+        _ => exit_with_error("choose linear or differential"),
+    };
+    AttackResult {
+        attack: kind,
+        trail: Some(trail),
+        round_key: 4,
+        nibble_idx,
+        data_unit: "pairs",
+        data: num_pairs,
+        ranking,
+        actual: Some(nibble(cipher.round_keys()[4], nibble_idx)),
+    }
+}
+
+/// CPA success rate against SNR on the demo key
+fn cpa_snr_sweep() -> ExperimentSummary {
+    let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+    let snrs: Vec<f64> = (-8..=2).map(|e| 2f64.powi(e)).collect();
+    let (traces_per_trial, trials) = (200, 50);
+    ExperimentSummary {
+        name: "cpa-snr-sweep".to_string(),
+        parameters: vec![("traces_per_trial".to_string(), traces_per_trial as f64), ("trials".to_string(), trials as f64)],
+        x_label: "snr".to_string(),
+        y_label: "success_rate".to_string(),
+        points: snr_sweep(&cipher, LeakageModel::HammingWeight, &snrs, traces_per_trial, trials, 1),
+    }
+}
+
+fn challenge_attack(command: &str, attack: &str) -> AttackKind {
+    match attack {
+        "linear" => AttackKind::Linear,
+        "differential" => AttackKind::Differential,
+        _ => exit_with_error(&format!("{}: choose linear or differential", command)),
+    }
+}
+
+fn parse_seed(command: &str, seed: &str) -> u64 {
+    seed.parse().unwrap_or_else(|_| exit_with_error(&format!("{}: the seed must be a whole number", command)))
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
 }
\ No newline at end of file
@@ -0,0 +1,160 @@
+// MILP Bounds on Active S-boxes
+// -----------------------------
+//
+// A mixed-integer program in CPLEX LP format whose optimum is the fewest
+// S-boxes any differential (or linear) trail over r rounds can activate, for
+// the cipher's P-box and a given S-box. One binary variable per state bit
+// marks it as active (nonzero difference, or mask bit set) and one per S-box
+// marks the S-box as active. The P-box is wiring between rounds, key
+// addition leaves both differences and masks alone, and each S-box
+// contributes the usual constraints: it is active exactly when an input bit
+// is, a bijective S-box has active outputs exactly when its inputs are, and
+// a branch-number constraint bounds active input plus output bits from below.
+// The model is a relaxation, so the optimum is a lower bound and the
+// solution an activity pattern rather than a trail the DDT or LAT allows.
+// Any LP-format solver (Gurobi, CPLEX, CBC, HiGHS, SCIP, glpsol) takes the
+// file; `parse_solution` reads back the variable values it writes.
+
+use std::fmt::Write as _;
+use std::io;
+
+use crate::sbox::Sbox;
+use crate::spn::pbox;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Which trails the model counts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Propagation {
+    Differential,
+    Linear,
+}
+
+/// Fewest active input plus output bits over the S-box's nontrivial
+/// transitions: DDT entries for differences, LAT entries for masks
+pub fn branch_number(sbox: &Sbox, propagation: Propagation) -> u32 {
+    let mut best = u32::MAX;
+    for a in 1..16 {
+        for b in 1..16 {
+            let possible = match propagation {
+                Propagation::Differential => sbox.ddt()[a][b] != 0,
+                Propagation::Linear => sbox.lat()[a][b] != 0,
+            };
+            if possible {
+                best = best.min(a.count_ones() + b.count_ones());
+            }
+        }
+    }
+    best
+}
+
+fn x(round: usize, bit: usize) -> String {
+    format!("x{}_{}", round, bit)
+}
+
+/// Bit `bit` of the S-box layer output in `round`, which the P-box wires
+/// into the next round's input
+fn y(round: usize, bit: usize) -> String {
+    x(round + 1, pbox(1 << bit).trailing_zeros() as usize)
+}
+
+fn sum(terms: impl IntoIterator<Item = String>, coefficient: u32) -> String {
+    let terms: Vec<String> =
+        terms.into_iter().map(|term| if coefficient == 1 { term } else { format!("{} {}", coefficient, term) }).collect();
+    terms.join(" + ")
+}
+
+/// `terms` with a minus sign on each
+fn minus(terms: impl IntoIterator<Item = String>) -> String {
+    terms.into_iter().map(|term| format!("- {}", term)).collect::<Vec<_>>().join(" ")
+}
+
+/// The LP file minimizing active S-boxes over `rounds` S-box layers
+pub fn active_sbox_model(sbox: &Sbox, propagation: Propagation, rounds: usize) -> String {
+    assert!(rounds > 0, "the model needs at least one round");
+    let branch = branch_number(sbox, propagation);
+    let mut lp = String::new();
+    writeln!(lp, "\\ Minimum active S-boxes, {:?} trails over {} rounds", propagation, rounds).unwrap();
+    writeln!(lp, "\\ x<r>_<b>: bit b entering S-box layer r; a<r>_<j>: S-box j of layer r active").unwrap();
+    writeln!(lp, "\\ S-box branch number {}", branch).unwrap();
+    writeln!(lp, "Minimize").unwrap();
+    let activity = (0..rounds).flat_map(|r| (0..4).map(move |j| format!("a{}_{}", r, j)));
+    writeln!(lp, " active: {}", sum(activity, 1)).unwrap();
+    writeln!(lp, "Subject To").unwrap();
+    writeln!(lp, " nonzero: {} >= 1", sum((0..16).map(|b| x(0, b)), 1)).unwrap();
+    for r in 0..rounds {
+        for j in 0..4 {
+            let (a, d) = (format!("a{}_{}", r, j), format!("d{}_{}", r, j));
+            let inputs: Vec<String> = (4 * j..4 * j + 4).map(|b| x(r, b)).collect();
+            let outputs: Vec<String> = (4 * j..4 * j + 4).map(|b| y(r, b)).collect();
+            for (k, bit) in inputs.iter().enumerate() {
+                writeln!(lp, " in{}_{}_{}: {} - {} >= 0", r, j, k, a, bit).unwrap();
+            }
+            writeln!(lp, " any{}_{}: {} - {} >= 0", r, j, sum(inputs.clone(), 1), a).unwrap();
+            writeln!(lp, " fwd{}_{}: {} {} >= 0", r, j, sum(outputs.clone(), 4), minus(inputs.clone())).unwrap();
+            writeln!(lp, " bwd{}_{}: {} {} >= 0", r, j, sum(inputs.clone(), 4), minus(outputs.clone())).unwrap();
+            for (k, bit) in inputs.iter().chain(&outputs).enumerate() {
+                writeln!(lp, " dmin{}_{}_{}: {} - {} >= 0", r, j, k, d, bit).unwrap();
+            }
+            let all = sum(inputs.into_iter().chain(outputs), 1);
+            writeln!(lp, " branch{}_{}: {} - {} {} >= 0", r, j, all, branch, d).unwrap();
+        }
+    }
+    writeln!(lp, "Binary").unwrap();
+    for r in 0..=rounds {
+        writeln!(lp, " {}", (0..16).map(|b| x(r, b)).collect::<Vec<_>>().join(" ")).unwrap();
+    }
+    for r in 0..rounds {
+        writeln!(lp, " {}", (0..4).flat_map(|j| [format!("a{}_{}", r, j), format!("d{}_{}", r, j)]).collect::<Vec<_>>().join(" "))
+            .unwrap();
+    }
+    writeln!(lp, "End").unwrap();
+    lp
+}
+
+/// Activity pattern read back from a solution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActivityTrail {
+    /// Active bits entering each S-box layer
+    pub inputs: Vec<u16>,
+    /// Active bits leaving the last S-box layer, before any P-box
+    pub output: u16,
+}
+
+impl ActivityTrail {
+    pub fn active_sboxes(&self) -> usize {
+        self.inputs.iter().map(|&word| (0..4).filter(|&j| (word >> (4 * j)) & 0xF != 0).count()).sum()
+    }
+}
+
+/// Read the `x` variables of a `rounds`-round model from a solution file.
+/// Any file listing a variable name followed by its value on one line
+/// works: Gurobi and HiGHS `.sol`, CBC `solution`, SCIP `write solution`.
+/// Variables the file leaves out are zero, as those solvers omit them.
+pub fn parse_solution(solution: &str, rounds: usize) -> io::Result<ActivityTrail> {
+    let mut words = vec![0u16; rounds + 1];
+    let mut found = false;
+    for line in solution.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        for pair in tokens.windows(2) {
+            let Some((round, bit)) = pair[0].strip_prefix('x').and_then(|rest| rest.split_once('_')) else { continue };
+            let (Ok(round), Ok(bit), Ok(value)) = (round.parse::<usize>(), bit.parse::<usize>(), pair[1].parse::<f64>()) else {
+                continue;
+            };
+            if round > rounds || bit >= 16 {
+                return Err(invalid("solution mentions a bit outside the model"));
+            }
+            found = true;
+            if value > 0.5 {
+                words[round] |= 1 << bit;
+            }
+        }
+    }
+    if !found {
+        return Err(invalid("no state variables in solution"));
+    }
+    let output = pbox(words.pop().unwrap());
+    Ok(ActivityTrail { inputs: words, output })
+}
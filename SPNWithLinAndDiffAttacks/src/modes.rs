@@ -0,0 +1,462 @@
+// Block Cipher Modes of Operation
+// -------------------------------
+//
+// Every mode keeps its chaining state between calls, so a long message can be
+// fed through in several chunks and produce the same output as a single call.
+// ECB and CBC only accept whole blocks; CTR, OFB and CFB turn the cipher into
+// a stream cipher and accept any length.
+
+use std::fmt;
+
+use crate::cipher::BlockCipher;
+use crate::padding::{InvalidPadding, Padding};
+
+/// Errors reported by the block-aligned modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModeError {
+    /// The input length is not a multiple of the block size
+    PartialBlock { len: usize, block_size: usize },
+    /// Decryption succeeded but the recovered padding is malformed
+    InvalidPadding,
+}
+
+impl fmt::Display for ModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModeError::PartialBlock { len, block_size } => write!(
+                f,
+                "input of {} bytes is not a multiple of the {}-byte block size",
+                len, block_size
+            ),
+            ModeError::InvalidPadding => write!(f, "invalid padding"),
+        }
+    }
+}
+
+impl std::error::Error for ModeError {}
+
+impl From<InvalidPadding> for ModeError {
+    fn from(_: InvalidPadding) -> Self {
+        ModeError::InvalidPadding
+    }
+}
+
+fn check_aligned<C: BlockCipher>(data: &[u8]) -> Result<(), ModeError> {
+    if !data.len().is_multiple_of(C::BLOCK_SIZE) {
+        return Err(ModeError::PartialBlock { len: data.len(), block_size: C::BLOCK_SIZE });
+    }
+    Ok(())
+}
+
+fn check_iv<C: BlockCipher>(iv: &[u8]) {
+    assert_eq!(iv.len(), C::BLOCK_SIZE, "IV/nonce must be exactly one block long");
+}
+
+fn xor_in_place(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+/// Increment a block as a big-endian counter, wrapping around at the top
+fn increment_counter(counter: &mut [u8]) {
+    for byte in counter.iter_mut().rev() {
+        let (next, carry) = byte.overflowing_add(1);
+        *byte = next;
+        if !carry {
+            break;
+        }
+    }
+}
+
+/// Electronic Codebook: every block is encrypted independently
+///
+/// Equal plaintext blocks give equal ciphertext blocks, which is exactly the
+/// weakness the other modes exist to avoid.
+#[derive(Clone, Debug)]
+pub struct Ecb<C> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Ecb<C> {
+    pub fn new(cipher: C) -> Self {
+        Ecb { cipher }
+    }
+
+    pub fn encrypt(&self, data: &mut [u8]) -> Result<(), ModeError> {
+        check_aligned::<C>(data)?;
+        self.cipher.encrypt_blocks(data);
+        Ok(())
+    }
+
+    pub fn decrypt(&self, data: &mut [u8]) -> Result<(), ModeError> {
+        check_aligned::<C>(data)?;
+        self.cipher.decrypt_blocks(data);
+        Ok(())
+    }
+
+    /// Pad a message of any length and encrypt it
+    pub fn encrypt_padded(&self, message: &[u8], padding: Padding) -> Vec<u8> {
+        let mut data = message.to_vec();
+        padding.pad(&mut data, C::BLOCK_SIZE);
+        self.encrypt(&mut data).expect("padded data is block aligned");
+        data
+    }
+
+    /// Decrypt and strip the padding
+    pub fn decrypt_padded(&self, ciphertext: &[u8], padding: Padding) -> Result<Vec<u8>, ModeError> {
+        let mut data = ciphertext.to_vec();
+        self.decrypt(&mut data)?;
+        let len = padding.unpad(&data, C::BLOCK_SIZE)?.len();
+        data.truncate(len);
+        Ok(data)
+    }
+}
+
+/// Cipher Block Chaining: each plaintext block is XORed with the previous
+/// ciphertext block (or the IV) before encryption
+///
+/// The chaining value carries over between calls, so use one instance per
+/// message and per direction.
+#[derive(Clone, Debug)]
+pub struct Cbc<C> {
+    cipher: C,
+    chain: Vec<u8>,
+}
+
+impl<C: BlockCipher> Cbc<C> {
+    pub fn new(cipher: C, iv: &[u8]) -> Self {
+        check_iv::<C>(iv);
+        Cbc { cipher, chain: iv.to_vec() }
+    }
+
+    pub fn encrypt(&mut self, data: &mut [u8]) -> Result<(), ModeError> {
+        check_aligned::<C>(data)?;
+        for block in data.chunks_exact_mut(C::BLOCK_SIZE) {
+            xor_in_place(block, &self.chain);
+            self.cipher.encrypt_block(block);
+            self.chain.copy_from_slice(block);
+        }
+        Ok(())
+    }
+
+    pub fn decrypt(&mut self, data: &mut [u8]) -> Result<(), ModeError> {
+        check_aligned::<C>(data)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        // Block i only needs ciphertext block i - 1, so all blocks can be
+        // decrypted in one batch and unchained afterwards
+        let ciphertext = data.to_vec();
+        self.cipher.decrypt_blocks(data);
+        let (first, rest) = data.split_at_mut(C::BLOCK_SIZE);
+        xor_in_place(first, &self.chain);
+        xor_in_place(rest, &ciphertext);
+        self.chain.copy_from_slice(&ciphertext[ciphertext.len() - C::BLOCK_SIZE..]);
+        Ok(())
+    }
+
+    /// Pad a message of any length and encrypt it as the final chunk
+    pub fn encrypt_padded(&mut self, message: &[u8], padding: Padding) -> Vec<u8> {
+        let mut data = message.to_vec();
+        padding.pad(&mut data, C::BLOCK_SIZE);
+        self.encrypt(&mut data).expect("padded data is block aligned");
+        data
+    }
+
+    /// Decrypt the final chunk and strip the padding
+    pub fn decrypt_padded(&mut self, ciphertext: &[u8], padding: Padding) -> Result<Vec<u8>, ModeError> {
+        let mut data = ciphertext.to_vec();
+        self.decrypt(&mut data)?;
+        let len = padding.unpad(&data, C::BLOCK_SIZE)?.len();
+        data.truncate(len);
+        Ok(data)
+    }
+}
+
+/// Counter mode: the keystream is the encryption of successive counter blocks
+///
+/// Encryption and decryption are the same operation.
+#[derive(Clone, Debug)]
+pub struct Ctr<C> {
+    cipher: C,
+    counter: Vec<u8>,
+    keystream: Vec<u8>,
+    used: usize,
+}
+
+impl<C: BlockCipher> Ctr<C> {
+    /// `nonce` is the initial counter block
+    pub fn new(cipher: C, nonce: &[u8]) -> Self {
+        check_iv::<C>(nonce);
+        Ctr {
+            cipher,
+            counter: nonce.to_vec(),
+            keystream: vec![0; C::BLOCK_SIZE],
+            used: C::BLOCK_SIZE,
+        }
+    }
+
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        // Use up the current keystream block, then encrypt the counters for
+        // all remaining whole blocks in one batch
+        let pending = (C::BLOCK_SIZE - self.used).min(data.len());
+        let (head, rest) = data.split_at_mut(pending);
+        xor_in_place(head, &self.keystream[self.used..]);
+        self.used += pending;
+
+        let whole = rest.len() / C::BLOCK_SIZE * C::BLOCK_SIZE;
+        let (blocks, tail) = rest.split_at_mut(whole);
+        if !blocks.is_empty() {
+            let mut keystream = Vec::with_capacity(whole);
+            for _ in 0..whole / C::BLOCK_SIZE {
+                keystream.extend_from_slice(&self.counter);
+                increment_counter(&mut self.counter);
+            }
+            self.cipher.encrypt_blocks(&mut keystream);
+            xor_in_place(blocks, &keystream);
+        }
+
+        for byte in tail {
+            if self.used == C::BLOCK_SIZE {
+                self.keystream.copy_from_slice(&self.counter);
+                self.cipher.encrypt_block(&mut self.keystream);
+                increment_counter(&mut self.counter);
+                self.used = 0;
+            }
+            *byte ^= self.keystream[self.used];
+            self.used += 1;
+        }
+    }
+}
+
+/// Output Feedback: the keystream is the cipher iterated on the IV
+///
+/// Encryption and decryption are the same operation.
+#[derive(Clone, Debug)]
+pub struct Ofb<C> {
+    cipher: C,
+    register: Vec<u8>,
+    used: usize,
+}
+
+impl<C: BlockCipher> Ofb<C> {
+    pub fn new(cipher: C, iv: &[u8]) -> Self {
+        check_iv::<C>(iv);
+        Ofb { cipher, register: iv.to_vec(), used: C::BLOCK_SIZE }
+    }
+
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data {
+            if self.used == C::BLOCK_SIZE {
+                self.cipher.encrypt_block(&mut self.register);
+                self.used = 0;
+            }
+            *byte ^= self.register[self.used];
+            self.used += 1;
+        }
+    }
+}
+
+/// Cipher Feedback (full-block segments): the keystream is the encryption of
+/// the previous ciphertext block (or the IV)
+///
+/// A trailing partial block is allowed, and the feedback register is filled
+/// byte by byte so chunked calls line up with a single call.
+#[derive(Clone, Debug)]
+pub struct Cfb<C> {
+    cipher: C,
+    keystream: Vec<u8>,
+    feedback: Vec<u8>,
+    used: usize,
+}
+
+impl<C: BlockCipher> Cfb<C> {
+    pub fn new(cipher: C, iv: &[u8]) -> Self {
+        check_iv::<C>(iv);
+        Cfb {
+            cipher,
+            keystream: vec![0; C::BLOCK_SIZE],
+            feedback: iv.to_vec(),
+            used: C::BLOCK_SIZE,
+        }
+    }
+
+    fn refill(&mut self) {
+        if self.used == C::BLOCK_SIZE {
+            self.keystream.copy_from_slice(&self.feedback);
+            self.cipher.encrypt_block(&mut self.keystream);
+            self.used = 0;
+        }
+    }
+
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data {
+            self.refill();
+            *byte ^= self.keystream[self.used];
+            self.feedback[self.used] = *byte;
+            self.used += 1;
+        }
+    }
+
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data {
+            self.refill();
+            self.feedback[self.used] = *byte;
+            *byte ^= self.keystream[self.used];
+            self.used += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::Spn;
+
+    const IV: [u8; 2] = [0xA5, 0x3C];
+
+    fn cipher() -> Spn {
+        Spn::new(0x1234_5678_90AB_CDEF_1234)
+    }
+
+    /// 101 bytes: 50 whole blocks and a trailing byte
+    fn message() -> Vec<u8> {
+        (0..101u32).map(|i| (i * 37 + 11) as u8).collect()
+    }
+
+    fn aligned() -> Vec<u8> {
+        message()[..100].to_vec()
+    }
+
+    #[test]
+    fn ecb_round_trips() {
+        let ecb = Ecb::new(cipher());
+        let mut data = aligned();
+        ecb.encrypt(&mut data).unwrap();
+        assert_ne!(data, aligned());
+        ecb.decrypt(&mut data).unwrap();
+        assert_eq!(data, aligned());
+    }
+
+    #[test]
+    fn cbc_round_trips() {
+        let mut data = aligned();
+        Cbc::new(cipher(), &IV).encrypt(&mut data).unwrap();
+        assert_ne!(data, aligned());
+        Cbc::new(cipher(), &IV).decrypt(&mut data).unwrap();
+        assert_eq!(data, aligned());
+    }
+
+    #[test]
+    fn ctr_round_trips() {
+        let mut data = message();
+        Ctr::new(cipher(), &IV).apply_keystream(&mut data);
+        assert_ne!(data, message());
+        Ctr::new(cipher(), &IV).apply_keystream(&mut data);
+        assert_eq!(data, message());
+    }
+
+    #[test]
+    fn ofb_round_trips() {
+        let mut data = message();
+        Ofb::new(cipher(), &IV).apply_keystream(&mut data);
+        assert_ne!(data, message());
+        Ofb::new(cipher(), &IV).apply_keystream(&mut data);
+        assert_eq!(data, message());
+    }
+
+    #[test]
+    fn cfb_round_trips() {
+        let mut data = message();
+        Cfb::new(cipher(), &IV).encrypt(&mut data);
+        assert_ne!(data, message());
+        Cfb::new(cipher(), &IV).decrypt(&mut data);
+        assert_eq!(data, message());
+    }
+
+    #[test]
+    fn block_modes_reject_partial_blocks() {
+        let mut data = message();
+        let error = ModeError::PartialBlock { len: 101, block_size: 2 };
+        assert_eq!(Ecb::new(cipher()).encrypt(&mut data), Err(error));
+        assert_eq!(Cbc::new(cipher(), &IV).decrypt(&mut data), Err(error));
+    }
+
+    #[test]
+    fn chunked_cbc_matches_single_call() {
+        let mut whole = aligned();
+        Cbc::new(cipher(), &IV).encrypt(&mut whole).unwrap();
+        let mut chunked = aligned();
+        let mut cbc = Cbc::new(cipher(), &IV);
+        for chunk in chunked.chunks_mut(14) {
+            cbc.encrypt(chunk).unwrap();
+        }
+        assert_eq!(chunked, whole);
+        let mut cbc = Cbc::new(cipher(), &IV);
+        for chunk in chunked.chunks_mut(6) {
+            cbc.decrypt(chunk).unwrap();
+        }
+        assert_eq!(chunked, aligned());
+    }
+
+    #[test]
+    fn chunked_stream_modes_match_single_call() {
+        // Odd chunk sizes split keystream blocks between calls
+        for chunk_len in [1, 3, 7, 64] {
+            let mut whole = message();
+            Ctr::new(cipher(), &IV).apply_keystream(&mut whole);
+            let mut chunked = message();
+            let mut ctr = Ctr::new(cipher(), &IV);
+            chunked.chunks_mut(chunk_len).for_each(|chunk| ctr.apply_keystream(chunk));
+            assert_eq!(chunked, whole, "CTR, chunks of {}", chunk_len);
+
+            let mut whole = message();
+            Ofb::new(cipher(), &IV).apply_keystream(&mut whole);
+            let mut chunked = message();
+            let mut ofb = Ofb::new(cipher(), &IV);
+            chunked.chunks_mut(chunk_len).for_each(|chunk| ofb.apply_keystream(chunk));
+            assert_eq!(chunked, whole, "OFB, chunks of {}", chunk_len);
+
+            let mut whole = message();
+            Cfb::new(cipher(), &IV).encrypt(&mut whole);
+            let mut chunked = message();
+            let mut cfb = Cfb::new(cipher(), &IV);
+            chunked.chunks_mut(chunk_len).for_each(|chunk| cfb.encrypt(chunk));
+            assert_eq!(chunked, whole, "CFB, chunks of {}", chunk_len);
+            let mut cfb = Cfb::new(cipher(), &IV);
+            chunked.chunks_mut(chunk_len).for_each(|chunk| cfb.decrypt(chunk));
+            assert_eq!(chunked, message(), "CFB decryption, chunks of {}", chunk_len);
+        }
+    }
+
+    #[test]
+    fn cbc_batch_decryption_matches_block_by_block() {
+        let mut ciphertext = aligned();
+        Cbc::new(cipher(), &IV).encrypt(&mut ciphertext).unwrap();
+        let mut batch = ciphertext.clone();
+        Cbc::new(cipher(), &IV).decrypt(&mut batch).unwrap();
+        let cipher = cipher();
+        let mut chain = IV.to_vec();
+        let mut expected = Vec::new();
+        for block in ciphertext.chunks_exact(2) {
+            let mut plain = block.to_vec();
+            cipher.decrypt_block(&mut plain);
+            xor_in_place(&mut plain, &chain);
+            expected.extend_from_slice(&plain);
+            chain.copy_from_slice(block);
+        }
+        assert_eq!(batch, expected);
+        assert_eq!(batch, aligned());
+    }
+
+    #[test]
+    fn padded_round_trips() {
+        for padding in [Padding::Pkcs7, Padding::Zero] {
+            let ciphertext = Cbc::new(cipher(), &IV).encrypt_padded(&message(), padding);
+            assert_eq!(Cbc::new(cipher(), &IV).decrypt_padded(&ciphertext, padding).unwrap(), message());
+            let ciphertext = Ecb::new(cipher()).encrypt_padded(&message(), padding);
+            assert_eq!(Ecb::new(cipher()).decrypt_padded(&ciphertext, padding).unwrap(), message());
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// Multi-Key Attacks
+// -----------------
+//
+// In the multi-key setting the attacker sees the same known plaintexts, e.g.
+// a fixed protocol header, encrypted under many independent keys and is
+// satisfied with recovering any one of them. Exhaustive search then checks
+// every trial key against all targets at once through a hash table of their
+// ciphertexts: with T targets the first hit comes after about 2^k / (T + 1)
+// trials instead of 2^k / 2 for one target, at the cost of memory for T
+// ciphertexts. The full 80-bit key is out of reach for a simulation, so the
+// keys here come from a reduced space of `key_bits` low bits, as from a weak
+// key generator; those bits are the last round key and then round key 3.
+// Searches past 2^24 keys take minutes, so that is the cap.
+
+use std::collections::HashMap;
+
+use crate::results::ExperimentSummary;
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::Spn;
+
+/// Largest reduced key space `MultiKeyData::generate` accepts
+pub const MAX_KEY_BITS: u32 = 24;
+
+/// Known plaintexts encrypted under many keys
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiKeyData {
+    pub key_bits: u32,
+    /// Shared by every target; enough blocks to rule out wrong keys
+    pub plaintexts: Vec<u16>,
+    /// Per target, the encryptions of `plaintexts`
+    pub ciphertexts: Vec<Vec<u16>>,
+    /// The secret keys, to check a recovery against
+    pub keys: Vec<u128>,
+}
+
+impl MultiKeyData {
+    /// `targets` independent keys below 2^`key_bits`
+    pub fn generate(targets: usize, key_bits: u32, seed: u64) -> Self {
+        assert!((1..=MAX_KEY_BITS).contains(&key_bits), "key_bits must be between 1 and {}", MAX_KEY_BITS);
+        let mut rng = SplitMix64::new(seed);
+        // One block per 16 key bits leaves about one wrong key; one more
+        // rules it out
+        let plaintexts: Vec<u16> = (0..key_bits.div_ceil(16) + 1).map(|_| rng.next_u16()).collect();
+        let keys: Vec<u128> = (0..targets).map(|_| rng.below(1 << key_bits) as u128).collect();
+        let ciphertexts = keys
+            .iter()
+            .map(|&key| {
+                let cipher = Spn::new(key);
+                plaintexts.iter().map(|&p| cipher.encrypt(p)).collect()
+            })
+            .collect();
+        MultiKeyData { key_bits, plaintexts, ciphertexts, keys }
+    }
+}
+
+/// The first key found by `search_any`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    pub target: usize,
+    pub key: u128,
+    /// Trial keys encrypted, this one included
+    pub trials: u64,
+}
+
+/// Try keys 0, 1, 2, ... against all targets and stop at the first that
+/// encrypts every plaintext to one target's ciphertexts and is that
+/// target's key; a wrong key that happens to fit the plaintexts is skipped
+pub fn search_any(data: &MultiKeyData) -> Option<Recovery> {
+    let mut table: HashMap<u16, Vec<usize>> = HashMap::new();
+    for (target, ciphertexts) in data.ciphertexts.iter().enumerate() {
+        table.entry(ciphertexts[0]).or_default().push(target);
+    }
+    for key in 0..1u128 << data.key_bits {
+        let cipher = Spn::new(key);
+        let Some(targets) = table.get(&cipher.encrypt(data.plaintexts[0])) else {
+            continue;
+        };
+        for &target in targets {
+            let rest = data.plaintexts[1..].iter().zip(&data.ciphertexts[target][1..]);
+            if rest.clone().all(|(&p, &c)| cipher.encrypt(p) == c) && data.keys[target] == key {
+                return Some(Recovery { target, key, trials: key as u64 + 1 });
+            }
+        }
+    }
+    None
+}
+
+/// Expected trials until the first of `targets` uniform keys is hit
+pub fn expected_trials(key_bits: u32, targets: usize) -> f64 {
+    (2f64.powi(key_bits as i32) + 1.0) / (targets as f64 + 1.0)
+}
+
+/// Mean trials of `search_any` against each nonzero number of targets over
+/// `runs` fresh data sets
+pub fn multi_target_experiment(key_bits: u32, target_counts: &[usize], runs: usize, seed: u64) -> ExperimentSummary {
+    let root = SplitMix64::new(seed);
+    let points = target_counts
+        .iter()
+        .filter(|&&targets| targets > 0)
+        .map(|&targets| {
+            let total: u64 = (0..runs)
+                .map(|run| {
+                    let data = MultiKeyData::generate(targets, key_bits, root.split((targets * runs + run) as u64).next_u64());
+                    search_any(&data).expect("every target key is in the searched space").trials
+                })
+                .sum();
+            (targets as f64, total as f64 / runs.max(1) as f64)
+        })
+        .collect();
+    ExperimentSummary {
+        name: "multi-target key search".to_string(),
+        parameters: vec![("key_bits".to_string(), key_bits as f64), ("runs".to_string(), runs as f64)],
+        x_label: "targets".to_string(),
+        y_label: "trials to first key".to_string(),
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_a_target_key() {
+        let data = MultiKeyData::generate(8, 12, 1);
+        let recovery = search_any(&data).unwrap();
+        assert_eq!(data.keys[recovery.target], recovery.key);
+        assert_eq!(recovery.trials, recovery.key as u64 + 1);
+        assert_eq!(recovery.key, *data.keys.iter().min().unwrap());
+    }
+
+    #[test]
+    fn skips_keys_that_only_fit_the_plaintexts() {
+        // One block cannot tell 2^17 keys apart; here a smaller wrong key fits it
+        let mut data = MultiKeyData::generate(1, 17, 3);
+        data.plaintexts.truncate(1);
+        data.ciphertexts[0].truncate(1);
+        let fits = |key| Spn::new(key).encrypt(data.plaintexts[0]) == data.ciphertexts[0][0];
+        assert!((0..data.keys[0]).any(fits));
+        let recovery = search_any(&data).unwrap();
+        assert_eq!(recovery.key, data.keys[0]);
+    }
+
+    #[test]
+    fn experiment_ignores_zero_targets() {
+        let summary = multi_target_experiment(8, &[0, 1, 4], 2, 3);
+        assert_eq!(summary.points.iter().map(|&(targets, _)| targets).collect::<Vec<_>>(), [1.0, 4.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "key_bits")]
+    fn rejects_key_spaces_past_the_cap() {
+        MultiKeyData::generate(1, MAX_KEY_BITS + 1, 4);
+    }
+}
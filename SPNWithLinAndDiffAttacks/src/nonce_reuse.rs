@@ -0,0 +1,54 @@
+// Nonce-Reuse Attack on Stream Modes
+// ----------------------------------
+//
+// CTR and OFB produce the same keystream for the same key and nonce, so two
+// ciphertexts under a repeated nonce XOR to the XOR of their plaintexts. A
+// guessed word (crib) XORed in at the right offset then reveals the other
+// plaintext at that position; sliding it along every offset and keeping the
+// readable results is crib-dragging.
+
+/// XOR two ciphertexts over their common length
+pub fn xor_ciphertexts(first: &[u8], second: &[u8]) -> Vec<u8> {
+    first.iter().zip(second).map(|(a, b)| a ^ b).collect()
+}
+
+/// A crib placed at one offset and what it exposes of the other plaintext
+#[derive(Clone, Debug, PartialEq)]
+pub struct CribMatch {
+    pub offset: usize,
+    pub crib: String,
+    /// The other plaintext at `offset`, assuming the crib is correct
+    pub revealed: Vec<u8>,
+    /// Fraction of `revealed` that looks like English text
+    pub score: f32,
+}
+
+/// Share of bytes that are letters, digits, spaces or common punctuation
+fn text_score(bytes: &[u8]) -> f32 {
+    let readable = bytes
+        .iter()
+        .filter(|&&b| b.is_ascii_alphanumeric() || b" .,'!?-".contains(&b))
+        .count();
+    readable as f32 / bytes.len() as f32
+}
+
+/// Drag every crib across `xored` and return placements whose revealed text
+/// scores at least `min_score`, best first
+pub fn crib_drag(xored: &[u8], cribs: &[&str], min_score: f32) -> Vec<CribMatch> {
+    let mut matches = Vec::new();
+    for crib in cribs {
+        let crib_bytes = crib.as_bytes();
+        if crib_bytes.is_empty() || crib_bytes.len() > xored.len() {
+            continue;
+        }
+        for offset in 0..=xored.len() - crib_bytes.len() {
+            let revealed = xor_ciphertexts(&xored[offset..offset + crib_bytes.len()], crib_bytes);
+            let score = text_score(&revealed);
+            if score >= min_score {
+                matches.push(CribMatch { offset, crib: crib.to_string(), revealed, score });
+            }
+        }
+    }
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.offset.cmp(&b.offset)));
+    matches
+}
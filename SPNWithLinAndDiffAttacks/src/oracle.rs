@@ -0,0 +1,29 @@
+// Encryption Oracles
+// ------------------
+//
+// Chosen- and known-plaintext attacks only need someone to encrypt blocks
+// for them. `EncryptionOracle` is that someone: the cipher itself, or a
+// server on the other end of a socket (see `remote`), whose answers can fail.
+
+use std::io;
+
+use crate::spn::Spn;
+
+/// Anything that encrypts 16-bit blocks on request
+pub trait EncryptionOracle {
+    /// Replace every block with its encryption
+    fn query(&mut self, blocks: &mut [u16]) -> io::Result<()>;
+}
+
+impl EncryptionOracle for Spn {
+    fn query(&mut self, blocks: &mut [u16]) -> io::Result<()> {
+        self.encrypt_blocks(blocks);
+        Ok(())
+    }
+}
+
+impl<O: EncryptionOracle + ?Sized> EncryptionOracle for &mut O {
+    fn query(&mut self, blocks: &mut [u16]) -> io::Result<()> {
+        (**self).query(blocks)
+    }
+}
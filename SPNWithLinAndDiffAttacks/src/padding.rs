@@ -0,0 +1,140 @@
+// Block Padding
+// -------------
+//
+// PKCS#7 appends `n` bytes of value `n` (always at least one byte, a whole
+// block when the message is already aligned) and can be removed unambiguously.
+// Zero padding appends zero bytes only when needed, so trailing zeros of the
+// message itself are lost on removal.
+
+use std::fmt;
+
+/// Padding scheme used to extend a message to a whole number of blocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+    Pkcs7,
+    Zero,
+}
+
+/// The padded message is malformed
+///
+/// Kept as a single error on purpose: a decryptor that reveals anything more
+/// than "padding was bad" hands an attacker an even stronger oracle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidPadding;
+
+impl fmt::Display for InvalidPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid padding")
+    }
+}
+
+impl std::error::Error for InvalidPadding {}
+
+impl Padding {
+    /// Append padding so `data.len()` becomes a multiple of `block_size`
+    pub fn pad(self, data: &mut Vec<u8>, block_size: usize) {
+        assert!((1..=255).contains(&block_size), "block size must be 1..=255 bytes");
+        let remainder = data.len() % block_size;
+        match self {
+            Padding::Pkcs7 => {
+                let n = block_size - remainder;
+                data.resize(data.len() + n, n as u8);
+            }
+            Padding::Zero => {
+                if remainder != 0 {
+                    data.resize(data.len() + block_size - remainder, 0);
+                }
+            }
+        }
+    }
+
+    /// Strip the padding and return the message it was covering
+    ///
+    /// PKCS#7 is checked strictly: the data must be block aligned and every
+    /// padding byte must carry the padding length.
+    pub fn unpad(self, data: &[u8], block_size: usize) -> Result<&[u8], InvalidPadding> {
+        if !data.len().is_multiple_of(block_size) {
+            return Err(InvalidPadding);
+        }
+        match self {
+            Padding::Pkcs7 => {
+                let n = *data.last().ok_or(InvalidPadding)? as usize;
+                if n == 0 || n > block_size {
+                    return Err(InvalidPadding);
+                }
+                let (message, padding) = data.split_at(data.len() - n);
+                if padding.iter().any(|&b| b as usize != n) {
+                    return Err(InvalidPadding);
+                }
+                Ok(message)
+            }
+            Padding::Zero => {
+                let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+                Ok(&data[..end])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkcs7_pads_and_unpads() {
+        let mut data = b"abc".to_vec();
+        Padding::Pkcs7.pad(&mut data, 4);
+        assert_eq!(data, b"abc\x01");
+        assert_eq!(Padding::Pkcs7.unpad(&data, 4), Ok(&b"abc"[..]));
+    }
+
+    #[test]
+    fn pkcs7_adds_a_whole_block_to_aligned_data() {
+        let mut data = b"abcd".to_vec();
+        Padding::Pkcs7.pad(&mut data, 4);
+        assert_eq!(data, b"abcd\x04\x04\x04\x04");
+        assert_eq!(Padding::Pkcs7.unpad(&data, 4), Ok(&b"abcd"[..]));
+    }
+
+    #[test]
+    fn pkcs7_pads_an_empty_message() {
+        let mut data = Vec::new();
+        Padding::Pkcs7.pad(&mut data, 2);
+        assert_eq!(data, [2, 2]);
+        assert_eq!(Padding::Pkcs7.unpad(&data, 2), Ok(&[][..]));
+    }
+
+    #[test]
+    fn pkcs7_rejects_bad_padding() {
+        // Zero length, longer than a block, inconsistent bytes, unaligned
+        // and empty input
+        assert_eq!(Padding::Pkcs7.unpad(b"abc\x00", 4), Err(InvalidPadding));
+        assert_eq!(Padding::Pkcs7.unpad(b"abc\x05", 4), Err(InvalidPadding));
+        assert_eq!(Padding::Pkcs7.unpad(b"ab\x01\x02", 4), Err(InvalidPadding));
+        assert_eq!(Padding::Pkcs7.unpad(b"abc\x01\x01", 4), Err(InvalidPadding));
+        assert_eq!(Padding::Pkcs7.unpad(b"", 4), Err(InvalidPadding));
+    }
+
+    #[test]
+    fn zero_padding_only_pads_when_needed() {
+        let mut data = b"abc".to_vec();
+        Padding::Zero.pad(&mut data, 4);
+        assert_eq!(data, b"abc\x00");
+        assert_eq!(Padding::Zero.unpad(&data, 4), Ok(&b"abc"[..]));
+
+        let mut data = b"abcd".to_vec();
+        Padding::Zero.pad(&mut data, 4);
+        assert_eq!(data, b"abcd");
+
+        let mut data = Vec::new();
+        Padding::Zero.pad(&mut data, 4);
+        assert!(data.is_empty());
+        assert_eq!(Padding::Zero.unpad(&data, 4), Ok(&[][..]));
+    }
+
+    #[test]
+    fn zero_padding_loses_trailing_zeros_and_rejects_unaligned_input() {
+        assert_eq!(Padding::Zero.unpad(b"ab\x00\x00", 4), Ok(&b"ab"[..]));
+        assert_eq!(Padding::Zero.unpad(b"abc", 4), Err(InvalidPadding));
+    }
+}
@@ -0,0 +1,211 @@
+// CBC Padding-Oracle Attack
+// -------------------------
+//
+// The server decrypts CBC ciphertexts and only says whether the PKCS#7
+// padding was valid. For a target block C_i the attacker sends a forged
+// previous block C' and adjusts its last bytes until the padding of
+// D(C_i) ^ C' is valid; that reveals the intermediate value D(C_i) one byte
+// at a time, and XORing it with the real previous block gives the plaintext.
+// Each byte costs at most 256 queries.
+
+use std::fmt;
+
+use crate::cipher::BlockCipher;
+use crate::modes::Cbc;
+use crate::padding::Padding;
+
+/// Anything that answers "was the padding valid?" for an IV and ciphertext
+pub trait PaddingOracle {
+    fn padding_is_valid(&mut self, iv: &[u8], ciphertext: &[u8]) -> bool;
+}
+
+/// A server that decrypts CBC/PKCS#7 messages and leaks padding validity
+#[derive(Clone, Debug)]
+pub struct CbcPaddingServer<C> {
+    cipher: C,
+    queries: u64,
+}
+
+impl<C: BlockCipher + Clone> CbcPaddingServer<C> {
+    pub fn new(cipher: C) -> Self {
+        CbcPaddingServer { cipher, queries: 0 }
+    }
+
+    /// Produce a target ciphertext the way a legitimate sender would
+    pub fn encrypt(&self, iv: &[u8], message: &[u8]) -> Vec<u8> {
+        Cbc::new(self.cipher.clone(), iv).encrypt_padded(message, Padding::Pkcs7)
+    }
+
+    /// Number of oracle queries answered so far
+    pub fn queries(&self) -> u64 {
+        self.queries
+    }
+}
+
+impl<C: BlockCipher + Clone> PaddingOracle for CbcPaddingServer<C> {
+    fn padding_is_valid(&mut self, iv: &[u8], ciphertext: &[u8]) -> bool {
+        self.queries += 1;
+        let result = Cbc::new(self.cipher.clone(), iv).decrypt_padded(ciphertext, Padding::Pkcs7);
+        result.is_ok()
+    }
+}
+
+/// Why the attack could not decrypt a ciphertext
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingOracleError {
+    /// The ciphertext is not a whole number of IV-sized blocks
+    PartialBlock { len: usize, block_size: usize },
+    /// No forged byte made the padding valid, so the oracle is not a CBC
+    /// padding check for this block size
+    NoValidByte { block: usize, position: usize },
+    /// The recovered plaintext does not end in PKCS#7 padding
+    InvalidPadding,
+}
+
+impl fmt::Display for PaddingOracleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaddingOracleError::PartialBlock { len, block_size } => write!(
+                f,
+                "ciphertext of {} bytes is not a multiple of the {}-byte block size",
+                len, block_size
+            ),
+            PaddingOracleError::NoValidByte { block, position } => {
+                write!(f, "the oracle accepted no value for byte {} of block {}", position, block)
+            }
+            PaddingOracleError::InvalidPadding => write!(f, "recovered plaintext has invalid padding"),
+        }
+    }
+}
+
+impl std::error::Error for PaddingOracleError {}
+
+/// Recover D(block) using only the padding oracle; `Err` holds the byte
+/// position no guess was accepted for
+fn recover_intermediate<O: PaddingOracle>(oracle: &mut O, block: &[u8]) -> Result<Vec<u8>, usize> {
+    let n = block.len();
+    let mut intermediate = vec![0u8; n];
+    let mut forged = vec![0u8; n];
+    for pad in 1..=n {
+        let pos = n - pad;
+        for j in pos + 1..n {
+            forged[j] = intermediate[j] ^ pad as u8;
+        }
+        let mut found = None;
+        for guess in 0..=255u8 {
+            forged[pos] = guess;
+            if !oracle.padding_is_valid(&forged, block) {
+                continue;
+            }
+            // For the last byte a valid answer may come from a longer padding
+            // such as 02 02; changing the byte before rules that out
+            if pad == 1 && pos > 0 {
+                forged[pos - 1] ^= 0xFF;
+                let still_valid = oracle.padding_is_valid(&forged, block);
+                forged[pos - 1] ^= 0xFF;
+                if !still_valid {
+                    continue;
+                }
+            }
+            found = Some(guess);
+            break;
+        }
+        let guess = found.ok_or(pos)?;
+        intermediate[pos] = guess ^ pad as u8;
+    }
+    Ok(intermediate)
+}
+
+/// Decrypt `ciphertext` (with its `iv`) block by block through the oracle
+/// and return the unpadded plaintext
+pub fn padding_oracle_attack<O: PaddingOracle>(
+    oracle: &mut O,
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, PaddingOracleError> {
+    let n = iv.len();
+    if n == 0 || !ciphertext.len().is_multiple_of(n) {
+        return Err(PaddingOracleError::PartialBlock { len: ciphertext.len(), block_size: n });
+    }
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut previous = iv;
+    for (index, block) in ciphertext.chunks_exact(n).enumerate() {
+        let intermediate = recover_intermediate(oracle, block)
+            .map_err(|position| PaddingOracleError::NoValidByte { block: index, position })?;
+        plaintext.extend(intermediate.iter().zip(previous).map(|(i, p)| i ^ p));
+        previous = block;
+    }
+    let len = Padding::Pkcs7.unpad(&plaintext, n).map_err(|_| PaddingOracleError::InvalidPadding)?.len();
+    plaintext.truncate(len);
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::Spn;
+
+    /// Accepts every forgery, like a server that does not check padding
+    struct AlwaysValid;
+
+    impl PaddingOracle for AlwaysValid {
+        fn padding_is_valid(&mut self, _iv: &[u8], _ciphertext: &[u8]) -> bool {
+            true
+        }
+    }
+
+    /// Rejects every forgery
+    struct NeverValid;
+
+    impl PaddingOracle for NeverValid {
+        fn padding_is_valid(&mut self, _iv: &[u8], _ciphertext: &[u8]) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn recovers_the_message() {
+        let mut server = CbcPaddingServer::new(Spn::new(0x1234_5678_90AB_CDEF_1234));
+        let iv = [0x91, 0x2E];
+        let target = server.encrypt(&iv, b"user=admin;pin=4321");
+        assert_eq!(padding_oracle_attack(&mut server, &iv, &target).unwrap(), b"user=admin;pin=4321");
+    }
+
+    #[test]
+    fn rejects_a_partial_trailing_block() {
+        let mut server = CbcPaddingServer::new(Spn::new(0x1234_5678_90AB_CDEF_1234));
+        assert_eq!(
+            padding_oracle_attack(&mut server, &[0, 0], &[1, 2, 3]),
+            Err(PaddingOracleError::PartialBlock { len: 3, block_size: 2 })
+        );
+    }
+
+    #[test]
+    fn server_rejects_every_malformed_ciphertext() {
+        let mut server = CbcPaddingServer::new(Spn::new(0x1234_5678_90AB_CDEF_1234));
+        let iv = [0x91, 0x2E];
+        let target = server.encrypt(&iv, b"pin=4321");
+        assert!(server.padding_is_valid(&iv, &target));
+        assert!(!server.padding_is_valid(&iv, &target[..target.len() - 1]));
+        assert!(!server.padding_is_valid(&iv, &[]));
+        assert_eq!(server.queries(), 3);
+    }
+
+    #[test]
+    fn reports_an_oracle_that_accepts_nothing() {
+        assert_eq!(
+            padding_oracle_attack(&mut NeverValid, &[0, 0], &[1, 2, 3, 4]),
+            Err(PaddingOracleError::NoValidByte { block: 0, position: 1 })
+        );
+    }
+
+    #[test]
+    fn reports_a_plaintext_without_padding() {
+        // Guess 0 is accepted for every byte, so D(C) comes out as 02 01 and
+        // the last plaintext byte as 01 ^ FF
+        assert_eq!(
+            padding_oracle_attack(&mut AlwaysValid, &[0, 0xFF], &[1, 2]),
+            Err(PaddingOracleError::InvalidPadding)
+        );
+    }
+}
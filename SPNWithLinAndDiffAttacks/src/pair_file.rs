@@ -0,0 +1,442 @@
+// Binary Pair Files
+// -----------------
+//
+// Pairs are stored as fixed-size little-endian records behind a 40-byte
+// header that records what produced them:
+//
+//   0..8    magic "SPNPAIRS"
+//   8..10   format version (1)
+//   10      kind: 0 = known plaintext, 1 = chosen plaintext
+//   11      reserved (0)
+//   12..14  delta_p (chosen plaintext only)
+//   14..16  reserved (0)
+//   16..24  config hash of the cipher, see `dataset::ConfigHasher`
+//   24..32  generation seed
+//   32..40  number of records
+//
+// A known-plaintext record is (p, c), 4 bytes. A chosen-plaintext record is
+// (p1, c1, c2), 6 bytes, since p2 = p1 ^ delta_p. Files are read through a
+// read-only memory map on Unix, so the operating system pages the records in
+// and out and the data set may be much larger than RAM; elsewhere the file is
+// read into memory, as it is on 32-bit targets where the width of `off_t`
+// varies. The file must not be truncated while it is mapped. Writing or
+// reading pairs of the other kind fails with `InvalidInput`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::dataset::ConfigHasher;
+use crate::differential::differential_counts_from_iter;
+use crate::linear::linear_counts_from_iter;
+use crate::spn::Spn;
+
+const MAGIC: &[u8; 8] = b"SPNPAIRS";
+const VERSION: u16 = 2;
+pub const HEADER_LEN: usize = 40;
+
+/// Which pair layout a file holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PairKind {
+    Known,
+    Chosen,
+}
+
+impl PairKind {
+    /// Bytes per record
+    pub fn record_len(self) -> usize {
+        match self {
+            PairKind::Known => 4,
+            PairKind::Chosen => 6,
+        }
+    }
+}
+
+/// Everything stored in front of the records
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PairFileHeader {
+    pub kind: PairKind,
+    pub delta_p: u16,
+    /// Matches the cipher without storing its keys
+    pub config_hash: u64,
+    pub seed: u64,
+    pub count: u64,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Pairs of the other kind than the file holds were pushed or requested
+fn wrong_kind(kind: PairKind) -> io::Error {
+    let holds = match kind {
+        PairKind::Known => "this file holds known-plaintext pairs",
+        PairKind::Chosen => "this file holds chosen-plaintext pairs",
+    };
+    io::Error::new(io::ErrorKind::InvalidInput, holds)
+}
+
+impl PairFileHeader {
+    /// Header for known-plaintext pairs from `cipher` generated with `seed`
+    pub fn known(cipher: &Spn, seed: u64) -> Self {
+        PairFileHeader { kind: PairKind::Known, ..Self::chosen(cipher, 0, seed) }
+    }
+
+    /// Header for chosen-plaintext pairs with input difference `delta_p`
+    pub fn chosen(cipher: &Spn, delta_p: u16, seed: u64) -> Self {
+        let config_hash = ConfigHasher::cipher(cipher.round_keys()).finish();
+        PairFileHeader { kind: PairKind::Chosen, delta_p, config_hash, seed, count: 0 }
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[0..8].copy_from_slice(MAGIC);
+        bytes[8..10].copy_from_slice(&VERSION.to_le_bytes());
+        bytes[10] = match self.kind {
+            PairKind::Known => 0,
+            PairKind::Chosen => 1,
+        };
+        bytes[12..14].copy_from_slice(&self.delta_p.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.config_hash.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.seed.to_le_bytes());
+        bytes[32..40].copy_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[0..8] != MAGIC {
+            return Err(invalid("not a pair file"));
+        }
+        if u16::from_le_bytes([bytes[8], bytes[9]]) != VERSION {
+            return Err(invalid("unsupported pair file version"));
+        }
+        let kind = match bytes[10] {
+            0 => PairKind::Known,
+            1 => PairKind::Chosen,
+            _ => return Err(invalid("unknown pair kind")),
+        };
+        let word = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let long = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        Ok(PairFileHeader {
+            kind,
+            delta_p: word(12),
+            config_hash: long(16),
+            seed: long(24),
+            count: long(32),
+        })
+    }
+}
+
+/// Appends records to a new pair file; the record count in the header is
+/// filled in by `finish`
+pub struct PairWriter {
+    out: BufWriter<File>,
+    header: PairFileHeader,
+}
+
+impl PairWriter {
+    /// Create (or truncate) `path`; `header.count` is ignored
+    pub fn create(path: &Path, header: PairFileHeader) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let header = PairFileHeader { count: 0, ..header };
+        out.write_all(&header.to_bytes())?;
+        Ok(PairWriter { out, header })
+    }
+
+    pub fn push_known(&mut self, (plain, cipher): (u16, u16)) -> io::Result<()> {
+        if self.header.kind != PairKind::Known {
+            return Err(wrong_kind(self.header.kind));
+        }
+        self.out.write_all(&plain.to_le_bytes())?;
+        self.out.write_all(&cipher.to_le_bytes())?;
+        self.header.count += 1;
+        Ok(())
+    }
+
+    /// Only p1 is stored; `p2` must be `p1 ^ delta_p`
+    pub fn push_chosen(&mut self, (p1, p2, c1, c2): (u16, u16, u16, u16)) -> io::Result<()> {
+        if self.header.kind != PairKind::Chosen {
+            return Err(wrong_kind(self.header.kind));
+        }
+        if p1 ^ p2 != self.header.delta_p {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "pair does not have the file's input difference"));
+        }
+        for word in [p1, c1, c2] {
+            self.out.write_all(&word.to_le_bytes())?;
+        }
+        self.header.count += 1;
+        Ok(())
+    }
+
+    /// Write the final record count and flush
+    pub fn finish(mut self) -> io::Result<PairFileHeader> {
+        self.out.flush()?;
+        let file = self.out.get_mut();
+        file.seek(SeekFrom::Start(32))?;
+        file.write_all(&self.header.count.to_le_bytes())?;
+        file.sync_all()?;
+        Ok(self.header)
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+mod mmap {
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    const PROT_READ: i32 = 1;
+    const MAP_PRIVATE: i32 = 2;
+
+    unsafe extern "C" {
+        fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut c_void;
+        fn munmap(addr: *mut c_void, len: usize) -> i32;
+    }
+
+    /// A read-only private mapping of a whole file
+    pub struct Mmap {
+        ptr: *mut c_void,
+        len: usize,
+    }
+
+    // SAFETY: the mapping is read-only and owned by this value
+    unsafe impl Send for Mmap {}
+    unsafe impl Sync for Mmap {}
+
+    impl Mmap {
+        pub fn map(file: &File, len: usize) -> io::Result<Self> {
+            // SAFETY: a fresh read-only mapping of `len` bytes of an open file
+            let ptr = unsafe { mmap(std::ptr::null_mut(), len, PROT_READ, MAP_PRIVATE, file.as_raw_fd(), 0) };
+            if ptr as isize == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Mmap { ptr, len })
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            // SAFETY: `ptr` points to `len` mapped, readable bytes until drop
+            unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+
+    impl Drop for Mmap {
+        fn drop(&mut self) {
+            // SAFETY: unmapping exactly the region returned by `mmap`
+            unsafe { munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+enum Storage {
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    Mapped(mmap::Mmap),
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    Owned(Vec<u8>),
+}
+
+impl Storage {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            #[cfg(all(unix, target_pointer_width = "64"))]
+            Storage::Mapped(map) => map.as_slice(),
+            #[cfg(not(all(unix, target_pointer_width = "64")))]
+            Storage::Owned(bytes) => bytes,
+        }
+    }
+}
+
+/// A pair file opened for reading, records accessed in place
+pub struct MappedPairFile {
+    storage: Storage,
+    header: PairFileHeader,
+}
+
+impl MappedPairFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len < HEADER_LEN {
+            return Err(invalid("not a pair file"));
+        }
+        #[cfg(all(unix, target_pointer_width = "64"))]
+        let storage = Storage::Mapped(mmap::Mmap::map(&file, len)?);
+        #[cfg(not(all(unix, target_pointer_width = "64")))]
+        let storage = Storage::Owned(std::fs::read(path)?);
+
+        let header = PairFileHeader::parse(storage.bytes())?;
+        let expected = (header.count as usize)
+            .checked_mul(header.kind.record_len())
+            .and_then(|records| records.checked_add(HEADER_LEN));
+        if expected != Some(storage.bytes().len()) {
+            return Err(invalid("pair file length does not match its record count"));
+        }
+        Ok(MappedPairFile { storage, header })
+    }
+
+    pub fn header(&self) -> &PairFileHeader {
+        &self.header
+    }
+
+    pub fn len(&self) -> usize {
+        self.header.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.count == 0
+    }
+
+    fn records(&self, range: Range<usize>) -> impl Iterator<Item = &[u8]> {
+        let record_len = self.header.kind.record_len();
+        self.storage.bytes()[HEADER_LEN + range.start * record_len..HEADER_LEN + range.end * record_len]
+            .chunks_exact(record_len)
+    }
+
+    fn check_kind(&self, kind: PairKind) -> io::Result<()> {
+        if self.header.kind != kind {
+            return Err(wrong_kind(self.header.kind));
+        }
+        Ok(())
+    }
+
+    /// Records in `range` read as known pairs, whatever the file holds
+    fn known_records(&self, range: Range<usize>) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.records(range).map(|r| (u16::from_le_bytes([r[0], r[1]]), u16::from_le_bytes([r[2], r[3]])))
+    }
+
+    /// Records in `range` read as chosen pairs, whatever the file holds
+    fn chosen_records(&self, range: Range<usize>) -> impl Iterator<Item = (u16, u16, u16, u16)> + '_ {
+        let delta_p = self.header.delta_p;
+        self.records(range).map(move |r| {
+            let p1 = u16::from_le_bytes([r[0], r[1]]);
+            (p1, p1 ^ delta_p, u16::from_le_bytes([r[2], r[3]]), u16::from_le_bytes([r[4], r[5]]))
+        })
+    }
+
+    /// Known-plaintext pairs in `range` as (plaintext, ciphertext)
+    pub fn known_pairs(&self, range: Range<usize>) -> io::Result<impl Iterator<Item = (u16, u16)> + '_> {
+        self.check_kind(PairKind::Known)?;
+        Ok(self.known_records(range))
+    }
+
+    /// Chosen-plaintext pairs in `range` as (p1, p2, c1, c2)
+    pub fn chosen_pairs(&self, range: Range<usize>) -> io::Result<impl Iterator<Item = (u16, u16, u16, u16)> + '_> {
+        self.check_kind(PairKind::Chosen)?;
+        Ok(self.chosen_records(range))
+    }
+
+    /// `linear::linear_counts` streamed over every record in the file
+    pub fn linear_counts(&self, alpha: u16, beta: u16, nibble_idx: usize) -> io::Result<[u64; 16]> {
+        self.check_kind(PairKind::Known)?;
+        #[cfg(feature = "parallel")]
+        return Ok(crate::parallel::count_ranges_in_parallel(self.len(), |range| {
+            linear_counts_from_iter(self.known_records(range), alpha, beta, nibble_idx)
+        }));
+        #[cfg(not(feature = "parallel"))]
+        Ok(linear_counts_from_iter(self.known_records(0..self.len()), alpha, beta, nibble_idx))
+    }
+
+    /// `differential::differential_counts` streamed over every record in the
+    /// file
+    pub fn differential_counts(&self, delta_u: u16, nibble_idx: usize) -> io::Result<[u64; 16]> {
+        self.check_kind(PairKind::Chosen)?;
+        let delta_p = self.header.delta_p;
+        #[cfg(feature = "parallel")]
+        return Ok(crate::parallel::count_ranges_in_parallel(self.len(), |range| {
+            differential_counts_from_iter(self.chosen_records(range), delta_p, delta_u, nibble_idx)
+        }));
+        #[cfg(not(feature = "parallel"))]
+        Ok(differential_counts_from_iter(self.chosen_records(0..self.len()), delta_p, delta_u, nibble_idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::differential::differential_counts;
+    use crate::linear::linear_counts;
+    use crate::pairs::{chosen_plaintext_pairs, known_plaintext_pairs};
+    use std::path::PathBuf;
+
+    /// A file in the temporary directory, removed on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            TempFile(std::env::temp_dir().join(format!("spn_pair_file_{}_{}.bin", name, std::process::id())))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn cipher() -> Spn {
+        Spn::new(0x1234_5678_90AB_CDEF_1234)
+    }
+
+    #[test]
+    fn known_pairs_round_trip_and_count_like_memory() {
+        let file = TempFile::new("known");
+        let pairs = known_plaintext_pairs(&cipher(), 1000, 1);
+        let mut writer = PairWriter::create(&file.0, PairFileHeader::known(&cipher(), 1)).unwrap();
+        pairs.iter().try_for_each(|&pair| writer.push_known(pair)).unwrap();
+        let header = writer.finish().unwrap();
+        assert_eq!(header.count, 1000);
+
+        let mapped = MappedPairFile::open(&file.0).unwrap();
+        assert_eq!(*mapped.header(), header);
+        assert_eq!(mapped.known_pairs(0..mapped.len()).unwrap().collect::<Vec<_>>(), pairs);
+        assert_eq!(mapped.linear_counts(0x0B00, 0x0400, 2).unwrap(), linear_counts(&pairs, 0x0B00, 0x0400, 2));
+    }
+
+    #[test]
+    fn chosen_pairs_round_trip_and_count_like_memory() {
+        let file = TempFile::new("chosen");
+        let pairs = chosen_plaintext_pairs(&cipher(), 0x0040, 500, 2);
+        let mut writer = PairWriter::create(&file.0, PairFileHeader::chosen(&cipher(), 0x0040, 2)).unwrap();
+        pairs.iter().try_for_each(|&pair| writer.push_chosen(pair)).unwrap();
+        writer.finish().unwrap();
+
+        let mapped = MappedPairFile::open(&file.0).unwrap();
+        assert_eq!(mapped.chosen_pairs(0..mapped.len()).unwrap().collect::<Vec<_>>(), pairs);
+        assert_eq!(mapped.differential_counts(0x0060, 1).unwrap(), differential_counts(&pairs, 0x0040, 0x0060, 1));
+    }
+
+    #[test]
+    fn header_does_not_hold_the_keys() {
+        let bytes = PairFileHeader::known(&cipher(), 1).to_bytes();
+        for key in cipher().round_keys() {
+            assert!(!bytes.windows(2).any(|w| w == key.to_le_bytes()), "{:04X}", key);
+        }
+    }
+
+    #[test]
+    fn rejects_a_length_that_does_not_match_the_count() {
+        let file = TempFile::new("truncated");
+        let mut writer = PairWriter::create(&file.0, PairFileHeader::known(&cipher(), 1)).unwrap();
+        writer.push_known((1, 2)).unwrap();
+        writer.push_known((3, 4)).unwrap();
+        writer.finish().unwrap();
+        let bytes = std::fs::read(&file.0).unwrap();
+        std::fs::write(&file.0, &bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(MappedPairFile::open(&file.0).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_pairs_of_the_other_kind() {
+        let file = TempFile::new("kind");
+        let mut writer = PairWriter::create(&file.0, PairFileHeader::chosen(&cipher(), 0x0040, 1)).unwrap();
+        assert_eq!(writer.push_known((1, 2)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(writer.push_chosen((1, 2, 3, 4)).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        writer.push_chosen((1, 0x41, 3, 4)).unwrap();
+        writer.finish().unwrap();
+
+        let mapped = MappedPairFile::open(&file.0).unwrap();
+        assert_eq!(mapped.known_pairs(0..1).err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(mapped.linear_counts(0x0B00, 0x0400, 2).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,120 @@
+// Pair Generation
+// ---------------
+//
+// Plaintexts are drawn in fixed chunks of `CHUNK_SIZE`, chunk k using the
+// generator `SplitMix64::new(seed).split(k)`. The data set is therefore a
+// function of the seed alone: with the `parallel` feature the chunks are
+// spread over all cores and the result is identical to a serial run. Pairs
+// collected through an `EncryptionOracle` use the same plaintexts, so a
+// remote run can be compared with a local one. The `_with` variants draw
+// from any `RandomSource` in order instead, e.g. one that records its draws.
+
+use std::io;
+
+use crate::oracle::EncryptionOracle;
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::Spn;
+
+/// Pairs generated per RNG sub-stream
+pub const CHUNK_SIZE: usize = 4096;
+
+fn generate<T: Send + Default + Clone>(count: usize, seed: u64, fill_chunk: impl Fn(&mut SplitMix64, &mut [T]) + Sync) -> Vec<T> {
+    let root = SplitMix64::new(seed);
+    let mut output = vec![T::default(); count];
+    let fill = |index: usize, chunk: &mut [T]| fill_chunk(&mut root.split(index as u64), chunk);
+    #[cfg(feature = "parallel")]
+    crate::parallel::fill_chunks(&mut output, CHUNK_SIZE, fill);
+    #[cfg(not(feature = "parallel"))]
+    for (index, chunk) in output.chunks_mut(CHUNK_SIZE).enumerate() {
+        fill(index, chunk);
+    }
+    output
+}
+
+/// Random known-plaintext (plaintext, ciphertext) pairs for `linear_attack`
+pub fn known_plaintext_pairs(cipher: &Spn, count: usize, seed: u64) -> Vec<(u16, u16)> {
+    generate(count, seed, |rng, chunk: &mut [(u16, u16)]| {
+        let mut blocks = [0u16; CHUNK_SIZE];
+        let blocks = &mut blocks[..chunk.len()];
+        for (pair, block) in chunk.iter_mut().zip(blocks.iter_mut()) {
+            pair.0 = rng.next_u16();
+            *block = pair.0;
+        }
+        cipher.encrypt_blocks(blocks);
+        for (pair, &block) in chunk.iter_mut().zip(blocks.iter()) {
+            pair.1 = block;
+        }
+    })
+}
+
+/// Random chosen-plaintext (p1, p2, c1, c2) pairs with p1 ^ p2 = `delta_p`
+/// for `differential_attack`
+pub fn chosen_plaintext_pairs(cipher: &Spn, delta_p: u16, count: usize, seed: u64) -> Vec<(u16, u16, u16, u16)> {
+    generate(count, seed, |rng, chunk: &mut [(u16, u16, u16, u16)]| {
+        // p1 of every pair, then p2 of every pair
+        let mut blocks = [0u16; 2 * CHUNK_SIZE];
+        let (first, second) = blocks[..2 * chunk.len()].split_at_mut(chunk.len());
+        for ((pair, b1), b2) in chunk.iter_mut().zip(first.iter_mut()).zip(second.iter_mut()) {
+            let p1 = rng.next_u16();
+            *pair = (p1, p1 ^ delta_p, 0, 0);
+            (*b1, *b2) = (pair.0, pair.1);
+        }
+        cipher.encrypt_blocks(first);
+        cipher.encrypt_blocks(second);
+        for ((pair, &c1), &c2) in chunk.iter_mut().zip(first.iter()).zip(second.iter()) {
+            (pair.2, pair.3) = (c1, c2);
+        }
+    })
+}
+
+/// `known_plaintext_pairs` with plaintexts drawn in order from `rng`
+pub fn known_plaintext_pairs_with(cipher: &Spn, count: usize, rng: &mut impl RandomSource) -> Vec<(u16, u16)> {
+    let plaintexts: Vec<u16> = (0..count).map(|_| rng.next_u16()).collect();
+    let mut ciphertexts = plaintexts.clone();
+    cipher.encrypt_blocks(&mut ciphertexts);
+    plaintexts.into_iter().zip(ciphertexts).collect()
+}
+
+/// `chosen_plaintext_pairs` with the p1 drawn in order from `rng`
+pub fn chosen_plaintext_pairs_with(cipher: &Spn, delta_p: u16, count: usize, rng: &mut impl RandomSource) -> Vec<(u16, u16, u16, u16)> {
+    (0..count)
+        .map(|_| {
+            let p1 = rng.next_u16();
+            (p1, p1 ^ delta_p, cipher.encrypt(p1), cipher.encrypt(p1 ^ delta_p))
+        })
+        .collect()
+}
+
+/// Plaintexts in the order `generate` draws them
+fn plaintexts(count: usize, seed: u64) -> Vec<u16> {
+    let root = SplitMix64::new(seed);
+    (0..count.div_ceil(CHUNK_SIZE))
+        .flat_map(|index| {
+            let mut rng = root.split(index as u64);
+            let len = CHUNK_SIZE.min(count - index * CHUNK_SIZE);
+            (0..len).map(move |_| rng.next_u16())
+        })
+        .collect()
+}
+
+/// `known_plaintext_pairs` with the ciphertexts requested from `oracle`
+pub fn known_plaintext_pairs_from<O: EncryptionOracle>(oracle: &mut O, count: usize, seed: u64) -> io::Result<Vec<(u16, u16)>> {
+    let plaintexts = plaintexts(count, seed);
+    let mut ciphertexts = plaintexts.clone();
+    oracle.query(&mut ciphertexts)?;
+    Ok(plaintexts.into_iter().zip(ciphertexts).collect())
+}
+
+/// `chosen_plaintext_pairs` with the ciphertexts requested from `oracle`
+pub fn chosen_plaintext_pairs_from<O: EncryptionOracle>(
+    oracle: &mut O,
+    delta_p: u16,
+    count: usize,
+    seed: u64,
+) -> io::Result<Vec<(u16, u16, u16, u16)>> {
+    let first = plaintexts(count, seed);
+    let mut blocks: Vec<u16> = first.iter().copied().chain(first.iter().map(|&p1| p1 ^ delta_p)).collect();
+    oracle.query(&mut blocks)?;
+    let (c1, c2) = blocks.split_at(count);
+    Ok((0..count).map(|i| (first[i], first[i] ^ delta_p, c1[i], c2[i])).collect())
+}
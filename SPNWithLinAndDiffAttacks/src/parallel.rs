@@ -0,0 +1,77 @@
+// Multi-Threaded Counting and Generation
+// --------------------------------------
+//
+// Candidate counting is independent per pair, so the pair set is cut into one
+// contiguous chunk per thread, every thread fills its own counters, and the
+// counters are added up once all threads are done. Data generation works on
+// fixed-size numbered chunks so its output does not depend on the thread
+// count.
+
+use std::ops::Range;
+use std::thread;
+
+/// Number of worker threads: one per available core
+pub fn thread_count() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Run `count` over chunks of `items` on all cores and merge the counters
+pub fn count_in_parallel<T, F>(items: &[T], count: F) -> [u64; 16]
+where
+    T: Sync,
+    F: Fn(&[T]) -> [u64; 16] + Sync,
+{
+    count_ranges_in_parallel(items.len(), |range| count(&items[range]))
+}
+
+/// Split `0..len` into one contiguous range per thread, run `count` on each
+/// and merge the counters; for data not stored as a single slice
+pub fn count_ranges_in_parallel<F>(len: usize, count: F) -> [u64; 16]
+where
+    F: Fn(Range<usize>) -> [u64; 16] + Sync,
+{
+    let chunk_size = len.div_ceil(thread_count()).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..len)
+            .step_by(chunk_size)
+            .map(|start| {
+                let count = &count;
+                scope.spawn(move || count(start..(start + chunk_size).min(len)))
+            })
+            .collect();
+        let mut total = [0u64; 16];
+        for worker in workers {
+            let counts = worker.join().expect("counting thread panicked");
+            for (t, c) in total.iter_mut().zip(counts) {
+                *t += c;
+            }
+        }
+        total
+    })
+}
+
+/// Fill `output` in fixed-size chunks spread over all cores; `fill` gets the
+/// chunk index and the chunk to write
+pub fn fill_chunks<T, F>(output: &mut [T], chunk_size: usize, fill: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync,
+{
+    let chunks: Vec<(usize, &mut [T])> = output.chunks_mut(chunk_size).enumerate().collect();
+    let per_thread = chunks.len().div_ceil(thread_count()).max(1);
+    let mut chunks = chunks.into_iter();
+    thread::scope(|scope| {
+        loop {
+            let batch: Vec<_> = chunks.by_ref().take(per_thread).collect();
+            if batch.is_empty() {
+                break;
+            }
+            let fill = &fill;
+            scope.spawn(move || {
+                for (index, chunk) in batch {
+                    fill(index, chunk);
+                }
+            });
+        }
+    });
+}
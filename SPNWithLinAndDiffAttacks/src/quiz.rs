@@ -0,0 +1,323 @@
+// Exercise Generator
+// ------------------
+//
+// Randomized problem sets for homework, with solutions a script can check.
+// Three kinds of question, each answered with the crate's own analysis:
+//
+//   bias          the bias of an input/output mask pair of a random S-box,
+//                 from its LAT
+//   propagation   a difference taken through rounds of S-boxes and the
+//                 P-box, every active S-box following its most likely output
+//                 difference (the smallest on ties), with the probability
+//   counters      the key nibble suggested by the counters of a linear or
+//                 differential attack, run on fresh pairs under a random key
+//
+// Question i of a set is drawn from `SplitMix64::new(seed).split(i)`, so a
+// set is rebuilt from its seed to mark answers. Answers are one per line,
+// `<number> = <answer>`: biases and probabilities as decimals or fractions
+// such as -1/4, differences and nibbles in hex.
+
+use std::fmt::Write as _;
+use std::io;
+
+use crate::differential::differential_counts;
+use crate::linear::linear_counts;
+use crate::pairs::{chosen_plaintext_pairs, known_plaintext_pairs};
+use crate::results::AttackKind;
+use crate::rng::{RandomSource, SplitMix64};
+use crate::sbox::Sbox;
+use crate::spn::{nibble, pbox, random_master_key, Spn};
+
+/// Rounds a difference is propagated through
+const PROPAGATION_ROUNDS: usize = 2;
+
+/// Largest error accepted on a bias or probability
+const TOLERANCE: f64 = 1e-6;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A decimal or a fraction `n/d`
+fn parse_number(text: &str) -> Option<f64> {
+    match text.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator: f64 = denominator.trim().parse().ok()?;
+            (denominator != 0.0).then_some(numerator.trim().parse::<f64>().ok()? / denominator)
+        }
+        None => text.parse().ok(),
+    }
+}
+
+fn parse_hex(text: &str) -> Option<u16> {
+    let text = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u16::from_str_radix(text, 16).ok()
+}
+
+fn sbox_row(sbox: &Sbox) -> String {
+    let entries: Vec<String> = sbox.table().iter().map(|y| format!("{:X}", y)).collect();
+    entries.join(" ")
+}
+
+/// One step of a propagation: the difference entering the S-boxes, leaving
+/// them, and the probability of that S-box layer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PropagationStep {
+    pub input: u16,
+    pub output: u16,
+    pub probability: f64,
+}
+
+/// Take `difference` through `rounds` S-box layers and P-boxes, each active
+/// S-box following its most likely output difference
+pub fn propagate(sbox: &Sbox, difference: u16, rounds: usize) -> Vec<PropagationStep> {
+    let mut steps = Vec::with_capacity(rounds);
+    let mut input = difference;
+    for _ in 0..rounds {
+        let (mut output, mut probability) = (0u16, 1.0);
+        for idx in (0..4).filter(|&idx| nibble(input, idx) != 0) {
+            let row = &sbox.ddt()[nibble(input, idx) as usize];
+            // First maximum, so ties go to the smallest difference
+            let best = (1..16).fold(1, |best, b| if row[b] > row[best] { b } else { best });
+            output |= (best as u16) << (4 * idx);
+            probability *= row[best] as f64 / 16.0;
+        }
+        steps.push(PropagationStep { input, output, probability });
+        input = pbox(output);
+    }
+    steps
+}
+
+/// One exercise
+#[derive(Clone, Debug)]
+pub enum Question {
+    Bias { sbox: Sbox, input_mask: u8, output_mask: u8 },
+    Propagation { sbox: Sbox, difference: u16 },
+    /// Counters of `linear_counts` (0x0B00 -> 0x0400, nibble 2) or
+    /// `differential_counts` (0x0040 -> 0x0060, nibble 1) over `pairs` pairs
+    Counters { attack: AttackKind, pairs: usize, counts: [u64; 16] },
+}
+
+impl Question {
+    pub fn random(rng: &mut SplitMix64) -> Self {
+        match rng.below(3) {
+            0 => Question::Bias {
+                sbox: Sbox::random(rng),
+                input_mask: 1 + rng.below(15) as u8,
+                output_mask: 1 + rng.below(15) as u8,
+            },
+            1 => {
+                // One or two active S-boxes
+                let mut difference = (1 + rng.below(15) as u16) << (4 * rng.below(4));
+                if rng.below(2) == 0 {
+                    difference |= (1 + rng.below(15) as u16) << (4 * rng.below(4));
+                }
+                Question::Propagation { sbox: Sbox::random(rng), difference }
+            }
+            _ => {
+                let cipher = Spn::new(random_master_key(rng));
+                let pairs = 1000 * (2 + rng.below(9) as usize);
+                let seed = rng.next_u64();
+                if rng.below(2) == 0 {
+                    let counts = linear_counts(&known_plaintext_pairs(&cipher, pairs, seed), 0x0B00, 0x0400, 2);
+                    Question::Counters { attack: AttackKind::Linear, pairs, counts }
+                } else {
+                    let counts = differential_counts(&chosen_plaintext_pairs(&cipher, 0x0040, pairs, seed), 0x0040, 0x0060, 1);
+                    Question::Counters { attack: AttackKind::Differential, pairs, counts }
+                }
+            }
+        }
+    }
+
+    /// The question in Markdown
+    pub fn prompt(&self) -> String {
+        match self {
+            Question::Bias { sbox, input_mask, output_mask } => format!(
+                "The S-box maps 0..F to `{}`. Compute the bias of the linear approximation with input mask {:X} and \
+                 output mask {:X}.",
+                sbox_row(sbox),
+                input_mask,
+                output_mask
+            ),
+            Question::Propagation { sbox, difference } => format!(
+                "The S-box maps 0..F to `{}`, and the P-box sends bit i of nibble j to bit j of nibble i. Propagate the \
+                 difference {:04X} through {} rounds of S-boxes and P-box, letting every active S-box take its most \
+                 likely output difference (the smallest one on ties). Give the difference after the last P-box and \
+                 the probability of the path.",
+                sbox_row(sbox),
+                difference,
+                PROPAGATION_ROUNDS
+            ),
+            Question::Counters { attack: AttackKind::Linear, pairs, counts } => format!(
+                "A linear attack with plaintext mask 0B00 and mask 0400 before the last S-box layer counts, over {} \
+                 known pairs, how often the approximation holds for each candidate of last-round key nibble 2: {}. \
+                 Which key nibble do these counters suggest?",
+                pairs,
+                counter_list(counts)
+            ),
+            Question::Counters { pairs, counts, .. } => format!(
+                "A differential attack with plaintext difference 0040 and difference 0060 before the last S-box layer \
+                 counts, over {} chosen pairs, the right pairs for each candidate of last-round key nibble 1: {}. \
+                 Which key nibble do these counters suggest?",
+                pairs,
+                counter_list(counts)
+            ),
+        }
+    }
+
+    /// Candidates with the best score: largest |count / pairs - 1/2| for a
+    /// linear attack, largest count for a differential one
+    fn best_candidates(&self) -> Vec<u8> {
+        let Question::Counters { attack, pairs, counts } = self else {
+            return Vec::new();
+        };
+        let score = |count: u64| match attack {
+            AttackKind::Linear => (count as f64 / *pairs as f64 - 0.5).abs(),
+            _ => count as f64,
+        };
+        let best = counts.iter().map(|&count| score(count)).fold(f64::MIN, f64::max);
+        (0..16u8).filter(|&candidate| score(counts[candidate as usize]) == best).collect()
+    }
+
+    /// The expected answer in the answer-file format
+    pub fn answer(&self) -> String {
+        match self {
+            Question::Bias { sbox, input_mask, output_mask } => {
+                format!("{}/16", sbox.lat()[*input_mask as usize][*output_mask as usize])
+            }
+            Question::Propagation { sbox, difference } => {
+                let steps = propagate(sbox, *difference, PROPAGATION_ROUNDS);
+                let probability: f64 = steps.iter().map(|step| step.probability).product();
+                format!("{:04X} {}", pbox(steps[PROPAGATION_ROUNDS - 1].output), probability)
+            }
+            Question::Counters { .. } => format!("{:X}", self.best_candidates()[0]),
+        }
+    }
+
+    /// Whether `answer` is right; any of several tied key nibbles is
+    pub fn check(&self, answer: &str) -> bool {
+        let answer = answer.trim();
+        match self {
+            Question::Bias { sbox, input_mask, output_mask } => {
+                let bias = sbox.lat()[*input_mask as usize][*output_mask as usize] as f64 / 16.0;
+                parse_number(answer).is_some_and(|value| (value - bias).abs() <= TOLERANCE)
+            }
+            Question::Propagation { sbox, difference } => {
+                let steps = propagate(sbox, *difference, PROPAGATION_ROUNDS);
+                let probability: f64 = steps.iter().map(|step| step.probability).product();
+                let mut parts = answer.split_whitespace();
+                match (parts.next().and_then(parse_hex), parts.next().and_then(parse_number), parts.next()) {
+                    (Some(output), Some(value), None) => {
+                        output == pbox(steps[PROPAGATION_ROUNDS - 1].output) && (value - probability).abs() <= TOLERANCE
+                    }
+                    _ => false,
+                }
+            }
+            Question::Counters { .. } => {
+                parse_hex(answer).is_some_and(|candidate| candidate < 16 && self.best_candidates().contains(&(candidate as u8)))
+            }
+        }
+    }
+
+    /// Worked solution in Markdown
+    pub fn explanation(&self) -> String {
+        match self {
+            Question::Bias { sbox, input_mask, output_mask } => {
+                let entry = sbox.lat()[*input_mask as usize][*output_mask as usize];
+                format!(
+                    "<{:X}, x> = <{:X}, S(x)> holds for {} of the 16 inputs x, so the bias is {}/16 - 1/2 = {}/16 = {}.",
+                    input_mask,
+                    output_mask,
+                    entry + 8,
+                    entry + 8,
+                    entry,
+                    entry as f64 / 16.0
+                )
+            }
+            Question::Propagation { sbox, difference } => {
+                let steps = propagate(sbox, *difference, PROPAGATION_ROUNDS);
+                let mut text = String::new();
+                for (round, step) in steps.iter().enumerate() {
+                    let sboxes: Vec<String> = (0..4)
+                        .filter(|&idx| nibble(step.input, idx) != 0)
+                        .map(|idx| {
+                            let (a, b) = (nibble(step.input, idx), nibble(step.output, idx));
+                            format!("S{}: {:X} → {:X} ({}/16)", idx, a, b, sbox.ddt()[a as usize][b as usize])
+                        })
+                        .collect();
+                    writeln!(
+                        text,
+                        "Round {}: {:04X} enters the S-boxes; {}; leaves as {:04X}, {:04X} after the P-box, probability {}.",
+                        round + 1,
+                        step.input,
+                        sboxes.join(", "),
+                        step.output,
+                        pbox(step.output),
+                        step.probability
+                    )
+                    .unwrap();
+                }
+                let probability: f64 = steps.iter().map(|step| step.probability).product();
+                write!(text, "The path has probability {}.", probability).unwrap();
+                text
+            }
+            Question::Counters { attack, pairs, .. } => {
+                let best: Vec<String> = self.best_candidates().iter().map(|c| format!("{:X}", c)).collect();
+                match attack {
+                    AttackKind::Linear => format!(
+                        "Score each candidate by |count / {} - 1/2|; the largest deviation from one half is candidate {}.",
+                        pairs,
+                        best.join(" or ")
+                    ),
+                    _ => format!("The right key produces the most right pairs: candidate {}.", best.join(" or ")),
+                }
+            }
+        }
+    }
+}
+
+fn counter_list(counts: &[u64; 16]) -> String {
+    let entries: Vec<String> = counts.iter().enumerate().map(|(candidate, count)| format!("{:X}: {}", candidate, count)).collect();
+    entries.join(", ")
+}
+
+/// `count` questions from `seed`
+pub fn problem_set(count: usize, seed: u64) -> Vec<Question> {
+    let root = SplitMix64::new(seed);
+    (0..count).map(|i| Question::random(&mut root.split(i as u64))).collect()
+}
+
+/// The questions as a Markdown sheet
+pub fn problem_sheet(questions: &[Question], seed: u64) -> String {
+    let mut sheet = format!("# Problem set {}\n\nAnswer each question on its own line as `<number> = <answer>`.\n\n", seed);
+    for (i, question) in questions.iter().enumerate() {
+        writeln!(sheet, "{}. {}\n", i + 1, question.prompt()).unwrap();
+    }
+    sheet
+}
+
+/// Worked solutions in Markdown, then the answer key in the answer-file format
+pub fn solution_sheet(questions: &[Question], seed: u64) -> String {
+    let mut sheet = format!("# Solutions to problem set {}\n\n", seed);
+    for (i, question) in questions.iter().enumerate() {
+        writeln!(sheet, "{}. {}\n", i + 1, question.explanation()).unwrap();
+    }
+    sheet.push_str("## Answer key\n\n```\n");
+    for (i, question) in questions.iter().enumerate() {
+        writeln!(sheet, "{} = {}", i + 1, question.answer()).unwrap();
+    }
+    sheet.push_str("```\n");
+    sheet
+}
+
+/// Mark an answer file: per question, whether it was answered correctly
+pub fn mark(questions: &[Question], answers: &str) -> io::Result<Vec<bool>> {
+    let mut marks = vec![false; questions.len()];
+    for line in answers.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let (number, answer) = line.split_once('=').ok_or_else(|| invalid("expected <number> = <answer>"))?;
+        let number: usize = number.trim().parse().map_err(|_| invalid("question numbers are whole numbers"))?;
+        let question = questions.get(number.wrapping_sub(1)).ok_or_else(|| invalid("no such question"))?;
+        marks[number - 1] = question.check(answer);
+    }
+    Ok(marks)
+}
@@ -0,0 +1,220 @@
+// Remote Encryption Oracle
+// ------------------------
+//
+// A TCP server that encrypts blocks under a key the client never sees, for
+// CTF-style key-recovery exercises, and a client that is an
+// `EncryptionOracle`, so the pair collection and attacks run unchanged
+// against it. The protocol is line-based text and works from netcat:
+//
+//   ENC <hex block> [<hex block> ...]   ->  OK <hex ciphertext> ...
+//   QUERIES                             ->  OK <blocks encrypted so far>
+//   QUIT                                    closes the connection
+//
+// Anything else, more than `MAX_BLOCKS_PER_LINE` blocks, or a request past
+// the query budget gets `ERR <reason>`. A line longer than `MAX_LINE_BYTES`
+// gets `ERR` and the connection is closed, as the rest of it is never read
+// into memory. With a rate limit the server delays
+// its answers so that each connection is served at most that many blocks per
+// second.
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::oracle::EncryptionOracle;
+use crate::spn::Spn;
+
+/// Most blocks accepted in one `ENC` line
+pub const MAX_BLOCKS_PER_LINE: usize = 4096;
+
+/// Longest request line read; room for `MAX_BLOCKS_PER_LINE` blocks with
+/// leading zeros and extra spaces
+pub const MAX_LINE_BYTES: usize = 8 * (MAX_BLOCKS_PER_LINE + 1);
+
+/// Limits applied by the server
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ServerLimits {
+    /// Blocks per second per connection; `None` is unlimited
+    pub blocks_per_second: Option<f64>,
+    /// Blocks encrypted over the server's lifetime before it refuses;
+    /// `None` is unlimited
+    pub query_budget: Option<u64>,
+}
+
+/// Encryption oracle listening on a TCP socket
+pub struct OracleServer {
+    listener: TcpListener,
+    cipher: Spn,
+    limits: ServerLimits,
+    queries: Arc<AtomicU64>,
+}
+
+impl OracleServer {
+    /// Bind to `addr`; port 0 picks a free port (see `local_addr`)
+    pub fn bind(addr: impl ToSocketAddrs, cipher: Spn, limits: ServerLimits) -> io::Result<Self> {
+        if let Some(rate) = limits.blocks_per_second {
+            assert!(rate > 0.0, "the rate limit must be positive");
+        }
+        let listener = TcpListener::bind(addr)?;
+        Ok(OracleServer { listener, cipher, limits, queries: Arc::new(AtomicU64::new(0)) })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Blocks encrypted so far over all connections; stays valid after the
+    /// server moves into `serve`
+    pub fn query_counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.queries)
+    }
+
+    /// Accept connections forever, one thread each
+    pub fn serve(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let session = Session {
+                cipher: self.cipher.clone(),
+                limits: self.limits,
+                queries: Arc::clone(&self.queries),
+                started: Instant::now(),
+                served: 0,
+            };
+            thread::spawn(move || {
+                // A client going away mid-request is not the server's problem
+                let _ = session.run(stream);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// One client connection
+struct Session {
+    cipher: Spn,
+    limits: ServerLimits,
+    queries: Arc<AtomicU64>,
+    started: Instant,
+    served: u64,
+}
+
+impl Session {
+    fn run(mut self, stream: TcpStream) -> io::Result<()> {
+        let mut out = BufWriter::new(stream.try_clone()?);
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let n = (&mut reader).take(MAX_LINE_BYTES as u64).read_line(&mut line)?;
+            if n == 0 {
+                break;
+            }
+            if n == MAX_LINE_BYTES && !line.ends_with('\n') {
+                writeln!(out, "ERR line longer than {} bytes", MAX_LINE_BYTES)?;
+                out.flush()?;
+                break;
+            }
+            let mut words = line.split_whitespace();
+            let reply = match words.next() {
+                Some("ENC") => self.encrypt(words),
+                Some("QUERIES") => Ok(format!("OK {}", self.queries.load(Ordering::Relaxed))),
+                Some("QUIT") => break,
+                _ => Err("unknown command".to_string()),
+            };
+            match reply {
+                Ok(reply) => writeln!(out, "{}", reply)?,
+                Err(reason) => writeln!(out, "ERR {}", reason)?,
+            }
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn encrypt<'a>(&mut self, words: impl Iterator<Item = &'a str>) -> Result<String, String> {
+        // Count before parsing so an overlong request is refused unparsed
+        let words: Vec<&str> = words.take(MAX_BLOCKS_PER_LINE + 1).collect();
+        if words.len() > MAX_BLOCKS_PER_LINE {
+            return Err(format!("at most {} blocks per line", MAX_BLOCKS_PER_LINE));
+        }
+        let mut blocks = words
+            .iter()
+            .map(|word| u16::from_str_radix(word, 16).map_err(|_| format!("bad block {}", word)))
+            .collect::<Result<Vec<u16>, String>>()?;
+        let n = blocks.len() as u64;
+        let previous = self.queries.fetch_add(n, Ordering::Relaxed);
+        if self.limits.query_budget.is_some_and(|budget| previous + n > budget) {
+            self.queries.fetch_sub(n, Ordering::Relaxed);
+            return Err("query budget exhausted".to_string());
+        }
+        if let Some(rate) = self.limits.blocks_per_second {
+            // Hold the answer until this connection is back under the rate
+            let due = Duration::from_secs_f64((self.served + n) as f64 / rate);
+            thread::sleep(due.saturating_sub(self.started.elapsed()));
+        }
+        self.served += n;
+        self.cipher.encrypt_blocks(&mut blocks);
+        let words: Vec<String> = blocks.iter().map(|c| format!("{:04X}", c)).collect();
+        Ok(format!("OK {}", words.join(" ")).trim_end().to_string())
+    }
+}
+
+/// Client side of `OracleServer`
+pub struct RemoteOracle {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+fn server_error(message: &str) -> io::Error {
+    io::Error::other(message.to_string())
+}
+
+impl RemoteOracle {
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        Ok(RemoteOracle { reader: BufReader::new(stream.try_clone()?), writer: BufWriter::new(stream) })
+    }
+
+    /// Send one request line and return the words after `OK`
+    fn request(&mut self, line: &str) -> io::Result<Vec<String>> {
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        let mut reply = String::new();
+        if self.reader.read_line(&mut reply)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "server closed the connection"));
+        }
+        match reply.trim_end().split_once(' ').unwrap_or((reply.trim_end(), "")) {
+            ("OK", rest) => Ok(rest.split_whitespace().map(str::to_string).collect()),
+            ("ERR", reason) => Err(server_error(reason)),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "malformed reply")),
+        }
+    }
+
+    /// Blocks the server has encrypted so far, over all clients
+    pub fn server_queries(&mut self) -> io::Result<u64> {
+        let words = self.request("QUERIES")?;
+        words
+            .first()
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed query count"))
+    }
+}
+
+impl EncryptionOracle for RemoteOracle {
+    fn query(&mut self, blocks: &mut [u16]) -> io::Result<()> {
+        for chunk in blocks.chunks_mut(MAX_BLOCKS_PER_LINE) {
+            let words: Vec<String> = chunk.iter().map(|b| format!("{:04X}", b)).collect();
+            let replies = self.request(&format!("ENC {}", words.join(" ")))?;
+            if replies.len() != chunk.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "wrong number of ciphertexts"));
+            }
+            for (block, reply) in chunk.iter_mut().zip(&replies) {
+                *block = u16::from_str_radix(reply, 16)
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "malformed ciphertext"))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+// Attack Result Interchange
+// -------------------------
+//
+// Attack outcomes and experiment summaries as versioned JSON documents, so
+// dashboards and grading scripts can read them without depending on the
+// crate's types. Every document starts with a "schema" name and a "version";
+// fields are only ever added within a version, and removing or changing the
+// meaning of one bumps `SCHEMA_VERSION`. The layout is specified as JSON
+// Schema in `schema/attack-result.schema.json` and
+// `schema/experiment-summary.schema.json`. The JSON is written by hand to
+// keep the crate free of dependencies.
+
+use std::fmt::Write as _;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON string literal for `text`
+pub(crate) fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// JSON number for `value`; JSON has no NaN or infinity, so those are null
+pub(crate) fn json_number<T: Copy + Into<f64> + std::fmt::Display>(value: T) -> String {
+    if value.into().is_finite() { value.to_string() } else { "null".to_string() }
+}
+
+pub(crate) fn json_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |v| v.to_string())
+}
+
+/// Which attack produced a result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackKind {
+    Linear,
+    Differential,
+    Cpa,
+    Template,
+    Dfa,
+}
+
+impl AttackKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            AttackKind::Linear => "linear",
+            AttackKind::Differential => "differential",
+            AttackKind::Cpa => "cpa",
+            AttackKind::Template => "template",
+            AttackKind::Dfa => "dfa",
+        }
+    }
+}
+
+/// The approximation or differential an attack relies on; the bias or
+/// probability is `None` when it was not estimated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trail {
+    /// Plaintext mask `alpha`, mask `beta` before the last S-box layer
+    Linear { alpha: u16, beta: u16, bias: Option<f64> },
+    /// Plaintext difference `delta_p`, difference `delta_u` before the last
+    /// S-box layer
+    Differential { delta_p: u16, delta_u: u16, probability: Option<f64> },
+}
+
+impl Trail {
+    pub fn to_json(&self) -> String {
+        match *self {
+            Trail::Linear { alpha, beta, bias } => format!(
+                "{{\"type\": \"linear\", \"input_mask\": {}, \"output_mask\": {}, \"bias\": {}}}",
+                alpha,
+                beta,
+                json_option(bias.map(json_number))
+            ),
+            Trail::Differential { delta_p, delta_u, probability } => format!(
+                "{{\"type\": \"differential\", \"input_difference\": {}, \"output_difference\": {}, \"probability\": {}}}",
+                delta_p,
+                delta_u,
+                json_option(probability.map(json_number))
+            ),
+        }
+    }
+}
+
+/// Outcome of one key-nibble recovery
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackResult {
+    pub attack: AttackKind,
+    pub trail: Option<Trail>,
+    /// Round key attacked: 0 for the whitening key, 4 for the last round key
+    pub round_key: usize,
+    pub nibble_idx: usize,
+    /// What `data` counts, e.g. "pairs" or "traces"
+    pub data_unit: &'static str,
+    pub data: usize,
+    /// All candidates, best first
+    pub ranking: [(u8, f32); 16],
+    /// The right nibble, when the experiment knows it
+    pub actual: Option<u8>,
+}
+
+impl AttackResult {
+    pub fn recovered(&self) -> u8 {
+        self.ranking[0].0
+    }
+
+    /// Position of the right nibble in the ranking (0 = recovered)
+    pub fn rank_of_actual(&self) -> Option<usize> {
+        let actual = self.actual?;
+        self.ranking.iter().position(|&(key, _)| key == actual)
+    }
+
+    pub fn to_json(&self) -> String {
+        let ranking: Vec<String> = self
+            .ranking
+            .iter()
+            .map(|&(key, score)| format!("{{\"key\": {}, \"score\": {}}}", key, json_number(score)))
+            .collect();
+        format!(
+            "{{\n  \"schema\": \"spn-attacks/attack-result\",\n  \"version\": {},\n  \"attack\": {},\n  \
+             \"trail\": {},\n  \"target\": {{\"round_key\": {}, \"nibble\": {}}},\n  \
+             \"data\": {{\"unit\": {}, \"count\": {}}},\n  \"ranking\": [{}],\n  \"recovered\": {},\n  \
+             \"actual\": {},\n  \"rank_of_actual\": {}\n}}",
+            SCHEMA_VERSION,
+            json_string(self.attack.name()),
+            json_option(self.trail.map(|trail| trail.to_json())),
+            self.round_key,
+            self.nibble_idx,
+            json_string(self.data_unit),
+            self.data,
+            ranking.join(", "),
+            self.recovered(),
+            json_option(self.actual),
+            json_option(self.rank_of_actual())
+        )
+    }
+}
+
+/// A measured curve, e.g. success rate against SNR, with the settings that
+/// produced it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExperimentSummary {
+    pub name: String,
+    pub parameters: Vec<(String, f64)>,
+    pub x_label: String,
+    pub y_label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl ExperimentSummary {
+    pub fn to_json(&self) -> String {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|(name, value)| format!("{}: {}", json_string(name), json_number(*value)))
+            .collect();
+        let points: Vec<String> =
+            self.points.iter().map(|&(x, y)| format!("[{}, {}]", json_number(x), json_number(y))).collect();
+        format!(
+            "{{\n  \"schema\": \"spn-attacks/experiment-summary\",\n  \"version\": {},\n  \"name\": {},\n  \
+             \"parameters\": {{{}}},\n  \"x\": {},\n  \"y\": {},\n  \"points\": [{}]\n}}",
+            SCHEMA_VERSION,
+            json_string(&self.name),
+            parameters.join(", "),
+            json_string(&self.x_label),
+            json_string(&self.y_label),
+            points.join(", ")
+        )
+    }
+}
@@ -0,0 +1,175 @@
+// Deterministic Randomness
+// ------------------------
+//
+// Experiments need reproducible random data without pulling in a dependency,
+// so the crate carries its own small generator. Code that draws randomness
+// takes any `RandomSource`, whose required methods mirror `rand_core::RngCore`,
+// so a generator from the rand ecosystem plugs in through a forwarding impl.
+// `RecordingRng` logs every word a source hands out and `ReplayRng` plays a
+// log back, which makes a run auditable and replayable draw for draw.
+
+use std::fmt::Write as _;
+use std::io;
+
+/// A source of uniformly random 64-bit words; everything else is derived
+/// from `next_u64`
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let word = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+
+    fn next_u16(&mut self) -> u16 {
+        (self.next_u64() >> 48) as u16
+    }
+
+    /// Uniform in [0, 1) with 53 bits of precision
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal sample (Box-Muller)
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64(); // in (0, 1], so ln is finite
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+
+    /// Uniform in 0..`bound`, without modulo bias
+    fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "the bound must be positive");
+        // Reject the top partial copy of 0..bound
+        let limit = u64::MAX - u64::MAX % bound;
+        loop {
+            let word = self.next_u64();
+            if word < limit {
+                return word % bound;
+            }
+        }
+    }
+}
+
+impl<R: RandomSource + ?Sized> RandomSource for &mut R {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+}
+
+/// SplitMix64: a fast 64-bit generator with good statistical quality,
+/// fully determined by its seed
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    /// Derive the independent generator for sub-stream `index`
+    ///
+    /// The result depends only on this generator's state and `index`, so work
+    /// split into numbered chunks draws the same numbers no matter which
+    /// thread handles which chunk.
+    pub fn split(&self, index: u64) -> SplitMix64 {
+        let mut mixer = SplitMix64::new(self.state ^ index.wrapping_mul(0xD1B5_4A32_D192_ED03));
+        SplitMix64::new(mixer.next_u64())
+    }
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Passes draws through from `inner` and keeps every word in a log
+#[derive(Clone, Debug)]
+pub struct RecordingRng<R> {
+    inner: R,
+    log: Vec<u64>,
+}
+
+impl<R: RandomSource> RecordingRng<R> {
+    pub fn new(inner: R) -> Self {
+        RecordingRng { inner, log: Vec::new() }
+    }
+
+    /// Words drawn so far, oldest first
+    pub fn log(&self) -> &[u64] {
+        &self.log
+    }
+
+    pub fn into_parts(self) -> (R, Vec<u64>) {
+        (self.inner, self.log)
+    }
+}
+
+impl<R: RandomSource> RandomSource for RecordingRng<R> {
+    fn next_u64(&mut self) -> u64 {
+        let word = self.inner.next_u64();
+        self.log.push(word);
+        word
+    }
+}
+
+/// Hands out the words of a recorded log in order; panics once the log is
+/// used up, since the replayed run has then diverged from the recorded one
+#[derive(Clone, Debug)]
+pub struct ReplayRng {
+    log: Vec<u64>,
+    position: usize,
+}
+
+impl ReplayRng {
+    pub fn new(log: Vec<u64>) -> Self {
+        ReplayRng { log, position: 0 }
+    }
+
+    /// Words not yet handed out
+    pub fn remaining(&self) -> usize {
+        self.log.len() - self.position
+    }
+}
+
+impl RandomSource for ReplayRng {
+    fn next_u64(&mut self) -> u64 {
+        let word = *self.log.get(self.position).expect("replay log exhausted");
+        self.position += 1;
+        word
+    }
+}
+
+/// Draw log as text, one 16-digit hex word per line
+pub fn log_to_text(log: &[u64]) -> String {
+    let mut text = format!("# {} random words\n", log.len());
+    for word in log {
+        writeln!(text, "{:016X}", word).unwrap();
+    }
+    text
+}
+
+/// Inverse of `log_to_text`; `#` lines are comments
+pub fn parse_log(text: &str) -> io::Result<Vec<u64>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            u64::from_str_radix(line, 16)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("bad log word {}", line)))
+        })
+        .collect()
+}
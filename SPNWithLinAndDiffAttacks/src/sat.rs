@@ -0,0 +1,278 @@
+// SAT-Based Key Recovery
+// ----------------------
+//
+// Key recovery from a few known pairs as a CNF formula: one variable per
+// master-key bit and per bit of every intermediate state, clauses for the
+// key XORs and for the S-boxes (each input value forces its output value and
+// vice versa), while the P-box is just wiring. The formula is written in
+// DIMACS, handed to any external solver that prints a model in the SAT
+// competition format (CryptoMiniSat, Kissat, CaDiCaL, MiniSat's `-model`
+// output, ...), and the model is mapped back to round keys and the master
+// key. The five round keys are consecutive slices of the 80-bit master key,
+// so the key variables are the master key itself. About five pairs pin down
+// the 80 key bits; with fewer the solver returns one of several keys.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::spn::{pbox, SBOX, SBOX_INV};
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A formula in conjunctive normal form; literals are DIMACS variables
+/// (1-based), negative for negation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cnf {
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    pub fn new_var(&mut self) -> i32 {
+        self.num_vars += 1;
+        self.num_vars as i32
+    }
+
+    /// Sixteen fresh variables, bit 0 first
+    pub fn new_word(&mut self) -> [i32; 16] {
+        std::array::from_fn(|_| self.new_var())
+    }
+
+    pub fn add_clause(&mut self, clause: Vec<i32>) {
+        self.clauses.push(clause);
+    }
+
+    /// c = a ^ b
+    fn add_xor(&mut self, a: i32, b: i32, c: i32) {
+        for (sa, sb) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            // Rule out a = sa, b = sb with the wrong c
+            let c_value = if sa == sb { -c } else { c };
+            self.add_clause(vec![-sa * a, -sb * b, c_value]);
+        }
+    }
+
+    /// Clauses forcing `literals` (bit 0 first) to equal `value`
+    fn add_constant(&mut self, literals: &[i32], value: u16) {
+        for (bit, &literal) in literals.iter().enumerate() {
+            self.add_clause(vec![if (value >> bit) & 1 == 1 { literal } else { -literal }]);
+        }
+    }
+
+    /// Literal that is true when `literals` (bit 0 first) spell `value`
+    fn matches(literals: &[i32], value: u8, bit: usize) -> i32 {
+        if (value >> bit) & 1 == 1 { literals[bit] } else { -literals[bit] }
+    }
+
+    /// y = table[x] for 4-bit `x` and `y`
+    fn add_sbox(&mut self, x: &[i32], y: &[i32], table: &[u8; 16]) {
+        for input in 0..16u8 {
+            let output = table[input as usize];
+            for bit in 0..4 {
+                // x = input implies bit `bit` of y
+                let mut clause: Vec<i32> = (0..4).map(|i| -Self::matches(x, input, i)).collect();
+                clause.push(Self::matches(y, output, bit));
+                self.add_clause(clause);
+            }
+        }
+    }
+
+    /// The formula in DIMACS format
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.num_vars, self.clauses.len());
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(dimacs, "{} ", literal).unwrap();
+            }
+            dimacs.push_str("0\n");
+        }
+        dimacs
+    }
+
+    /// Whether `assignment` (index 0 = variable 1) satisfies every clause
+    pub fn is_satisfied_by(&self, assignment: &[bool]) -> bool {
+        self.clauses.iter().all(|clause| {
+            clause.iter().any(|&literal| assignment[literal.unsigned_abs() as usize - 1] == (literal > 0))
+        })
+    }
+}
+
+/// The key-recovery formula and where the key bits live in it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyRecoveryCnf {
+    pub cnf: Cnf,
+    /// Variable of bit `b` of round key `i` at `key_vars[i][b]`
+    pub key_vars: [[i32; 16]; 5],
+}
+
+/// Constrain the state of each encryption in `pairs` to go from the
+/// plaintext to the ciphertext under an unknown master key
+pub fn key_recovery_cnf(pairs: &[(u16, u16)]) -> KeyRecoveryCnf {
+    let mut cnf = Cnf::default();
+    let key_vars: [[i32; 16]; 5] = std::array::from_fn(|_| cnf.new_word());
+    for &(plaintext, ciphertext) in pairs {
+        let input = cnf.new_word();
+        cnf.add_constant(&input, plaintext);
+        let mut state = cnf.new_word();
+        for bit in 0..16 {
+            cnf.add_xor(input[bit], key_vars[0][bit], state[bit]);
+        }
+        for (round, round_key) in key_vars.iter().enumerate().skip(1) {
+            let substituted = cnf.new_word();
+            for nibble in 0..4 {
+                let bits = 4 * nibble..4 * nibble + 4;
+                cnf.add_sbox(&state[bits.clone()], &substituted[bits.clone()], &SBOX);
+                cnf.add_sbox(&substituted[bits.clone()], &state[bits], &SBOX_INV);
+            }
+            // Bit i of the S-box output lands on bit pbox(1 << i)
+            let mut permuted = substituted;
+            if round < 4 {
+                for (bit, &var) in substituted.iter().enumerate() {
+                    permuted[pbox(1 << bit).trailing_zeros() as usize] = var;
+                }
+            }
+            let next = cnf.new_word();
+            for bit in 0..16 {
+                cnf.add_xor(permuted[bit], round_key[bit], next[bit]);
+            }
+            state = next;
+        }
+        cnf.add_constant(&state, ciphertext);
+    }
+    KeyRecoveryCnf { cnf, key_vars }
+}
+
+impl KeyRecoveryCnf {
+    /// DIMACS with comment lines mapping round-key bits to variables
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = String::new();
+        for (round, word) in self.key_vars.iter().enumerate() {
+            writeln!(dimacs, "c round key {} bits 0..15: variables {}..{}", round, word[0], word[15]).unwrap();
+        }
+        dimacs.push_str(&self.cnf.to_dimacs());
+        dimacs
+    }
+
+    /// Round keys read off a satisfying assignment
+    pub fn round_keys(&self, assignment: &[bool]) -> [u16; 5] {
+        self.key_vars.map(|word| {
+            word.iter().enumerate().fold(0u16, |key, (bit, &var)| key | (assignment[var as usize - 1] as u16) << bit)
+        })
+    }
+
+    /// The 80-bit master key read off a satisfying assignment
+    pub fn master_key(&self, assignment: &[bool]) -> u128 {
+        self.round_keys(assignment).iter().fold(0u128, |key, &round_key| key << 16 | round_key as u128)
+    }
+}
+
+/// Parse solver output in the SAT competition format: `s SATISFIABLE` or
+/// `s UNSATISFIABLE` and `v` lines listing the model, terminated by 0.
+/// `None` means unsatisfiable; unmentioned variables are false.
+pub fn parse_model(output: &str, num_vars: usize) -> io::Result<Option<Vec<bool>>> {
+    let mut status = None;
+    let mut assignment = vec![false; num_vars];
+    for line in output.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("s") => status = Some(words.collect::<Vec<_>>().join(" ")),
+            Some("v") => {
+                for word in words {
+                    let literal: i64 = word.parse().map_err(|_| invalid("bad literal in solver model"))?;
+                    let var = literal.unsigned_abs() as usize;
+                    if var > num_vars {
+                        return Err(invalid("solver model mentions an unknown variable"));
+                    }
+                    if var > 0 {
+                        assignment[var - 1] = literal > 0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    match status.as_deref() {
+        Some("SATISFIABLE") => Ok(Some(assignment)),
+        Some("UNSATISFIABLE") => Ok(None),
+        Some(other) => Err(invalid(&format!("solver gave up: {}", other))),
+        None => Err(invalid("no status line in solver output")),
+    }
+}
+
+/// Write `cnf` to `cnf_path`, run `solver` on it (`args` come before the
+/// file name) and parse the model it prints
+pub fn run_solver(solver: &str, args: &[&str], cnf: &Cnf, cnf_path: &Path) -> io::Result<Option<Vec<bool>>> {
+    fs::write(cnf_path, cnf.to_dimacs())?;
+    solve_file(solver, args, cnf_path, cnf.num_vars)
+}
+
+fn solve_file(solver: &str, args: &[&str], cnf_path: &Path, num_vars: usize) -> io::Result<Option<Vec<bool>>> {
+    // Solvers exit with 10 (SAT) or 20 (UNSAT), so the status is not checked
+    let output = Command::new(solver).args(args).arg(cnf_path).output()?;
+    parse_model(&String::from_utf8_lossy(&output.stdout), num_vars)
+}
+
+/// Encode `pairs`, solve with `solver` and return the round keys of the
+/// model, `None` if the solver proves no key fits
+pub fn recover_round_keys(solver: &str, args: &[&str], pairs: &[(u16, u16)], cnf_path: &Path) -> io::Result<Option<[u16; 5]>> {
+    let formula = key_recovery_cnf(pairs);
+    fs::write(cnf_path, formula.to_dimacs())?;
+    let model = solve_file(solver, args, cnf_path, formula.cnf.num_vars)?;
+    Ok(model.map(|assignment| formula.round_keys(&assignment)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{RandomSource, SplitMix64};
+    use crate::spn::{sbox_layer, Spn};
+
+    /// The assignment an encryption under `round_keys` induces, variables in
+    /// the order `key_recovery_cnf` allocates them
+    fn assignment(round_keys: &[u16], pairs: &[(u16, u16)]) -> Vec<bool> {
+        let mut words = round_keys.to_vec();
+        for &(plaintext, _) in pairs {
+            let mut state = plaintext ^ round_keys[0];
+            words.extend([plaintext, state]);
+            for (round, &round_key) in (1..).zip(&round_keys[1..5]) {
+                let substituted = sbox_layer(state);
+                state = if round < 4 { pbox(substituted) } else { substituted } ^ round_key;
+                words.extend([substituted, state]);
+            }
+        }
+        words.iter().flat_map(|&word| (0..16).map(move |bit| (word >> bit) & 1 == 1)).collect()
+    }
+
+    #[test]
+    fn the_true_key_satisfies_the_formula() {
+        let key = 0x1234_5678_90AB_CDEF_1234;
+        let cipher = Spn::new(key);
+        let mut rng = SplitMix64::new(8);
+        let pairs: Vec<(u16, u16)> = (0..5).map(|_| rng.next_u16()).map(|p| (p, cipher.encrypt(p))).collect();
+        let formula = key_recovery_cnf(&pairs);
+        let model = assignment(cipher.round_keys(), &pairs);
+        assert_eq!(model.len(), formula.cnf.num_vars);
+        assert!(formula.cnf.is_satisfied_by(&model));
+        assert_eq!(formula.round_keys(&model), cipher.round_keys());
+        assert_eq!(formula.master_key(&model), key);
+
+        let mut wrong_keys = cipher.round_keys().to_vec();
+        wrong_keys[4] ^= 1;
+        assert!(!formula.cnf.is_satisfied_by(&assignment(&wrong_keys, &pairs)));
+    }
+
+    #[test]
+    fn parses_solver_output() {
+        let sat = "c comment\ns SATISFIABLE\nv 1 -2\nv 3 0\n";
+        assert_eq!(parse_model(sat, 4).unwrap(), Some(vec![true, false, true, false]));
+        assert_eq!(parse_model("s UNSATISFIABLE\n", 4).unwrap(), None);
+        assert!(parse_model("s UNKNOWN\n", 4).is_err());
+        assert!(parse_model("segmentation fault\n", 4).is_err());
+        assert!(parse_model("s SATISFIABLE\nv 1 x 0\n", 4).is_err());
+        assert!(parse_model("s SATISFIABLE\nv 5 0\n", 4).is_err());
+    }
+}
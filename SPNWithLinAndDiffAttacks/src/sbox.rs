@@ -0,0 +1,195 @@
+// S-box Analysis Tables
+// ---------------------
+//
+// The linear approximation table (LAT), difference distribution table (DDT)
+// and boomerang connectivity table (BCT) of a 4-bit S-box are computed the
+// first time they are asked for and kept on the `Sbox` instance, so repeated
+// analysis of the same S-box does not rebuild them. Changing the table drops
+// the cached ones. The PRESENT S-box of the cipher is one shared instance,
+// so its tables are built once per process.
+
+use std::sync::{LazyLock, OnceLock};
+
+use crate::rng::RandomSource;
+use crate::spn::SBOX;
+
+/// A 4-bit bijective S-box with lazily built analysis tables
+#[derive(Clone, Debug)]
+pub struct Sbox {
+    table: [u8; 16],
+    inverse: [u8; 16],
+    lat: OnceLock<[[i8; 16]; 16]>,
+    ddt: OnceLock<[[u8; 16]; 16]>,
+    bct: OnceLock<[[u8; 16]; 16]>,
+}
+
+fn invert(table: &[u8; 16]) -> [u8; 16] {
+    let mut inverse = [0xFF; 16];
+    for (x, &y) in table.iter().enumerate() {
+        assert!(y < 16 && inverse[y as usize] == 0xFF, "the S-box must be a permutation of 0..16");
+        inverse[y as usize] = x as u8;
+    }
+    inverse
+}
+
+impl Sbox {
+    /// Panics if `table` is not a permutation of 0..16
+    pub fn new(table: [u8; 16]) -> Self {
+        Sbox { inverse: invert(&table), table, lat: OnceLock::new(), ddt: OnceLock::new(), bct: OnceLock::new() }
+    }
+
+    /// The PRESENT S-box used by `spn`, shared with its cached tables
+    pub fn present() -> &'static Self {
+        static PRESENT: LazyLock<Sbox> = LazyLock::new(|| Sbox::new(SBOX));
+        &PRESENT
+    }
+
+    /// Uniformly random permutation (Fisher-Yates), e.g. to search for
+    /// S-boxes with good tables
+    pub fn random(rng: &mut impl RandomSource) -> Self {
+        let mut table: [u8; 16] = std::array::from_fn(|x| x as u8);
+        for i in (1..16).rev() {
+            table.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+        Self::new(table)
+    }
+
+    pub fn table(&self) -> &[u8; 16] {
+        &self.table
+    }
+
+    pub fn inverse(&self) -> &[u8; 16] {
+        &self.inverse
+    }
+
+    #[inline]
+    pub fn apply(&self, x: u8) -> u8 {
+        self.table[x as usize]
+    }
+
+    /// Replace the table and drop every cached analysis table
+    pub fn set_table(&mut self, table: [u8; 16]) {
+        self.inverse = invert(&table);
+        self.table = table;
+        self.invalidate_cache();
+    }
+
+    /// Forget the cached tables; they are rebuilt on next use
+    pub fn invalidate_cache(&mut self) {
+        self.lat = OnceLock::new();
+        self.ddt = OnceLock::new();
+        self.bct = OnceLock::new();
+    }
+
+    /// LAT[a][b] = #{x : <a, x> = <b, S(x)>} - 8, so bias(a, b) = LAT[a][b] / 16
+    pub fn lat(&self) -> &[[i8; 16]; 16] {
+        self.lat.get_or_init(|| {
+            let mut lat = [[-8i8; 16]; 16];
+            for (a, row) in lat.iter_mut().enumerate() {
+                for (b, entry) in row.iter_mut().enumerate() {
+                    for x in 0..16 {
+                        let input_dot = (a & x).count_ones() % 2;
+                        let output_dot = (b & self.table[x] as usize).count_ones() % 2;
+                        if input_dot == output_dot {
+                            *entry += 1;
+                        }
+                    }
+                }
+            }
+            lat
+        })
+    }
+
+    /// DDT[din][dout] = #{x : S(x) ^ S(x ^ din) = dout}
+    pub fn ddt(&self) -> &[[u8; 16]; 16] {
+        self.ddt.get_or_init(|| {
+            let mut ddt = [[0u8; 16]; 16];
+            for (delta_in, row) in ddt.iter_mut().enumerate() {
+                for x in 0..16 {
+                    row[(self.table[x] ^ self.table[x ^ delta_in]) as usize] += 1;
+                }
+            }
+            ddt
+        })
+    }
+
+    /// BCT[din][dout] = #{x : S^-1(S(x) ^ dout) ^ S^-1(S(x ^ din) ^ dout) = din}
+    pub fn bct(&self) -> &[[u8; 16]; 16] {
+        self.bct.get_or_init(|| {
+            let mut bct = [[0u8; 16]; 16];
+            for (delta_in, row) in bct.iter_mut().enumerate() {
+                for (delta_out, entry) in row.iter_mut().enumerate() {
+                    for x in 0..16 {
+                        let first = self.inverse[(self.table[x] as usize) ^ delta_out];
+                        let second = self.inverse[(self.table[x ^ delta_in] as usize) ^ delta_out];
+                        if (first ^ second) as usize == delta_in {
+                            *entry += 1;
+                        }
+                    }
+                }
+            }
+            bct
+        })
+    }
+
+    /// Bias of the approximation <a, x> = <b, S(x)>, read from the LAT
+    pub fn linear_bias(&self, a: u8, b: u8) -> f32 {
+        self.lat()[a as usize][b as usize] as f32 / 16.0
+    }
+
+    /// Probability of the differential din -> dout, read from the DDT
+    pub fn differential_probability(&self, delta_in: u8, delta_out: u8) -> f32 {
+        self.ddt()[delta_in as usize][delta_out as usize] as f32 / 16.0
+    }
+}
+
+impl Default for Sbox {
+    fn default() -> Self {
+        Self::present().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::SBOX_INV;
+
+    #[test]
+    fn present_is_shared() {
+        assert!(std::ptr::eq(Sbox::present(), Sbox::present()));
+        assert_eq!(Sbox::present().table(), &SBOX);
+        assert_eq!(Sbox::present().inverse(), &SBOX_INV);
+        assert_eq!(Sbox::present().ddt()[0x9][0x2], 2);
+        assert_eq!(Sbox::present().lat()[0xB][0x4], -2);
+    }
+
+    #[test]
+    fn set_table_rebuilds_the_tables() {
+        let identity: [u8; 16] = std::array::from_fn(|x| x as u8);
+        let mut sbox = Sbox::present().clone();
+        let (lat, ddt, bct) = (*sbox.lat(), *sbox.ddt(), *sbox.bct());
+        sbox.set_table(identity);
+        assert_eq!(sbox.inverse(), &identity);
+        let fresh = Sbox::new(identity);
+        assert_eq!((sbox.lat(), sbox.ddt(), sbox.bct()), (fresh.lat(), fresh.ddt(), fresh.bct()));
+        assert_ne!(*sbox.ddt(), ddt);
+
+        sbox.set_table(SBOX);
+        assert_eq!((*sbox.lat(), *sbox.ddt(), *sbox.bct()), (lat, ddt, bct));
+    }
+
+    #[test]
+    fn invalidate_cache_keeps_the_same_tables() {
+        let mut sbox = Sbox::new(SBOX);
+        let lat = *sbox.lat();
+        sbox.invalidate_cache();
+        assert!(sbox.lat.get().is_none() && sbox.ddt.get().is_none() && sbox.bct.get().is_none());
+        assert_eq!(*sbox.lat(), lat);
+    }
+
+    #[test]
+    #[should_panic(expected = "the S-box must be a permutation of 0..16")]
+    fn set_table_rejects_a_non_permutation() {
+        Sbox::new(SBOX).set_table([0; 16]);
+    }
+}
@@ -0,0 +1,96 @@
+// Cipher Specification Export
+// ---------------------------
+//
+// Writes the cipher out as a self-contained Python script (S-box, bit
+// permutation, key schedule, round function, encryption and decryption) for
+// cross-checking results in other toolchains. The Sage flavour additionally
+// wraps the S-box in Sage's `SBox` class, whose LAT/DDT methods can be
+// compared against `sbox`. Both scripts end with known-answer checks
+// computed by this crate, so a mismatch in the port fails on load.
+
+use std::fmt::Write as _;
+
+use crate::sbox::Sbox;
+use crate::spn::{pbox, Spn, SBOX};
+
+/// Plaintexts whose ciphertexts are embedded as known-answer checks
+const CHECK_PLAINTEXTS: [u16; 4] = [0x0000, 0xFFFF, 0x1234, 0xBEEF];
+
+fn hex_list(values: impl IntoIterator<Item = u16>, digits: usize) -> String {
+    let items: Vec<String> = values.into_iter().map(|v| format!("0x{:0width$X}", v, width = digits)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Plain Python 3 definition of `cipher`
+pub fn python_script(cipher: &Spn) -> String {
+    let mut script = String::new();
+    let permutation = (0..16).map(|bit| pbox(1 << bit).trailing_zeros() as u16);
+    let checks = CHECK_PLAINTEXTS.iter().map(|&p| format!("({:#06X}, {:#06X})", p, cipher.encrypt(p)));
+
+    writeln!(script, "# 16-bit SPN exported by spn_attacks").unwrap();
+    writeln!(script, "# Nibble 0 and bit 0 are the least significant.\n").unwrap();
+    writeln!(script, "SBOX = {}", hex_list(SBOX.map(u16::from), 1)).unwrap();
+    writeln!(script, "SBOX_INV = [SBOX.index(x) for x in range(16)]").unwrap();
+    writeln!(script, "# Bit i of the input moves to bit PERM[i]; the permutation is an involution").unwrap();
+    writeln!(script, "PERM = {:?}", permutation.collect::<Vec<_>>()).unwrap();
+    writeln!(script, "ROUND_KEYS = {}\n", hex_list(cipher.round_keys().iter().copied(), 4)).unwrap();
+    script.push_str(
+        "\
+def expand_key(master_key):
+    \"\"\"Five 16-bit round keys: the 80-bit master key cut from the top\"\"\"
+    return [(master_key >> (80 - 16 * (i + 1))) & 0xFFFF for i in range(5)]
+
+def sbox_layer(state, table=SBOX):
+    return sum(table[(state >> (4 * i)) & 0xF] << (4 * i) for i in range(4))
+
+def pbox(state):
+    return sum(((state >> i) & 1) << PERM[i] for i in range(16))
+
+def round_function(state, round_key):
+    \"\"\"Rounds 1-3; the last round has no P-box\"\"\"
+    return pbox(sbox_layer(state)) ^ round_key
+
+def encrypt(plaintext, round_keys=ROUND_KEYS):
+    state = plaintext ^ round_keys[0]
+    for round_key in round_keys[1:4]:
+        state = round_function(state, round_key)
+    return sbox_layer(state) ^ round_keys[4]
+
+def decrypt(ciphertext, round_keys=ROUND_KEYS):
+    state = sbox_layer(ciphertext ^ round_keys[4], SBOX_INV)
+    for round_key in reversed(round_keys[1:4]):
+        state = sbox_layer(pbox(state ^ round_key), SBOX_INV)
+    return state ^ round_keys[0]
+
+",
+    );
+    writeln!(script, "KNOWN_ANSWERS = [{}]", checks.collect::<Vec<_>>().join(", ")).unwrap();
+    script.push_str(
+        "\
+for plaintext, ciphertext in KNOWN_ANSWERS:
+    assert encrypt(plaintext) == ciphertext, hex(plaintext)
+    assert decrypt(ciphertext) == plaintext, hex(ciphertext)
+",
+    );
+    script
+}
+
+/// `python_script` plus the S-box as a Sage `SBox` and the crate's maximum
+/// LAT and DDT entries for comparison
+pub fn sage_script(cipher: &Spn) -> String {
+    let sbox = Sbox::present();
+    let max_lat = sbox.lat().iter().skip(1).flat_map(|row| row.iter().skip(1)).map(|e| e.unsigned_abs()).max().unwrap();
+    let max_ddt = sbox.ddt().iter().skip(1).flat_map(|row| row.iter()).max().unwrap();
+    let mut script = python_script(cipher);
+    script.push_str(
+        "
+from sage.crypto.sbox import SBox
+
+# Sage numbers bits most-significant first; the table itself is the same
+S = SBox(SBOX)
+",
+    );
+    writeln!(script, "assert max(abs(e) for row in S.linear_approximation_table()[1:] for e in row[1:]) == {}", max_lat).unwrap();
+    writeln!(script, "assert max(e for row in S.difference_distribution_table()[1:] for e in row) == {}", max_ddt).unwrap();
+    script
+}
@@ -0,0 +1,309 @@
+use crate::bitslice;
+use crate::cipher::BlockCipher;
+use crate::constant_time;
+use crate::lut;
+use crate::rng::RandomSource;
+
+// PRESENT S-box (4-bit to 4-bit)
+pub const SBOX: [u8; 16] = [
+    0xC, 0x5, 0x6, 0xB, 0x9, 0x0, 0xA, 0xD,
+    0x3, 0xE, 0xF, 0x8, 0x4, 0x7, 0x1, 0x2,
+];
+
+// Inverse PRESENT S-box
+pub const SBOX_INV: [u8; 16] = [
+    0x5, 0xE, 0xF, 0x8, 0xC, 0x1, 0x2, 0xD,
+    0xB, 0x4, 0x6, 0x3, 0x0, 0x7, 0x9, 0xA,
+];
+
+/// Nibble `idx` (0 = least significant) of a 16-bit word
+#[inline]
+pub fn nibble(state: u16, idx: usize) -> u8 {
+    ((state >> (4 * idx)) & 0xF) as u8
+}
+
+/// Apply the S-box to each nibble (4-bit chunk) in a 16-bit word
+#[inline]
+pub fn sbox_layer(state: u16) -> u16 {
+    let mut output = 0;
+    for i in 0..4 {
+        let nibble = (state >> (i * 4)) as u8 & 0xF;
+        let substituted = SBOX[nibble as usize] as u16;
+        output |= substituted << (i * 4);
+    }
+    output
+}
+
+/// Apply the inverse S-box to each nibble in a 16-bit word
+#[inline]
+pub fn sbox_inv_layer(state: u16) -> u16 {
+    let mut output = 0;
+    for i in 0..4 {
+        let nibble = (state >> (i * 4)) as u8 & 0xF;
+        let substituted = SBOX_INV[nibble as usize] as u16;
+        output |= substituted << (i * 4);
+    }
+    output
+}
+
+/// Last-round partial decryption for every key nibble: row `k` maps a
+/// ciphertext nibble `c` to `SBOX_INV[c ^ k]`
+pub fn partial_decryption_table() -> [[u8; 16]; 16] {
+    let mut table = [[0; 16]; 16];
+    for (key, row) in table.iter_mut().enumerate() {
+        for (nibble, entry) in row.iter_mut().enumerate() {
+            *entry = SBOX_INV[nibble ^ key];
+        }
+    }
+    table
+}
+
+/// Bit permutation (transposition of a 4x4 bit matrix)
+#[inline]
+pub fn pbox(state: u16) -> u16 {
+    let mut output = 0;
+    // Transpose bits: original bit i goes to position (i % 4) * 4 + (i / 4)
+    for i in 0..16 {
+        let bit = (state >> i) & 1;
+        let j = (i % 4) * 4 + (i / 4);
+        output |= bit << j;
+    }
+    output
+}
+
+// Round constants for the unkeyed permutation (leading hex digits of pi)
+pub const ROUND_CONSTANTS: [u16; 8] = [
+    0x243F, 0x6A88, 0x85A3, 0x08D3, 0x1319, 0x8A2E, 0x0370, 0x7344,
+];
+
+/// One unkeyed round: S-box layer followed by the P-box
+#[inline]
+pub fn round(state: u16) -> u16 {
+    pbox(sbox_layer(state))
+}
+
+/// Keyless permutation: `rounds` unkeyed rounds, each followed by a round
+/// constant so the rounds are not all identical
+pub fn permutation(state: u16, rounds: usize) -> u16 {
+    (0..rounds).fold(state, |state, i| {
+        round(state) ^ ROUND_CONSTANTS[i % ROUND_CONSTANTS.len()]
+    })
+}
+
+/// Round key `i` of a master key (80 bits stored in u128)
+#[inline]
+pub fn round_key(master_key: u128, i: usize) -> u16 {
+    // Extract 16-bit chunks from the master key (shift right by 64, 48, 32, 16, 0 bits)
+    (master_key >> (80 - 16 * (i + 1))) as u16
+}
+
+/// Uniformly random 80-bit master key
+pub fn random_master_key(rng: &mut impl RandomSource) -> u128 {
+    ((rng.next_u64() as u128) << 16 | rng.next_u16() as u128) & ((1 << 80) - 1)
+}
+
+/// Generate round keys from a master key (80 bits stored in u128)
+pub fn expand_key(master_key: u128, rounds: usize) -> Vec<u16> {
+    (0..rounds).map(|i| round_key(master_key, i)).collect()
+}
+
+/// Encrypt a 16-bit block using the SPN
+#[inline]
+pub fn encrypt(plaintext: u16, round_keys: &[u16]) -> u16 {
+    let mut state = plaintext;
+    // Initial whitening
+    state ^= round_keys[0];
+
+    // Rounds 1 to 3: S-box, P-box, XOR round key
+    for round_key in &round_keys[1..4] {
+        state = sbox_layer(state);
+        state = pbox(state);
+        state ^= round_key;
+    }
+
+    // Final round: S-box and last key XOR (no P-box)
+    state = sbox_layer(state);
+    state ^= round_keys[4];
+    state
+}
+
+/// Decrypt a 16-bit block using the SPN
+#[inline]
+pub fn decrypt(ciphertext: u16, round_keys: &[u16]) -> u16 {
+    let mut state = ciphertext;
+    // Reverse final round
+    state ^= round_keys[4];
+    state = sbox_inv_layer(state);
+
+    // Rounds 3 to 1: XOR round key, inverse P-box, inverse S-box
+    for round_key in round_keys[1..4].iter().rev() {
+        state ^= round_key;
+        state = pbox(state); // P-box is its own inverse
+        state = sbox_inv_layer(state);
+    }
+
+    // Reverse initial whitening
+    state ^= round_keys[0];
+    state
+}
+
+/// How `Spn` evaluates the rounds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Per-nibble S-box and per-bit P-box loops
+    #[default]
+    Scalar,
+    /// Shared 65,536-entry full-round tables (see `lut`)
+    Lut,
+    /// Boolean S-box formula, no secret-indexed lookups or branches (see
+    /// `constant_time`)
+    ConstantTime,
+}
+
+/// The 16-bit SPN keyed with a fixed set of round keys
+#[derive(Clone, Debug)]
+pub struct Spn {
+    round_keys: [u16; 5],
+    backend: Backend,
+}
+
+impl Spn {
+    /// Expand an 80-bit master key into the five round keys
+    pub fn new(master_key: u128) -> Self {
+        Self::from_round_keys(std::array::from_fn(|i| round_key(master_key, i)))
+    }
+
+    /// Use an explicit set of five round keys
+    pub fn from_round_keys(round_keys: [u16; 5]) -> Self {
+        Spn { round_keys, backend: Backend::Scalar }
+    }
+
+    /// Switch how rounds are evaluated; the results are identical
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        if backend == Backend::Lut {
+            // Build the tables now rather than inside the first encryption
+            lut::tables();
+        }
+        self.backend = backend;
+        self
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn round_keys(&self) -> &[u16] {
+        &self.round_keys
+    }
+
+    #[inline]
+    pub fn encrypt(&self, plaintext: u16) -> u16 {
+        match self.backend {
+            Backend::Scalar => encrypt(plaintext, &self.round_keys),
+            Backend::Lut => lut::encrypt(plaintext, &self.round_keys),
+            Backend::ConstantTime => constant_time::encrypt(plaintext, &self.round_keys),
+        }
+    }
+
+    #[inline]
+    pub fn decrypt(&self, ciphertext: u16) -> u16 {
+        match self.backend {
+            Backend::Scalar => decrypt(ciphertext, &self.round_keys),
+            Backend::Lut => lut::decrypt(ciphertext, &self.round_keys),
+            Backend::ConstantTime => constant_time::decrypt(ciphertext, &self.round_keys),
+        }
+    }
+
+    /// Encrypt 64 blocks with the bitsliced backend
+    pub fn encrypt_batch64(&self, plaintexts: &[u16; 64]) -> [u16; 64] {
+        bitslice::encrypt_batch64(plaintexts, &self.round_keys)
+    }
+
+    /// Decrypt 64 blocks with the bitsliced backend
+    pub fn decrypt_batch64(&self, ciphertexts: &[u16; 64]) -> [u16; 64] {
+        bitslice::decrypt_batch64(ciphertexts, &self.round_keys)
+    }
+
+    /// Encrypt every block in place with the fastest path for the backend
+    ///
+    /// The table backend goes block by block. With the `simd` feature on a
+    /// CPU that has the vector unit, the scalar backend uses `simd`, which
+    /// only encrypts; the constant-time backend keeps to the Boolean S-box
+    /// formula. Otherwise whole groups of 64 run
+    /// through the bitsliced code, which has no secret-indexed lookups or
+    /// branches, and the remainder goes through the backend one block at a
+    /// time.
+    pub fn encrypt_blocks(&self, blocks: &mut [u16]) {
+        if self.backend == Backend::Lut {
+            blocks.iter_mut().for_each(|block| *block = lut::encrypt(*block, &self.round_keys));
+            return;
+        }
+        #[cfg(feature = "simd")]
+        if self.backend == Backend::Scalar && crate::simd::available() {
+            crate::simd::encrypt_blocks(blocks, &self.round_keys);
+            return;
+        }
+        let mut batches = blocks.chunks_exact_mut(64);
+        for batch in &mut batches {
+            let batch: &mut [u16; 64] = batch.try_into().unwrap();
+            *batch = self.encrypt_batch64(batch);
+        }
+        for block in batches.into_remainder() {
+            *block = self.encrypt(*block);
+        }
+    }
+
+    /// Decrypt every block in place; see `encrypt_blocks`
+    pub fn decrypt_blocks(&self, blocks: &mut [u16]) {
+        if self.backend == Backend::Lut {
+            blocks.iter_mut().for_each(|block| *block = lut::decrypt(*block, &self.round_keys));
+            return;
+        }
+        let mut batches = blocks.chunks_exact_mut(64);
+        for batch in &mut batches {
+            let batch: &mut [u16; 64] = batch.try_into().unwrap();
+            *batch = self.decrypt_batch64(batch);
+        }
+        for block in batches.into_remainder() {
+            *block = self.decrypt(*block);
+        }
+    }
+}
+
+/// Blocks are the 16-bit state in big-endian byte order
+impl BlockCipher for Spn {
+    const BLOCK_SIZE: usize = 2;
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let state = self.encrypt(u16::from_be_bytes([block[0], block[1]]));
+        block.copy_from_slice(&state.to_be_bytes());
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let state = self.decrypt(u16::from_be_bytes([block[0], block[1]]));
+        block.copy_from_slice(&state.to_be_bytes());
+    }
+
+    fn encrypt_blocks(&self, data: &mut [u8]) {
+        apply_to_words(data, |words| self.encrypt_blocks(words));
+    }
+
+    fn decrypt_blocks(&self, data: &mut [u8]) {
+        apply_to_words(data, |words| self.decrypt_blocks(words));
+    }
+}
+
+/// Run `f` over `data` read as big-endian 16-bit words, 64 words at a time
+fn apply_to_words(data: &mut [u8], f: impl Fn(&mut [u16])) {
+    let mut words = [0u16; 64];
+    for chunk in data.chunks_mut(128) {
+        let words = &mut words[..chunk.len() / 2];
+        for (word, bytes) in words.iter_mut().zip(chunk.chunks_exact(2)) {
+            *word = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        f(words);
+        for (bytes, word) in chunk.chunks_exact_mut(2).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Sponge Construction
+// -------------------
+//
+// The 16-bit state is split into an outer part of `rate` bits, which the
+// message is XORed into and output is read from, and an inner part of
+// `capacity = 16 - rate` bits that is never touched directly. Data moves in
+// nibbles, so the rate must be 4, 8 or 12 bits. Generic security is about
+// 2^(capacity / 2), which for a 16-bit state is tiny by design.
+
+use crate::spn::permutation;
+
+/// Nibbles, most significant first, as one word
+#[inline]
+fn pack_nibbles(nibbles: &[u8]) -> u16 {
+    nibbles.iter().fold(0, |acc, &n| (acc << 4) | n as u16)
+}
+
+/// Number of permutation rounds used by `sponge_hash` and `sponge_mac`
+pub const DEFAULT_ROUNDS: usize = 8;
+
+/// Incremental sponge: absorb any number of times, then squeeze
+#[derive(Clone, Debug)]
+pub struct Sponge {
+    state: u16,
+    rate: u32,
+    rounds: usize,
+    /// Message nibbles waiting for a full rate block
+    pending: Vec<u8>,
+    /// Output nibbles not yet handed out
+    output: Vec<u8>,
+    squeezing: bool,
+}
+
+impl Sponge {
+    pub fn new(rate: u32, rounds: usize) -> Self {
+        assert!(matches!(rate, 4 | 8 | 12), "rate must be 4, 8 or 12 bits");
+        Sponge { state: 0, rate, rounds, pending: Vec::new(), output: Vec::new(), squeezing: false }
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn capacity(&self) -> u32 {
+        16 - self.rate
+    }
+
+    fn rate_nibbles(&self) -> usize {
+        self.rate as usize / 4
+    }
+
+    /// XOR one rate block (packed by `pack_nibbles`) into the outer part
+    /// and permute
+    fn absorb_block(&mut self, block: u16) {
+        self.state ^= block << self.capacity();
+        self.state = permutation(self.state, self.rounds);
+    }
+
+    pub fn absorb(&mut self, data: &[u8]) {
+        assert!(!self.squeezing, "cannot absorb after squeezing has started");
+        for byte in data {
+            self.pending.extend_from_slice(&[byte >> 4, byte & 0xF]);
+            while self.pending.len() >= self.rate_nibbles() {
+                let block = pack_nibbles(&self.pending[..self.rate_nibbles()]);
+                self.pending.drain(..self.rate_nibbles());
+                self.absorb_block(block);
+            }
+        }
+    }
+
+    /// Pad the final block with 10*1 and absorb it
+    ///
+    /// `pending` is always shorter than a block, so the padding fits; when only
+    /// one nibble is free both 1 bits land in it (0x9).
+    fn finish_absorbing(&mut self) {
+        let mut block = std::mem::take(&mut self.pending);
+        block.push(0x8);
+        block.resize(self.rate_nibbles(), 0);
+        *block.last_mut().unwrap() |= 0x1;
+        self.absorb_block(pack_nibbles(&block));
+        self.squeezing = true;
+        self.read_outer();
+    }
+
+    /// Queue the nibbles of the outer part as output
+    fn read_outer(&mut self) {
+        let outer = self.state >> self.capacity();
+        for i in (0..self.rate_nibbles()).rev() {
+            self.output.push(((outer >> (4 * i)) & 0xF) as u8);
+        }
+    }
+
+    pub fn squeeze(&mut self, len: usize) -> Vec<u8> {
+        if !self.squeezing {
+            self.finish_absorbing();
+        }
+        let mut out = Vec::with_capacity(len);
+        while out.len() < len {
+            while self.output.len() < 2 {
+                self.state = permutation(self.state, self.rounds);
+                self.read_outer();
+            }
+            out.push((self.output[0] << 4) | self.output[1]);
+            self.output.drain(..2);
+        }
+        out
+    }
+}
+
+/// Hash `message` to `out_len` bytes
+pub fn sponge_hash(message: &[u8], rate: u32, out_len: usize) -> Vec<u8> {
+    let mut sponge = Sponge::new(rate, DEFAULT_ROUNDS);
+    sponge.absorb(message);
+    sponge.squeeze(out_len)
+}
+
+/// Keyed sponge MAC: absorb the key length, the key, then the message
+///
+/// Prefixing the key is safe for a sponge, unlike for Merkle-Damgard, because
+/// the inner part hides enough of the state to stop length extension. The
+/// 64-bit big-endian length in front keeps (key, message) pairs that share a
+/// concatenation, like ("ab", "c") and ("a", "bc"), apart.
+pub fn sponge_mac(key: &[u8], message: &[u8], rate: u32, out_len: usize) -> Vec<u8> {
+    let mut sponge = Sponge::new(rate, DEFAULT_ROUNDS);
+    sponge.absorb(&(key.len() as u64).to_be_bytes());
+    sponge.absorb(key);
+    sponge.absorb(message);
+    sponge.squeeze(out_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mac_separates_key_from_message() {
+        for rate in [4, 8, 12] {
+            assert_ne!(sponge_mac(b"ab", b"c", rate, 8), sponge_mac(b"a", b"bc", rate, 8));
+            assert_ne!(sponge_mac(b"", b"key", rate, 8), sponge_mac(b"key", b"", rate, 8));
+            assert_eq!(sponge_mac(b"ab", b"c", rate, 8), sponge_mac(b"ab", b"c", rate, 8));
+        }
+    }
+}
@@ -0,0 +1,146 @@
+// Template Attack
+// ---------------
+//
+// A profiled attack in two phases. Profiling: on a device whose key is known,
+// group traces by the value of the targeted intermediate (one nibble of the
+// first-round S-box output) and fit a multivariate Gaussian per value over a
+// few chosen samples, the points of interest. The covariance is pooled over
+// all values, which needs far fewer traces than one matrix per value.
+// Matching: on the target device, every key guess predicts the intermediate
+// of each trace, and the guess under which the traces are most likely wins.
+
+use crate::leakage::LeakyEncryption;
+use crate::spn::{nibble, SBOX};
+
+/// Gaussian templates for the 16 values of one S-box output nibble
+#[derive(Clone, Debug)]
+pub struct Templates {
+    nibble_idx: usize,
+    points: Vec<usize>,
+    /// Mean sample vector per intermediate value
+    means: Vec<Vec<f64>>,
+    /// Inverse of the pooled covariance matrix
+    precision: Vec<Vec<f64>>,
+}
+
+/// Invert a symmetric positive-definite matrix by Gauss-Jordan elimination
+/// with partial pivoting; `None` if it is (numerically) singular
+fn invert(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut a: Vec<Vec<f64>> = matrix.to_vec();
+    let mut inverse: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = a[col][col];
+        for j in 0..n {
+            a[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = a[row][col];
+                for j in 0..n {
+                    a[row][j] -= factor * a[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+impl Templates {
+    /// Build templates from traces of a device with known whitening key
+    /// `round_key0`, using samples `points` of every trace
+    ///
+    /// Panics if some intermediate value never occurs in the profiling
+    /// traces, or if the samples at `points` are linearly dependent (e.g.
+    /// noise-free traces).
+    pub fn profile(traces: &[LeakyEncryption], round_key0: u16, nibble_idx: usize, points: &[usize]) -> Self {
+        let dim = points.len();
+        let value_of = |trace: &LeakyEncryption| SBOX[nibble(trace.plaintext ^ round_key0, nibble_idx) as usize] as usize;
+        let point_samples = |trace: &LeakyEncryption| points.iter().map(|&i| trace.samples[i]).collect::<Vec<f64>>();
+
+        let mut counts = [0usize; 16];
+        let mut means = vec![vec![0.0; dim]; 16];
+        for trace in traces {
+            let value = value_of(trace);
+            counts[value] += 1;
+            for (mean, x) in means[value].iter_mut().zip(point_samples(trace)) {
+                *mean += x;
+            }
+        }
+        for (mean, &count) in means.iter_mut().zip(&counts) {
+            assert!(count > 0, "every intermediate value needs profiling traces");
+            mean.iter_mut().for_each(|m| *m /= count as f64);
+        }
+
+        let mut covariance = vec![vec![0.0; dim]; dim];
+        for trace in traces {
+            let mean = &means[value_of(trace)];
+            let centred: Vec<f64> = point_samples(trace).iter().zip(mean).map(|(x, m)| x - m).collect();
+            for i in 0..dim {
+                for j in 0..dim {
+                    covariance[i][j] += centred[i] * centred[j];
+                }
+            }
+        }
+        let degrees = (traces.len() - 16).max(1) as f64;
+        covariance.iter_mut().flatten().for_each(|c| *c /= degrees);
+        let precision = invert(&covariance).expect("singular covariance: add noise or choose other points");
+
+        Templates { nibble_idx, points: points.to_vec(), means, precision }
+    }
+
+    pub fn nibble_idx(&self) -> usize {
+        self.nibble_idx
+    }
+
+    pub fn points(&self) -> &[usize] {
+        &self.points
+    }
+
+    /// Log-likelihood of one trace under intermediate value `value`, up to a
+    /// constant shared by all values
+    fn log_likelihood(&self, trace: &LeakyEncryption, value: usize) -> f64 {
+        let centred: Vec<f64> = self.points.iter().zip(&self.means[value]).map(|(&i, m)| trace.samples[i] - m).collect();
+        let mut distance = 0.0;
+        for (row, ci) in self.precision.iter().zip(&centred) {
+            for (p, cj) in row.iter().zip(&centred) {
+                distance += ci * p * cj;
+            }
+        }
+        -0.5 * distance
+    }
+
+    /// Summed log-likelihood of the target traces for every key candidate
+    pub fn log_likelihoods(&self, traces: &[LeakyEncryption]) -> [f64; 16] {
+        let mut scores = [0.0; 16];
+        for trace in traces {
+            let p = nibble(trace.plaintext, self.nibble_idx) as usize;
+            // The likelihood depends only on the value, so compute it once per value
+            let per_value: [f64; 16] = std::array::from_fn(|value| self.log_likelihood(trace, value));
+            for (k, score) in scores.iter_mut().enumerate() {
+                *score += per_value[SBOX[p ^ k] as usize];
+            }
+        }
+        scores
+    }
+
+    /// Candidates for the whitening-key nibble by likelihood, best first
+    pub fn ranking(&self, traces: &[LeakyEncryption]) -> [(u8, f32); 16] {
+        let scores = self.log_likelihoods(traces);
+        let mut ranking: [(u8, f32); 16] = std::array::from_fn(|candidate| (candidate as u8, scores[candidate] as f32));
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranking
+    }
+
+    pub fn attack(&self, traces: &[LeakyEncryption]) -> u8 {
+        self.ranking(traces)[0].0
+    }
+}
@@ -0,0 +1,259 @@
+// Property Testing Kit
+// --------------------
+//
+// Generators for random inputs, a small property runner and checkers for
+// the invariants every implementation in the crate keeps, public so that
+// downstream ciphers built on `BlockCipher` or `LeakyCipher`, or new S-boxes
+// and P-boxes, can be held to the same properties in their own tests:
+//
+//   for_all(256, 1, testkit::round_keys, |&keys| {
+//       check_block_cipher(&MyCipher::new(keys), 16, &mut SplitMix64::new(7))
+//   })
+//
+// Case i of a run draws from `SplitMix64::new(seed).split(i)`, so a
+// counterexample is reproduced from its seed and case number alone. Inputs
+// are not shrunk. Checkers return the first violation as a message.
+
+use std::fmt::Debug;
+
+use crate::cipher::BlockCipher;
+use crate::leakage::LeakyCipher;
+use crate::rng::{RandomSource, SplitMix64};
+use crate::sbox::Sbox;
+use crate::spn::random_master_key;
+
+/// Uniformly random 80-bit master key
+pub fn master_key(rng: &mut SplitMix64) -> u128 {
+    random_master_key(rng)
+}
+
+/// Five independent uniformly random round keys
+pub fn round_keys(rng: &mut SplitMix64) -> [u16; 5] {
+    std::array::from_fn(|_| rng.next_u16())
+}
+
+/// Random bijective 4-bit S-box
+pub fn sbox(rng: &mut SplitMix64) -> Sbox {
+    Sbox::random(rng)
+}
+
+/// Up to `max_blocks` random blocks of `block_size` bytes, as one buffer
+pub fn blocks(rng: &mut SplitMix64, block_size: usize, max_blocks: usize) -> Vec<u8> {
+    let mut data = vec![0u8; block_size * rng.below(max_blocks as u64 + 1) as usize];
+    rng.fill_bytes(&mut data);
+    data
+}
+
+/// A failing case: where it came from, what was generated and what broke
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Counterexample<T> {
+    pub seed: u64,
+    pub case: usize,
+    pub input: T,
+    pub message: String,
+}
+
+/// Run `property` on `cases` generated inputs and stop at the first failure
+pub fn for_all<T: Debug>(
+    cases: usize,
+    seed: u64,
+    mut generate: impl FnMut(&mut SplitMix64) -> T,
+    mut property: impl FnMut(&T) -> Result<(), String>,
+) -> Result<(), Counterexample<T>> {
+    let root = SplitMix64::new(seed);
+    for case in 0..cases {
+        let input = generate(&mut root.split(case as u64));
+        if let Err(message) = property(&input) {
+            return Err(Counterexample { seed, case, input, message });
+        }
+    }
+    Ok(())
+}
+
+/// The input `for_all` generated for `case` of a run with `seed`
+pub fn replay<T>(seed: u64, case: usize, mut generate: impl FnMut(&mut SplitMix64) -> T) -> T {
+    generate(&mut SplitMix64::new(seed).split(case as u64))
+}
+
+/// Decryption inverts encryption block by block, and the batch methods
+/// agree with the single-block ones, on `cases` random buffers
+pub fn check_block_cipher<C: BlockCipher>(cipher: &C, cases: usize, rng: &mut impl RandomSource) -> Result<(), String> {
+    for _ in 0..cases {
+        let mut data = vec![0u8; C::BLOCK_SIZE * (1 + rng.below(200) as usize)];
+        rng.fill_bytes(&mut data);
+        let mut single = data.clone();
+        for block in single.chunks_exact_mut(C::BLOCK_SIZE) {
+            cipher.encrypt_block(block);
+        }
+        let mut batch = data.clone();
+        cipher.encrypt_blocks(&mut batch);
+        if batch != single {
+            return Err(format!("encrypt_blocks disagrees with encrypt_block on {} blocks", data.len() / C::BLOCK_SIZE));
+        }
+        cipher.decrypt_blocks(&mut batch);
+        for block in single.chunks_exact_mut(C::BLOCK_SIZE) {
+            cipher.decrypt_block(block);
+        }
+        if single != data {
+            return Err(format!("decrypt_block does not invert encrypt_block for {:02X?}", &data[..C::BLOCK_SIZE]));
+        }
+        if batch != data {
+            return Err("decrypt_blocks does not invert encrypt_blocks".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// `encrypt_observed` returns the same ciphertext as `reference`; the states
+/// are not checked, masked implementations record masked values
+pub fn check_leaky_cipher(cipher: &impl LeakyCipher, reference: impl Fn(u16) -> u16, cases: usize, rng: &mut impl RandomSource) -> Result<(), String> {
+    for _ in 0..cases {
+        let plaintext = rng.next_u16();
+        let (ciphertext, _) = cipher.encrypt_observed(plaintext);
+        if ciphertext != reference(plaintext) {
+            return Err(format!("plaintext {:04X}: ciphertext {:04X}, reference {:04X}", plaintext, ciphertext, reference(plaintext)));
+        }
+    }
+    Ok(())
+}
+
+/// `f` is a permutation of all 65,536 blocks and `inverse` undoes it
+pub fn check_permutation(f: impl Fn(u16) -> u16, inverse: impl Fn(u16) -> u16) -> Result<(), String> {
+    let mut seen = vec![false; 1 << 16];
+    for x in 0..=u16::MAX {
+        let y = f(x);
+        if std::mem::replace(&mut seen[y as usize], true) {
+            return Err(format!("{:04X} is hit twice", y));
+        }
+        if inverse(y) != x {
+            return Err(format!("inverse of {:04X} is {:04X}, expected {:04X}", y, inverse(y), x));
+        }
+    }
+    Ok(())
+}
+
+/// `table` is a permutation of 0..16
+pub fn check_sbox_bijective(table: &[u8; 16]) -> Result<(), String> {
+    let mut seen = 0u16;
+    for (x, &y) in table.iter().enumerate() {
+        if y >= 16 {
+            return Err(format!("S({:X}) = {} is not a nibble", x, y));
+        }
+        if seen & (1 << y) != 0 {
+            return Err(format!("{:X} appears twice in the table", y));
+        }
+        seen |= 1 << y;
+    }
+    Ok(())
+}
+
+/// `pbox` is a bit permutation: linear, and every input bit lands on its
+/// own output bit
+pub fn check_bit_permutation(pbox: impl Fn(u16) -> u16, cases: usize, rng: &mut impl RandomSource) -> Result<(), String> {
+    let mut targets = 0u16;
+    for bit in 0..16 {
+        let image = pbox(1 << bit);
+        if image.count_ones() != 1 || targets & image != 0 {
+            return Err(format!("bit {} maps to {:016b}", bit, image));
+        }
+        targets |= image;
+    }
+    for _ in 0..cases {
+        let (x, y) = (rng.next_u16(), rng.next_u16());
+        if pbox(x ^ y) != pbox(x) ^ pbox(y) {
+            return Err(format!("not linear on {:04X} and {:04X}", x, y));
+        }
+    }
+    Ok(())
+}
+
+/// LAT properties of a bijective S-box: entry [0][0] is 8, the rest of row
+/// and column 0 is 0, every entry is even, and each nonzero row satisfies
+/// Parseval, sum of squares 64
+pub fn check_lat(sbox: &Sbox) -> Result<(), String> {
+    let lat = sbox.lat();
+    for (a, row) in lat.iter().enumerate() {
+        for (b, &entry) in row.iter().enumerate() {
+            let expected_trivial = if a == 0 && b == 0 { Some(8) } else if a == 0 || b == 0 { Some(0) } else { None };
+            if expected_trivial.is_some_and(|expected| entry != expected) || entry % 2 != 0 {
+                return Err(format!("LAT[{:X}][{:X}] = {}", a, b, entry));
+            }
+        }
+        let squares: i32 = row.iter().map(|&e| e as i32 * e as i32).sum();
+        if a != 0 && squares != 64 {
+            return Err(format!("LAT row {:X} has sum of squares {}, not 64", a, squares));
+        }
+    }
+    Ok(())
+}
+
+/// DDT properties of a bijective S-box: every row sums to 16, entries are
+/// even, and row 0 is 16 at 0 and 0 elsewhere
+pub fn check_ddt(sbox: &Sbox) -> Result<(), String> {
+    for (a, row) in sbox.ddt().iter().enumerate() {
+        let sum: u32 = row.iter().map(|&e| e as u32).sum();
+        if sum != 16 {
+            return Err(format!("DDT row {:X} sums to {}", a, sum));
+        }
+        for (b, &entry) in row.iter().enumerate() {
+            let trivial_wrong = a == 0 && entry != if b == 0 { 16 } else { 0 };
+            if trivial_wrong || entry % 2 != 0 || (a != 0 && b == 0 && entry != 0) {
+                return Err(format!("DDT[{:X}][{:X}] = {}", a, b, entry));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spn::{pbox, Spn};
+
+    #[test]
+    fn the_crate_cipher_passes() {
+        let result = for_all(16, 1, master_key, |&key| {
+            let cipher = Spn::new(key);
+            check_block_cipher(&cipher, 8, &mut SplitMix64::new(2))?;
+            check_leaky_cipher(&cipher, |p| cipher.encrypt(p), 64, &mut SplitMix64::new(3))
+        });
+        assert_eq!(result, Ok(()));
+        let cipher = Spn::new(0x1234_5678_90AB_CDEF_1234);
+        assert_eq!(check_permutation(|p| cipher.encrypt(p), |c| cipher.decrypt(c)), Ok(()));
+    }
+
+    #[test]
+    fn the_present_sbox_and_pbox_pass() {
+        let present = Sbox::present();
+        assert_eq!(check_sbox_bijective(present.table()), Ok(()));
+        assert_eq!(check_lat(present), Ok(()));
+        assert_eq!(check_ddt(present), Ok(()));
+        assert_eq!(check_bit_permutation(pbox, 256, &mut SplitMix64::new(4)), Ok(()));
+        assert_eq!(check_permutation(pbox, pbox), Ok(()));
+    }
+
+    #[test]
+    fn broken_components_are_rejected() {
+        let mut repeated = *Sbox::present().table();
+        repeated[1] = repeated[0];
+        assert!(check_sbox_bijective(&repeated).is_err());
+        let mut too_wide = *Sbox::present().table();
+        too_wide[3] = 16;
+        assert!(check_sbox_bijective(&too_wide).is_err());
+
+        // Bit 15 lands on bit 0, which bit 0 already occupies
+        let merging = |x: u16| (x & 0x7FFF) | (x >> 15);
+        assert!(check_bit_permutation(merging, 256, &mut SplitMix64::new(5)).is_err());
+        assert!(check_permutation(merging, merging).is_err());
+        let affine = |x: u16| pbox(x) ^ 1;
+        assert!(check_bit_permutation(affine, 256, &mut SplitMix64::new(6)).is_err());
+    }
+
+    #[test]
+    fn counterexamples_replay() {
+        let counterexample = for_all(100, 7, master_key, |&key| if key & 1 == 0 { Ok(()) } else { Err("odd".to_string()) })
+            .unwrap_err();
+        assert_eq!(counterexample.message, "odd");
+        assert_eq!(replay(counterexample.seed, counterexample.case, master_key), counterexample.input);
+    }
+}
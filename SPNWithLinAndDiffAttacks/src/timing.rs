@@ -0,0 +1,147 @@
+// Cache-Timing Leakage
+// --------------------
+//
+// A table-driven S-box reads SBOX[x ^ k] from memory. With a cold cache the
+// first read of each cache line is slow and later reads of the same line are
+// fast, so the total time depends on which lines the secret-dependent indices
+// fall into. In the first round, nibbles a and b hit the same line exactly
+// when (p_a ^ k_a) and (p_b ^ k_b) agree above the line offset, i.e. when the
+// high bits of p_a ^ p_b equal those of k_a ^ k_b; encryptions where that
+// happens are faster on average. Averaging the time per value of the
+// plaintext difference exposes the key difference. The constant-time
+// backend does no lookups and takes the same time for every input.
+
+use crate::rng::{RandomSource, SplitMix64};
+use crate::spn::{nibble, pbox, sbox_layer, Backend, Spn};
+
+/// Simulated cache: the 16-entry S-box spans `16 / entries_per_line` lines,
+/// all evicted before every encryption
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CacheModel {
+    /// Set through `new`, which checks it splits the table evenly
+    entries_per_line: usize,
+    pub hit_cycles: f64,
+    pub miss_cycles: f64,
+    /// Standard deviation of Gaussian measurement noise, in cycles
+    pub noise_sigma: f64,
+}
+
+impl Default for CacheModel {
+    /// Four lines of four entries, a 10x miss penalty and a little jitter
+    fn default() -> Self {
+        CacheModel::new(4, 3.0, 30.0, 5.0)
+    }
+}
+
+impl CacheModel {
+    /// `entries_per_line` must be 1, 2, 4, 8 or 16
+    pub fn new(entries_per_line: usize, hit_cycles: f64, miss_cycles: f64, noise_sigma: f64) -> Self {
+        assert!(
+            entries_per_line != 0 && 16 % entries_per_line == 0,
+            "cache lines must split the table evenly"
+        );
+        CacheModel { entries_per_line, hit_cycles, miss_cycles, noise_sigma }
+    }
+
+    pub fn entries_per_line(&self) -> usize {
+        self.entries_per_line
+    }
+
+    pub fn lines(&self) -> usize {
+        16 / self.entries_per_line
+    }
+
+    /// Noise-free cycles for the S-box lookups of one table-driven encryption
+    pub fn lookup_cycles(&self, plaintext: u16, round_keys: &[u16]) -> f64 {
+        let mut cached = 0u32; // bit i set once line i is loaded
+        let mut cycles = 0.0;
+        let mut lookups = |state: u16| {
+            for idx in 0..4 {
+                let line = nibble(state, idx) as usize / self.entries_per_line;
+                if cached & (1 << line) == 0 {
+                    cached |= 1 << line;
+                    cycles += self.miss_cycles;
+                } else {
+                    cycles += self.hit_cycles;
+                }
+            }
+        };
+        let mut state = plaintext ^ round_keys[0];
+        for &round_key in &round_keys[1..4] {
+            lookups(state);
+            state = pbox(sbox_layer(state)) ^ round_key;
+        }
+        lookups(state);
+        cycles
+    }
+}
+
+/// Encryption oracle that reports how long each encryption took
+#[derive(Clone, Debug)]
+pub struct TimingOracle {
+    cipher: Spn,
+    model: CacheModel,
+    rng: SplitMix64,
+}
+
+impl TimingOracle {
+    pub fn new(cipher: Spn, model: CacheModel, seed: u64) -> Self {
+        TimingOracle { cipher, model, rng: SplitMix64::new(seed) }
+    }
+
+    /// Ciphertext and measured cycles; the constant-time backend always
+    /// costs the same as 16 cache hits
+    pub fn encrypt(&mut self, plaintext: u16) -> (u16, f64) {
+        let cycles = match self.cipher.backend() {
+            Backend::ConstantTime => 16.0 * self.model.hit_cycles,
+            Backend::Scalar | Backend::Lut => self.model.lookup_cycles(plaintext, self.cipher.round_keys()),
+        };
+        let noise = self.model.noise_sigma * self.rng.next_gaussian();
+        (self.cipher.encrypt(plaintext), cycles + noise)
+    }
+
+    /// Time every plaintext, returning (plaintext, cycles)
+    pub fn collect(&mut self, plaintexts: impl IntoIterator<Item = u16>) -> Vec<(u16, f64)> {
+        plaintexts.into_iter().map(|plaintext| (plaintext, self.encrypt(plaintext).1)).collect()
+    }
+}
+
+/// First-round collision attack on the line of k_a ^ k_b for nibbles `a` and
+/// `b` of the whitening key
+///
+/// Returns every candidate for (k_a ^ k_b) / entries_per_line with the mean
+/// time of the encryptions it predicts to collide, fastest (most likely)
+/// first. A flat profile, as from the constant-time backend, means there is
+/// nothing to learn.
+pub fn collision_timing_attack(timings: &[(u16, f64)], model: &CacheModel, a: usize, b: usize) -> Vec<(u8, f32)> {
+    let lines = model.lines();
+    let mut total = vec![0.0; lines];
+    let mut count = vec![0usize; lines];
+    for &(plaintext, cycles) in timings {
+        let line = (nibble(plaintext, a) ^ nibble(plaintext, b)) as usize / model.entries_per_line;
+        total[line] += cycles;
+        count[line] += 1;
+    }
+    let mut ranking: Vec<(u8, f32)> = (0..lines)
+        .map(|line| (line as u8, (total[line] / count[line].max(1) as f64) as f32))
+        .collect();
+    ranking.sort_by(|x, y| x.1.total_cmp(&y.1));
+    ranking
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "cache lines must split the table evenly")]
+    fn rejects_empty_cache_lines() {
+        CacheModel::new(0, 3.0, 30.0, 5.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "cache lines must split the table evenly")]
+    fn rejects_uneven_cache_lines() {
+        CacheModel::new(3, 3.0, 30.0, 5.0);
+    }
+}
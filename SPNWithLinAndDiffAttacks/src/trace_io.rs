@@ -0,0 +1,172 @@
+// Trace Set Files
+// ---------------
+//
+// Simulated traces can be saved and loaded again as CSV (one trace per row:
+// plaintext, ciphertext, then the samples) or as NumPy .npy arrays, which
+// Python tooling loads directly with `numpy.load`. The .npy export writes
+// three files next to each other: `<stem>_traces.npy` (float64, one row of
+// `TRACE_LEN` samples per trace), `<stem>_plaintexts.npy` and
+// `<stem>_ciphertexts.npy` (uint16). Only what this module writes is read
+// back: format version 1.0, little-endian, C order.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::leakage::{LeakyEncryption, TRACE_LEN};
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Traces as CSV text with a header row
+pub fn to_csv(traces: &[LeakyEncryption]) -> String {
+    let mut csv = String::from("plaintext,ciphertext");
+    for i in 0..TRACE_LEN {
+        write!(csv, ",s{}", i).unwrap();
+    }
+    csv.push('\n');
+    for trace in traces {
+        write!(csv, "{},{}", trace.plaintext, trace.ciphertext).unwrap();
+        for sample in &trace.samples {
+            write!(csv, ",{}", sample).unwrap();
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Parse what `to_csv` writes
+pub fn parse_csv(text: &str) -> io::Result<Vec<LeakyEncryption>> {
+    let mut lines = text.lines();
+    lines.next().ok_or_else(|| invalid("empty trace CSV"))?;
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 2 + TRACE_LEN {
+                return Err(invalid("wrong number of columns in trace CSV"));
+            }
+            let word = |field: &str| field.parse::<u16>().map_err(|_| invalid("bad plaintext or ciphertext in trace CSV"));
+            let mut samples = [0.0; TRACE_LEN];
+            for (sample, field) in samples.iter_mut().zip(&fields[2..]) {
+                *sample = field.parse().map_err(|_| invalid("bad sample in trace CSV"))?;
+            }
+            Ok(LeakyEncryption { plaintext: word(fields[0])?, ciphertext: word(fields[1])?, samples })
+        })
+        .collect()
+}
+
+pub fn write_csv(path: &Path, traces: &[LeakyEncryption]) -> io::Result<()> {
+    fs::write(path, to_csv(traces))
+}
+
+pub fn read_csv(path: &Path) -> io::Result<Vec<LeakyEncryption>> {
+    parse_csv(&fs::read_to_string(path)?)
+}
+
+/// A version 1.0 .npy file: magic, header length, then a Python dict
+/// literal padded with spaces to a multiple of 64 bytes, then the data
+fn npy_bytes(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        dims => format!("({})", dims.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// Check the header of a .npy file and return its shape and data
+fn parse_npy<'a>(bytes: &'a [u8], descr: &str) -> io::Result<(Vec<usize>, &'a [u8])> {
+    if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" || bytes[6] != 1 {
+        return Err(invalid("not a version 1 .npy file"));
+    }
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = bytes
+        .get(10..10 + header_len)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| invalid("truncated .npy header"))?;
+    if !header.contains(&format!("'descr': '{}'", descr)) {
+        return Err(invalid("unexpected .npy element type"));
+    }
+    if !header.contains("'fortran_order': False") {
+        return Err(invalid("only C-order .npy arrays are supported"));
+    }
+    let shape = header
+        .split("'shape': (")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .ok_or_else(|| invalid("missing .npy shape"))?;
+    let shape = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse().map_err(|_| invalid("bad .npy shape")))
+        .collect::<io::Result<Vec<usize>>>()?;
+    Ok((shape, &bytes[10 + header_len..]))
+}
+
+fn npy_paths(stem: &Path) -> [PathBuf; 3] {
+    let with_suffix = |suffix: &str| {
+        let mut name = stem.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        stem.with_file_name(name)
+    };
+    [with_suffix("_traces.npy"), with_suffix("_plaintexts.npy"), with_suffix("_ciphertexts.npy")]
+}
+
+/// Write the three .npy files for `stem` and return their paths
+pub fn write_npy(stem: &Path, traces: &[LeakyEncryption]) -> io::Result<Vec<PathBuf>> {
+    let [samples_path, plaintexts_path, ciphertexts_path] = npy_paths(stem);
+    let samples: Vec<u8> = traces.iter().flat_map(|t| t.samples.iter().flat_map(|s| s.to_le_bytes())).collect();
+    let plaintexts: Vec<u8> = traces.iter().flat_map(|t| t.plaintext.to_le_bytes()).collect();
+    let ciphertexts: Vec<u8> = traces.iter().flat_map(|t| t.ciphertext.to_le_bytes()).collect();
+    fs::write(&samples_path, npy_bytes("<f8", &[traces.len(), TRACE_LEN], &samples))?;
+    fs::write(&plaintexts_path, npy_bytes("<u2", &[traces.len()], &plaintexts))?;
+    fs::write(&ciphertexts_path, npy_bytes("<u2", &[traces.len()], &ciphertexts))?;
+    Ok(vec![samples_path, plaintexts_path, ciphertexts_path])
+}
+
+/// Read the three .npy files written by `write_npy` for `stem`
+pub fn read_npy(stem: &Path) -> io::Result<Vec<LeakyEncryption>> {
+    let [samples_path, plaintexts_path, ciphertexts_path] = npy_paths(stem);
+    let samples_file = fs::read(samples_path)?;
+    let plaintexts_file = fs::read(plaintexts_path)?;
+    let ciphertexts_file = fs::read(ciphertexts_path)?;
+
+    let (shape, samples) = parse_npy(&samples_file, "<f8")?;
+    let count = match shape[..] {
+        [count, TRACE_LEN] if samples.len() == count * TRACE_LEN * 8 => count,
+        _ => return Err(invalid("trace array has the wrong shape")),
+    };
+    let words = |bytes: &[u8]| -> io::Result<Vec<u16>> {
+        let (shape, data) = parse_npy(bytes, "<u2")?;
+        if shape != [count] || data.len() != count * 2 {
+            return Err(invalid("plaintext/ciphertext array does not match the traces"));
+        }
+        Ok(data.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]])).collect())
+    };
+    let plaintexts = words(&plaintexts_file)?;
+    let ciphertexts = words(&ciphertexts_file)?;
+
+    Ok(samples
+        .chunks_exact(TRACE_LEN * 8)
+        .zip(plaintexts.into_iter().zip(ciphertexts))
+        .map(|(row, (plaintext, ciphertext))| {
+            let mut samples = [0.0; TRACE_LEN];
+            for (sample, bytes) in samples.iter_mut().zip(row.chunks_exact(8)) {
+                *sample = f64::from_le_bytes(bytes.try_into().unwrap());
+            }
+            LeakyEncryption { plaintext, ciphertext, samples }
+        })
+        .collect())
+}
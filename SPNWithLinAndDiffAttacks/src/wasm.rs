@@ -0,0 +1,249 @@
+// WebAssembly Interface
+// ---------------------
+//
+// Plain exported functions for browser demos, kept free of wasm-bindgen so
+// the crate stays dependency-free; `wasm/spn.js` wraps them in a JavaScript
+// class. Build the module with
+//
+//   cargo rustc --lib --release --features wasm --target wasm32-unknown-unknown --crate-type cdylib
+//
+// Everything crossing the boundary is a number or a pointer into the module's
+// memory: ciphers are opaque handles, arrays are allocated with `spn_alloc`
+// and released with `spn_dealloc`. Long attacks report progress by calling
+// the imported `env.spn_progress(done, total)` after every chunk of pairs.
+
+use std::alloc::{self, Layout};
+
+use crate::differential::IncrementalDifferentialAttack;
+use crate::linear::IncrementalLinearAttack;
+use crate::pairs::{chosen_plaintext_pairs, known_plaintext_pairs};
+use crate::sbox::Sbox;
+use crate::spn::Spn;
+
+/// Pairs fed between two progress reports
+const PROGRESS_CHUNK: usize = 4096;
+
+/// Returned by the attacks instead of a key nibble when `nibble_idx` is not
+/// 0..=3
+pub const SPN_BAD_NIBBLE: u32 = u32::MAX;
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+unsafe extern "C" {
+    fn spn_progress(done: u32, total: u32);
+}
+
+fn report_progress(done: usize, total: usize) {
+    #[cfg(target_arch = "wasm32")]
+    // SAFETY: provided by the JavaScript host, takes two plain numbers
+    unsafe {
+        spn_progress(done as u32, total as u32)
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = (done, total);
+}
+
+/// Host buffers are aligned for any element type up to 64 bits
+fn buffer_layout(len: usize) -> Layout {
+    Layout::from_size_align(len.max(1), 8).expect("buffer too large")
+}
+
+/// Reserve `len` zeroed bytes of module memory for the host to fill
+#[unsafe(no_mangle)]
+pub extern "C" fn spn_alloc(len: usize) -> *mut u8 {
+    // SAFETY: the layout has a nonzero size
+    let ptr = unsafe { alloc::alloc_zeroed(buffer_layout(len)) };
+    if ptr.is_null() {
+        alloc::handle_alloc_error(buffer_layout(len));
+    }
+    ptr
+}
+
+/// Release memory from `spn_alloc`
+///
+/// # Safety
+/// `ptr` and `len` must come from one `spn_alloc` call that was not yet
+/// released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_dealloc(ptr: *mut u8, len: usize) {
+    // SAFETY: the caller passes back exactly what `spn_alloc` handed out
+    unsafe { alloc::dealloc(ptr, buffer_layout(len)) };
+}
+
+/// A cipher for the 80-bit master key `hi:mid:lo` (16, 32 and 32 bits)
+#[unsafe(no_mangle)]
+pub extern "C" fn spn_new(hi: u32, mid: u32, lo: u32) -> *mut Spn {
+    let master_key = ((hi as u128 & 0xFFFF) << 64) | ((mid as u128) << 32) | lo as u128;
+    Box::into_raw(Box::new(Spn::new(master_key)))
+}
+
+/// # Safety
+/// `cipher` must come from `spn_new` and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_free(cipher: *mut Spn) {
+    // SAFETY: the handle was created by `Box::into_raw` in `spn_new`
+    drop(unsafe { Box::from_raw(cipher) });
+}
+
+/// # Safety
+/// `cipher` must be a live handle from `spn_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_encrypt(cipher: *const Spn, block: u32) -> u32 {
+    // SAFETY: guaranteed by the caller
+    unsafe { &*cipher }.encrypt(block as u16) as u32
+}
+
+/// # Safety
+/// `cipher` must be a live handle from `spn_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_decrypt(cipher: *const Spn, block: u32) -> u32 {
+    // SAFETY: guaranteed by the caller
+    unsafe { &*cipher }.decrypt(block as u16) as u32
+}
+
+/// Encrypt `len` blocks at `blocks` in place
+///
+/// # Safety
+/// `cipher` must be a live handle and `blocks` must point to `len` writable
+/// 16-bit words.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_encrypt_blocks(cipher: *const Spn, blocks: *mut u16, len: usize) {
+    // SAFETY: guaranteed by the caller
+    let (cipher, blocks) = unsafe { (&*cipher, std::slice::from_raw_parts_mut(blocks, len)) };
+    cipher.encrypt_blocks(blocks);
+}
+
+/// Decrypt `len` blocks at `blocks` in place
+///
+/// # Safety
+/// As for `spn_encrypt_blocks`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_decrypt_blocks(cipher: *const Spn, blocks: *mut u16, len: usize) {
+    // SAFETY: guaranteed by the caller
+    let (cipher, blocks) = unsafe { (&*cipher, std::slice::from_raw_parts_mut(blocks, len)) };
+    cipher.decrypt_blocks(blocks);
+}
+
+/// Write the S-box LAT, row-major, as 256 signed bytes
+///
+/// # Safety
+/// `out` must point to 256 writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_lat(out: *mut i8) {
+    // SAFETY: guaranteed by the caller
+    let out = unsafe { std::slice::from_raw_parts_mut(out, 256) };
+    out.copy_from_slice(Sbox::present().lat().as_flattened());
+}
+
+/// Write the S-box DDT, row-major, as 256 bytes
+///
+/// # Safety
+/// `out` must point to 256 writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_ddt(out: *mut u8) {
+    // SAFETY: guaranteed by the caller
+    let out = unsafe { std::slice::from_raw_parts_mut(out, 256) };
+    out.copy_from_slice(Sbox::present().ddt().as_flattened());
+}
+
+/// Write a ranking, best first, as 16 candidates and 16 scores
+///
+/// # Safety
+/// `keys` and `scores` must each point to 16 writable elements.
+unsafe fn write_ranking(ranking: [(u8, f32); 16], keys: *mut u8, scores: *mut f32) {
+    for (i, (key, score)) in ranking.into_iter().enumerate() {
+        // SAFETY: guaranteed by the caller
+        unsafe {
+            *keys.add(i) = key;
+            *scores.add(i) = score;
+        }
+    }
+}
+
+/// Linear attack on `num_pairs` fresh known-plaintext pairs; writes the
+/// ranking to `keys`/`scores` and returns the best candidate, or
+/// `SPN_BAD_NIBBLE` without writing anything
+///
+/// # Safety
+/// `cipher` must be a live handle; `keys` and `scores` must each point to 16
+/// writable elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_linear_attack(
+    cipher: *const Spn,
+    num_pairs: usize,
+    alpha: u32,
+    beta: u32,
+    nibble_idx: usize,
+    seed: u32,
+    keys: *mut u8,
+    scores: *mut f32,
+) -> u32 {
+    if nibble_idx > 3 {
+        return SPN_BAD_NIBBLE;
+    }
+    // SAFETY: guaranteed by the caller
+    let cipher = unsafe { &*cipher };
+    let pairs = known_plaintext_pairs(cipher, num_pairs, seed as u64);
+    let mut attack = IncrementalLinearAttack::new(alpha as u16, beta as u16, nibble_idx);
+    for (i, chunk) in pairs.chunks(PROGRESS_CHUNK).enumerate() {
+        chunk.iter().for_each(|&pair| attack.feed(pair));
+        report_progress(i * PROGRESS_CHUNK + chunk.len(), num_pairs);
+    }
+    let ranking = attack.current_ranking();
+    // SAFETY: guaranteed by the caller
+    unsafe { write_ranking(ranking, keys, scores) };
+    ranking[0].0 as u32
+}
+
+/// Differential attack on `num_pairs` fresh chosen-plaintext pairs with input
+/// difference `delta_p`; output as for `spn_linear_attack`
+///
+/// # Safety
+/// As for `spn_linear_attack`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spn_differential_attack(
+    cipher: *const Spn,
+    num_pairs: usize,
+    delta_p: u32,
+    delta_u: u32,
+    nibble_idx: usize,
+    seed: u32,
+    keys: *mut u8,
+    scores: *mut f32,
+) -> u32 {
+    if nibble_idx > 3 {
+        return SPN_BAD_NIBBLE;
+    }
+    // SAFETY: guaranteed by the caller
+    let cipher = unsafe { &*cipher };
+    let pairs = chosen_plaintext_pairs(cipher, delta_p as u16, num_pairs, seed as u64);
+    let mut attack = IncrementalDifferentialAttack::new(delta_p as u16, delta_u as u16, nibble_idx);
+    for (i, chunk) in pairs.chunks(PROGRESS_CHUNK).enumerate() {
+        chunk.iter().for_each(|&pair| attack.feed(pair));
+        report_progress(i * PROGRESS_CHUNK + chunk.len(), num_pairs);
+    }
+    let ranking = attack.current_ranking();
+    // SAFETY: guaranteed by the caller
+    unsafe { write_ranking(ranking, keys, scores) };
+    ranking[0].0 as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attacks_reject_a_bad_nibble() {
+        let cipher = spn_new(0x1234, 0x5678_90AB, 0xCDEF_1234);
+        let (mut keys, mut scores) = ([0u8; 16], [0f32; 16]);
+        // SAFETY: a live handle and two 16-element buffers
+        unsafe {
+            let (keys, scores) = (keys.as_mut_ptr(), scores.as_mut_ptr());
+            assert_eq!(spn_linear_attack(cipher, 100, 0x0B00, 0x0404, 4, 1, keys, scores), SPN_BAD_NIBBLE);
+            assert_eq!(spn_differential_attack(cipher, 100, 0x0040, 0x0404, 4, 1, keys, scores), SPN_BAD_NIBBLE);
+            assert!(spn_linear_attack(cipher, 100, 0x0B00, 0x0404, 1, 1, keys, scores) < 16);
+            spn_free(cipher);
+        }
+        assert!(keys.iter().any(|&key| key != 0));
+    }
+}
@@ -0,0 +1,103 @@
+// JavaScript wrapper around the exports of src/wasm.rs
+//
+//   const spn = await loadSpn("spn_attacks.wasm", { onProgress: (done, total) => ... });
+//   const cipher = spn.cipher(0x1234_5678_90AB_CDEF_1234n);
+//   cipher.encrypt(0xBEEF);
+//   const { best, ranking } = cipher.linearAttack({ pairs: 20000, alpha: 0x0B00, beta: 0x0400, nibble: 2 });
+//   cipher.free();
+
+export async function loadSpn(source, { onProgress = () => {} } = {}) {
+  const imports = { env: { spn_progress: (done, total) => onProgress(done, total) } };
+  const bytes = typeof source === "string" ? await (await fetch(source)).arrayBuffer() : source;
+  const { instance } = await WebAssembly.instantiate(bytes, imports);
+  const wasm = instance.exports;
+
+  // Copy `len` elements of a typed array type out of a fresh buffer that
+  // `fill` writes through, then release the buffer
+  function withBuffer(ArrayType, len, fill) {
+    const byteLen = len * ArrayType.BYTES_PER_ELEMENT;
+    const ptr = wasm.spn_alloc(byteLen);
+    try {
+      fill(ptr);
+      return new ArrayType(wasm.memory.buffer, ptr, len).slice();
+    } finally {
+      wasm.spn_dealloc(ptr, byteLen);
+    }
+  }
+
+  const table = (ArrayType, exported) => {
+    const flat = withBuffer(ArrayType, 256, exported);
+    return Array.from({ length: 16 }, (_, row) => Array.from(flat.subarray(16 * row, 16 * row + 16)));
+  };
+
+  class Cipher {
+    // `masterKey` is an 80-bit BigInt
+    constructor(masterKey) {
+      const key = BigInt(masterKey);
+      this.handle = wasm.spn_new(Number((key >> 64n) & 0xFFFFn), Number((key >> 32n) & 0xFFFFFFFFn), Number(key & 0xFFFFFFFFn));
+    }
+
+    encrypt(block) {
+      return wasm.spn_encrypt(this.handle, block);
+    }
+
+    decrypt(block) {
+      return wasm.spn_decrypt(this.handle, block);
+    }
+
+    // Returns a new Uint16Array; the input is left untouched
+    encryptBlocks(blocks) {
+      return this.#blocks(blocks, wasm.spn_encrypt_blocks);
+    }
+
+    decryptBlocks(blocks) {
+      return this.#blocks(blocks, wasm.spn_decrypt_blocks);
+    }
+
+    linearAttack({ pairs, alpha, beta, nibble, seed = 1 }) {
+      return this.#attack((keys, scores) =>
+        wasm.spn_linear_attack(this.handle, pairs, alpha, beta, nibble, seed, keys, scores));
+    }
+
+    differentialAttack({ pairs, deltaP, deltaU, nibble, seed = 1 }) {
+      return this.#attack((keys, scores) =>
+        wasm.spn_differential_attack(this.handle, pairs, deltaP, deltaU, nibble, seed, keys, scores));
+    }
+
+    free() {
+      wasm.spn_free(this.handle);
+      this.handle = 0;
+    }
+
+    #blocks(blocks, exported) {
+      return withBuffer(Uint16Array, blocks.length, (ptr) => {
+        new Uint16Array(wasm.memory.buffer, ptr, blocks.length).set(blocks);
+        exported(this.handle, ptr, blocks.length);
+      });
+    }
+
+    // Ranking best first, as [{ key, score }]
+    #attack(run) {
+      const keysPtr = wasm.spn_alloc(16);
+      const scoresPtr = wasm.spn_alloc(64);
+      try {
+        const best = run(keysPtr, scoresPtr) >>> 0;
+        if (best === 0xFFFFFFFF) {
+          throw new RangeError("nibble must be 0, 1, 2 or 3");
+        }
+        const keys = new Uint8Array(wasm.memory.buffer, keysPtr, 16);
+        const scores = new Float32Array(wasm.memory.buffer, scoresPtr, 16);
+        return { best, ranking: Array.from(keys, (key, i) => ({ key, score: scores[i] })) };
+      } finally {
+        wasm.spn_dealloc(keysPtr, 16);
+        wasm.spn_dealloc(scoresPtr, 64);
+      }
+    }
+  }
+
+  return {
+    cipher: (masterKey) => new Cipher(masterKey),
+    lat: () => table(Int8Array, wasm.spn_lat),
+    ddt: () => table(Uint8Array, wasm.spn_ddt),
+  };
+}